@@ -1,12 +1,12 @@
+use async_trait::async_trait;
 use std::time::{SystemTime, UNIX_EPOCH};
 use serde_json::json;
 use uuid::Uuid;
 use crate::memory::project_memory::DesignDecision;
 use crate::orchestrator::context::AgentContext;
-use crate::orchestrator::protocol::AgentResponse;
+use crate::orchestrator::protocol::{AgentResponse, ErrorKind};
 use crate::orchestrator::registry::AgentHandler;
 use crate::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph};
-use crate::orchestrator::utils::write_json_to_project_file;
 
 pub struct ArchitectureAgent;
 
@@ -22,12 +22,17 @@ impl ArchitectureAgent {
             input_schema: "RequirementList".into(),
             output_schema: "ArchitecturePlan".into(),
             default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(300),
+            priority: 0,
+            preconditions: vec![],
+            allowed_tools: vec![],
         }
     }
 }
 
+#[async_trait]
 impl AgentHandler for ArchitectureAgent {
-    fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
+    async fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
         println!("[ArchitectureAgent] Generating architecture for task: {}", task.task_id);
 
         // Simulated LLM response
@@ -47,7 +52,9 @@ impl AgentHandler for ArchitectureAgent {
         let summary = architecture_plan["summary"].as_str().unwrap_or("Unknown summary");
         let rationale = architecture_plan["rationale"].as_str().unwrap_or("No rationale provided");
 
-        ctx.project.set_architecture(&architecture_plan.to_string());
+        if let Err(err) = ctx.project.set_architecture(&architecture_plan.to_string()) {
+            return AgentResponse::error(ErrorKind::Storage, &format!("Saving architecture failed: {}", err));
+        }
 
         let decision = DesignDecision{
             id: Uuid::new_v4().to_string(),
@@ -57,7 +64,9 @@ impl AgentHandler for ArchitectureAgent {
             timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs().to_string(),
         };
 
-        ctx.project.lock().unwrap().write_decisions(decision.clone());
+        if let Err(err) = ctx.project.write_decision(decision.clone()) {
+            eprintln!("[warn] Failed to save design decision: {}", err);
+        }
 
         let output = json!({
             "architecture": &serde_json::to_string(&architecture_plan).unwrap_or_default(),
@@ -65,8 +74,6 @@ impl AgentHandler for ArchitectureAgent {
             "rationale": decision.rationale
         });
 
-        write_json_to_project_file("architecture.json", &output).ok();
-
         AgentResponse::success(
             &output.to_string(),
             "ArchitectureAgent")
@@ -1,8 +1,10 @@
-use tauri::utils::acl::capability::CapabilityFile::Capability;
+use async_trait::async_trait;
 use crate::orchestrator::context::AgentContext;
-use crate::orchestrator::protocol::AgentResponse;
+use crate::orchestrator::protocol::{AgentResponse, ErrorKind};
 use crate::orchestrator::registry::AgentHandler;
-use crate::orchestrator::types::{AgentCard, AgentTask, ExecutionMode, SkillGraph};
+use crate::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph};
+use crate::prompt_assembler::PromptAssembler;
+use crate::tools::llm_tool::LLMTool;
 
 pub struct CodegenAgent;
 
@@ -18,12 +20,32 @@ impl CodegenAgent{
             input_schema: "ArchitecturePlan".into(),
             output_schema: "CodePatch".into(),
             default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(600),
+            priority: 0,
+            preconditions: vec![],
+            allowed_tools: vec![],
         }
     }
 }
-impl AgentHandler for CodegenAgent{
-    fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
-        println!("[CodegenAgent] Stub handling task: {}", task.task_id);
-        AgentResponse::success("Stub: code generated","CodegenAgent")
+#[async_trait]
+impl AgentHandler for CodegenAgent {
+    async fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
+        println!("[CodegenAgent] Generating code for task: {}", task.task_id);
+
+        // 1. Assemble prompt
+        let assembled = match PromptAssembler::assemble("codegen_agent", &task, &ctx.project, ctx.llm.as_ref()).await {
+            Ok(assembled) => assembled,
+            Err(err) => return AgentResponse::error(ErrorKind::Prompt, &format!("Prompt assembly failed: {}", err)),
+        };
+
+        // 2. Query LLM for the code patch
+        let llm_tool = LLMTool::new(ctx.llm.clone());
+        match llm_tool
+            .query_streaming(assembled.text, &task.task_id, "codegen", ctx.events.as_ref())
+            .await
+        {
+            Ok(text) => AgentResponse::success(&text, "CodegenAgent").with_trace(assembled.trace),
+            Err(err) => AgentResponse::error(ErrorKind::from(&err), &format!("LLM query failed: {}", err)),
+        }
     }
-}
\ No newline at end of file
+}
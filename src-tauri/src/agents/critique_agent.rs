@@ -1,11 +1,13 @@
+use async_trait::async_trait;
 use crate::orchestrator::context::AgentContext;
-use crate::orchestrator::protocol::AgentResponse;
+use crate::orchestrator::protocol::{AgentResponse, CriticList, ErrorKind};
 use crate::orchestrator::registry::AgentHandler;
 use crate::orchestrator::types::{
     AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph,
 };
-use winter_ui_lib::prompt_assembler::PromptAssembler;
-use winter_ui_lib::tools::llm_tool::LLMTool;
+use crate::prompt_assembler::PromptAssembler;
+use crate::llm::structured::schema_value;
+use crate::tools::llm_tool::LLMTool;
 
 pub struct CritiqueAgent;
 
@@ -21,49 +23,64 @@ impl CritiqueAgent {
             input_schema: "AgentOutput".into(),
             output_schema: "CriticList".into(),
             default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(300),
+            priority: 0,
+            preconditions: vec![],
+            allowed_tools: vec![],
         }
     }
-    pub fn new() -> Self {
-        CritiqueAgent
-    }
 }
 
+#[async_trait]
 impl AgentHandler for CritiqueAgent {
-    fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
+    async fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
         println!("[CritiqueAgent] Starting Reviewing...");
 
         // 1. Assemble prompt
-        let prompt_result = PromptAssembler::assemble("critique_agent", &task, &ctx.project);
+        let prompt_result = PromptAssembler::assemble_structured(
+            "critique_agent",
+            &task,
+            &ctx.project,
+            ctx.llm.as_ref(),
+            &schema_value::<CriticList>(),
+        )
+        .await;
         if let Err(err) = prompt_result {
-            return AgentResponse::error(&format!("Prompt assembly failed: {}", err), false);
+            return AgentResponse::error(ErrorKind::Prompt, &format!("Prompt assembly failed: {}", err));
         }
-        let prompt = prompt_result.unwrap();
+        let assembled = prompt_result.unwrap();
+        let prompt = assembled.text;
 
-        // 2. Query LLM
-        let llm_tool = LLMTool::new();
-        let query_result = llm_tool.query(prompt);
+        // 2. Query LLM for a CriticList
+        let llm_tool = LLMTool::new(ctx.llm.clone());
+        let query_result = llm_tool
+            .query_structured::<CriticList>(prompt, &task.task_id, "critique", ctx.events.as_ref())
+            .await;
 
         if let Err(err) = query_result {
-            return AgentResponse::error(&format!("LLM query failed: {}", err), false);
+            return AgentResponse::error(ErrorKind::from(&err), &format!("LLM query failed: {}", err));
         }
-        let llm_output = query_result.unwrap();
+        let critique = query_result.unwrap();
 
         println!("[CritiqueAgent] Received feedback from Model.");
 
         // 3. Save feedback to project feedback memory
+        let serialized = serde_json::to_string_pretty(&critique).unwrap_or_default();
         if let Err(err) = ctx
             .project
-            .save_to_project_memory("feedback_queue.json", &llm_output)
+            .save("feedback_queue.json", &serialized)
         {
             return AgentResponse::error(
+                ErrorKind::Storage,
                 &format!("Saving critique feedback failed: {}", err),
-                false,
             );
         }
 
-        AgentResponse::success(
-            "Critique generated and saved.".to_string().to_string(),
-            "critique_agent",
-        )
+        let mut response = AgentResponse::structured(&critique, "critique_agent");
+        if let AgentResponse::Success(output) = &mut response {
+            output.score = critique.score;
+            output.evaluation_notes = Some(critique.notes.clone());
+        }
+        response.with_trace(assembled.trace)
     }
 }
@@ -1,7 +1,10 @@
-use crate::agents::orchestrator::context::AgentContext;
-use crate::agents::orchestrator::protocol::AgentResponse;
-use crate::agents::orchestrator::registry::AgentHandler;
-use crate::agents::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph};
+use async_trait::async_trait;
+use crate::orchestrator::context::AgentContext;
+use crate::orchestrator::protocol::{AgentResponse, ErrorKind};
+use crate::orchestrator::registry::AgentHandler;
+use crate::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph, Precondition};
+use crate::prompt_assembler::PromptAssembler;
+use crate::tools::llm_tool::LLMTool;
 
 pub struct DeploymentAgent;
 
@@ -17,13 +20,33 @@ impl DeploymentAgent {
             input_schema: "DeploymentPlan".into(),
             output_schema: "DeploymentScript".into(),
             default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(900),
+            priority: 0,
+            preconditions: vec![Precondition::ProjectOpen],
+            allowed_tools: vec![],
         }
     }
 }
 
+#[async_trait]
 impl AgentHandler for DeploymentAgent {
-    fn handle_task(&self, task: AgentTask, _ctx: AgentContext) -> AgentResponse {
-        println!("[DeploymentAgent] Stub handling task: {}", task.task_id);
-        AgentResponse::success("Stub: deployment plan generated", "DeploymentAgent")
+    async fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
+        println!("[DeploymentAgent] Planning deployment for task: {}", task.task_id);
+
+        // 1. Assemble prompt
+        let assembled = match PromptAssembler::assemble("deployment_agent", &task, &ctx.project, ctx.llm.as_ref()).await {
+            Ok(assembled) => assembled,
+            Err(err) => return AgentResponse::error(ErrorKind::Prompt, &format!("Prompt assembly failed: {}", err)),
+        };
+
+        // 2. Query LLM for the deployment plan
+        let llm_tool = LLMTool::new(ctx.llm.clone());
+        match llm_tool
+            .query_streaming(assembled.text, &task.task_id, "deploy", ctx.events.as_ref())
+            .await
+        {
+            Ok(text) => AgentResponse::success(&text, "DeploymentAgent").with_trace(assembled.trace),
+            Err(err) => AgentResponse::error(ErrorKind::from(&err), &format!("LLM query failed: {}", err)),
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,12 +1,14 @@
-use crate::agents::orchestrator::context::AgentContext;
-use crate::agents::orchestrator::protocol::{AgentOutput, AgentResponse};
-use crate::agents::orchestrator::registry::{AgentHandler};
-use crate::agents::orchestrator::types::{AgentTask, AgentTaskContext, TaskStatus};
+use async_trait::async_trait;
+use crate::orchestrator::context::AgentContext;
+use crate::orchestrator::protocol::{AgentOutput, AgentResponse};
+use crate::orchestrator::registry::{AgentHandler};
+use crate::orchestrator::types::{AgentTask, AgentTaskContext, TaskStatus};
 
 pub struct HelloAgent;
 
+#[async_trait]
 impl AgentHandler for HelloAgent {
-    fn handle_task(&self, task: AgentTask, mut ctx: AgentContext) -> AgentResponse {
+    async fn handle_task(&self, task: AgentTask, _ctx: AgentContext) -> AgentResponse {
        let reply = format!("👋 Hello! You said: {}", task.payload);
 
         //Emit a follow up task
@@ -19,8 +21,15 @@ impl AgentHandler for HelloAgent {
                 goal_id: task.context.goal_id.clone(),
                 parent_task_id: Some(task.task_id.clone()),
                 retry_of:None,
+                revision_id: None,
+                execution_mode: task.context.execution_mode,
+                dependency_outputs: Default::default(),
+                timeout_secs: None,
+                attempt: 0,
+                skip_prerequisites: false,
             },
             status: TaskStatus::Pending,
+            depends_on: vec![],
         };
 
         AgentResponse::Success(AgentOutput{
@@ -33,6 +42,7 @@ impl AgentHandler for HelloAgent {
             produced_by: "HelloAgent".into(),
             planned_by: None,
             subtasks: Some(vec![follow_up]),
+            change_set: None,
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use crate::orchestrator::context::AgentContext;
+use crate::orchestrator::manifest::LoadedManifest;
+use crate::orchestrator::protocol::{output_schema, parse_output, AgentResponse, ErrorKind};
+use crate::orchestrator::registry::AgentHandler;
+use crate::orchestrator::types::AgentTask;
+use crate::prompt_assembler::PromptAssembler;
+use crate::tools::llm_tool::LLMTool;
+
+/// Generic LLM-driven agent defined by an `AgentManifest`: renders the manifest's
+/// prompt template for the task, fits it to the context window and returns the model's answer.
+pub struct ManifestAgent {
+    loaded: LoadedManifest,
+}
+
+impl ManifestAgent {
+    pub fn new(loaded: LoadedManifest) -> Self {
+        Self { loaded }
+    }
+
+    fn render_prompt(&self, task: &AgentTask) -> String {
+        let dependencies = task
+            .context
+            .dependency_outputs
+            .iter()
+            .map(|(task_id, output)| match output.content.as_str() {
+                Some(text) => format!("[{}]\n{}", task_id, text),
+                None => format!("[{}]\n{}", task_id, output.content),
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let template = &self.loaded.template;
+        let mut prompt = render_template(template, |name| match name {
+            "payload" => Some(task.payload.as_str()),
+            "task_type" => Some(task.task_type.as_str()),
+            "goal_id" => Some(task.context.goal_id.as_deref().unwrap_or("")),
+            "dependencies" => Some(dependencies.as_str()),
+            _ => None,
+        });
+
+        // Templates that do not place the payload get it appended
+        if !template.contains("{{payload}}") {
+            prompt.push_str("\n\n");
+            prompt.push_str(&task.payload);
+        }
+        prompt
+    }
+}
+
+/// Fills in `{{name}}` placeholders in one pass over the template, so placeholders inside the
+/// substituted values are left as they are. Unknown placeholders are kept verbatim.
+fn render_template<'a>(template: &str, value: impl Fn(&str) -> Option<&'a str>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let substituted = after
+            .find("}}")
+            .and_then(|end| value(&after[..end]).map(|text| (text, end)));
+        match substituted {
+            Some((text, end)) => {
+                rendered.push_str(text);
+                rest = &after[end + 2..];
+            }
+            None => {
+                rendered.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+#[async_trait]
+impl AgentHandler for ManifestAgent {
+    async fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
+        let id = &self.loaded.manifest.id;
+        println!("[ManifestAgent:{}] Handling task: {}", id, task.task_id);
+
+        // Manifests naming a known output type get schema-constrained, validated answers
+        let output_type = &self.loaded.manifest.output_schema;
+        let schema = output_schema(output_type);
+        let assembled = match PromptAssembler::assemble_template(
+            id,
+            &self.render_prompt(&task),
+            &task,
+            &ctx.project,
+            ctx.llm.as_ref(),
+            schema.as_ref(),
+        )
+        .await
+        {
+            Ok(assembled) => assembled,
+            Err(err) => return AgentResponse::error(ErrorKind::Prompt, &format!("Prompt assembly failed: {}", err)),
+        };
+
+        let llm_tool = LLMTool::new(ctx.llm.clone());
+        let response = match schema {
+            Some(schema) => llm_tool
+                .query_schema(
+                    assembled.text,
+                    schema,
+                    |text| parse_output(output_type, text),
+                    &task.task_id,
+                    id,
+                    ctx.events.as_ref(),
+                )
+                .await
+                .map(|value| AgentResponse::structured(&value, id)),
+            None => llm_tool
+                .query_streaming(assembled.text, &task.task_id, id, ctx.events.as_ref())
+                .await
+                .map(|text| AgentResponse::success(&text, id)),
+        };
+        match response {
+            Ok(response) => response.with_trace(assembled.trace),
+            Err(err) => AgentResponse::error(ErrorKind::from(&err), &format!("LLM query failed: {}", err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_inside_substituted_values_stay_as_they_are() {
+        let rendered = render_template("Goal {{goal_id}}: {{payload}} ({{unknown}})", |name| match name {
+            "payload" => Some("print {{goal_id}}"),
+            "goal_id" => Some("g1"),
+            _ => None,
+        });
+        assert_eq!(rendered, "Goal g1: print {{goal_id}} ({{unknown}})");
+    }
+}
@@ -14,3 +14,4 @@ pub(crate) mod scaffold_agent;
 pub(crate) mod deployment_agent;
 pub(crate) mod security_agent;
 pub(crate) mod repo_agent;
+pub(crate) mod manifest_agent;
@@ -1,18 +1,11 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use async_trait::async_trait;
 use serde_json::json;
-use sha2::digest::consts::U32;
-use uuid::Uuid;
-use crate::memory::planner_memory::PlannerMemoryEntry;
-use crate::orchestrator::protocol::{AgentResponse, AgentOutput, PlannerOutput};
+use crate::orchestrator::protocol::{AgentResponse, ErrorKind};
 use crate::orchestrator::types::{AgentCard, AgentTask, AgentTaskContext, Capability, ExecutionMode, SkillGraph, TaskStatus};
 use crate::orchestrator::context::AgentContext;
-use crate::orchestrator::feedback::{load_feedback_queue, load_plan_feedback_queue, PlanFeedbackAction};
-use crate::orchestrator::planning::meta_planner::{HeuristicMetaPlanner, MetaPlanner};
 use crate::orchestrator::registry::AgentHandler;
 
 
-const MAX_REVISIONS: i32 = 3;
-
 pub struct PlannerAgent;
 
 impl PlannerAgent {
@@ -27,34 +20,42 @@ impl PlannerAgent {
             input_schema: "Goal".to_string(),
             output_schema: "PlannerOutput".to_string(),
             default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(300),
+            priority: 0,
+            preconditions: vec![],
+            allowed_tools: vec![],
         }
     }
-    pub fn new() -> Self{
-        PlannerAgent
-    }
 }
 
+#[async_trait]
 impl AgentHandler for PlannerAgent{
-    fn handle_task(&self, task: AgentTask, _ctx: AgentContext) -> AgentResponse {
+    async fn handle_task(&self, task: AgentTask, _ctx: AgentContext) -> AgentResponse {
         println!("[PlannerAgent] Planning next steps...");
 
         // Step 1: Extract project goal
-        let goal = match task.payload.get("goal") {
+        let payload: serde_json::Value = serde_json::from_str(&task.payload).unwrap_or_default();
+        let goal = match payload.get("goal") {
             Some(g) => g.as_str().unwrap_or("").to_string(),
             None => {
-                return AgentResponse::error("PlannerAgent received task without a goal field.");
+                return AgentResponse::error(ErrorKind::InvalidInput, "PlannerAgent received task without a goal field.");
             }
         };
 
         // Step 2: Create a RequirementsAgent task
         let req_task = AgentTask {
             task_id: format!("requirements_{}", uuid::Uuid::new_v4()),
-            task_type: Capability::Requirements,
+            task_type: format!("{:?}", Capability::Requirements),
             payload: json!({
                 "goal": goal
-            }),
-            context: _ctx,
+            })
+            .to_string(),
+            context: AgentTaskContext {
+                parent_task_id: Some(task.task_id.clone()),
+                ..task.context.clone()
+            },
             status: TaskStatus::Pending,
+            depends_on: vec![],
         };
 
         // Step 3: Return new task as output
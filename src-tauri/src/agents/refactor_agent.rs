@@ -1,7 +1,8 @@
-use crate::agents::orchestrator::context::AgentContext;
-use crate::agents::orchestrator::protocol::AgentResponse;
-use crate::agents::orchestrator::registry::AgentHandler;
-use crate::agents::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph};
+use async_trait::async_trait;
+use crate::orchestrator::context::AgentContext;
+use crate::orchestrator::protocol::AgentResponse;
+use crate::orchestrator::registry::AgentHandler;
+use crate::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph, Precondition};
 
 pub struct RefactorAgent;
 
@@ -17,12 +18,17 @@ impl RefactorAgent {
             input_schema: "CodePatch".into(),
             output_schema: "RefactoredCode".into(),
             default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(600),
+            priority: 0,
+            preconditions: vec![Precondition::ProjectOpen],
+            allowed_tools: vec![],
         }
     }
 }
 
+#[async_trait]
 impl AgentHandler for RefactorAgent {
-    fn handle_task(&self, task: AgentTask, _ctx: AgentContext) -> AgentResponse {
+    async fn handle_task(&self, task: AgentTask, _ctx: AgentContext) -> AgentResponse {
         println!("[RefactorAgent] Stub handling task: {}", task.task_id);
         AgentResponse::success("Stub: code refactored", "RefactorAgent")
     }
@@ -1,16 +1,24 @@
+use async_trait::async_trait;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use uuid::Uuid;
 use crate::orchestrator::context::AgentContext;
-use crate::orchestrator::prompt_assembler::PromptAssembler;
-use crate::orchestrator::protocol::AgentResponse;
+use crate::prompt_assembler::PromptAssembler;
+use crate::orchestrator::protocol::{AgentResponse, ErrorKind, RequirementList};
+use crate::llm::structured::schema_value;
 use crate::tools::llm_tool::LLMTool;
-use crate::memory::project_memory::DesignDecisions;
+use crate::memory::project_memory::DesignDecision;
 use crate::orchestrator::registry::AgentHandler;
 use crate::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph};
 
 pub struct RequirementsAgent;
 
+impl Default for RequirementsAgent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RequirementsAgent{
     pub fn card()-> AgentCard{
         AgentCard{
@@ -23,6 +31,10 @@ impl RequirementsAgent{
             input_schema: "UserGoal".to_string(),
             output_schema: "RequirementList".to_string(),
             default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(300),
+            priority: 0,
+            preconditions: vec![],
+            allowed_tools: vec![],
         }
     }
     pub fn new() -> Self{
@@ -30,47 +42,61 @@ impl RequirementsAgent{
     }
 }
 
+#[async_trait]
 impl AgentHandler for RequirementsAgent{
-    fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
+    async fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
         println!("[RequirementsAgent] Starting requirements extraction.....");
 
         // 1. Assemble prompt
-        let prompt_result = PromptAssembler::assembler("requirements_agent",&task, &ctx.project);
+        let prompt_result = PromptAssembler::assemble_structured(
+            "requirements_agent",
+            &task,
+            &ctx.project,
+            ctx.llm.as_ref(),
+            &schema_value::<RequirementList>(),
+        )
+        .await;
         if let Err(err) = prompt_result{
-            return AgentResponse::error(&format!("Prompt Assembly failed: {}", err), false);
+            return AgentResponse::error(ErrorKind::Prompt, &format!("Prompt Assembly failed: {}", err));
         }
-        let prompt = prompt_result.unwrap();
+        let assembled = prompt_result.unwrap();
+        let prompt = assembled.text;
 
-        // 2. Query LLM
-        let llm_tool = LLMTool::new();
-        let query_result = llm_tool.query(prompt);
+        // 2. Query LLM for a RequirementList
+        let llm_tool = LLMTool::new(ctx.llm.clone());
+        let query_result = llm_tool
+            .query_structured::<RequirementList>(prompt, &task.task_id, "requirements", ctx.events.as_ref())
+            .await;
 
         if let Err(err) = query_result {
-            return AgentResponse::error(&format!("LLM query failed: {}", err),false);
+            return AgentResponse::error(ErrorKind::from(&err), &format!("LLM query failed: {}", err));
         }
-        let llm_output = query_result.unwrap();
+        let requirements = query_result.unwrap();
 
-        println!("[RequirementsAgent] Received output from LLM.");
+        println!("[RequirementsAgent] Received {} requirements from LLM.", requirements.requirements.len());
 
         // 3. Save to project memory
-        if let Err(err) = ctx.project.save_to_project_memory("requirements.json", &llm_output) {
-            return AgentResponse::error(&format!("Saving requirements failed: {}", err), false);
+        let serialized = serde_json::to_string_pretty(&requirements).unwrap_or_default();
+        if let Err(err) = ctx.project.set_requirements(&serialized) {
+            return AgentResponse::error(ErrorKind::Storage, &format!("Saving requirements failed: {}", err));
         }
 
         // 4. Log a design decision for memory enrichment
         let decision = DesignDecision{
             id: Uuid::new_v4().to_string(),
-            summary: summary.to_string(),
+            summary: format!("Captured {} requirements", requirements.requirements.len()),
             made_by: "RequirementsAgent".to_string(),
-            rationale: rationale.to_string(),
+            rationale: "Extracted from the user's goal".to_string(),
             timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs().to_string(),
         };
 
         // Log into ProjectMemory
-        ctx.project.write_decision(decision);
+        if let Err(err) = ctx.project.write_decision(decision) {
+            eprintln!("[warn] Failed to save design decision: {}", err);
+        }
 
 
         // Return the requirements as agent output
-        AgentResponse::success("Requirements generated and saved".to_string(), "RequirementsAgent")
+        AgentResponse::structured(&requirements, "RequirementsAgent").with_trace(assembled.trace)
     }
 }
\ No newline at end of file
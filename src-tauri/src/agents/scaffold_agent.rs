@@ -1,8 +1,9 @@
+use async_trait::async_trait;
 use serde_json::json;
-use crate::agents::orchestrator::context::AgentContext;
-use crate::agents::orchestrator::protocol::AgentResponse;
-use crate::agents::orchestrator::registry::AgentHandler;
-use crate::agents::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph};
+use crate::orchestrator::context::AgentContext;
+use crate::orchestrator::protocol::AgentResponse;
+use crate::orchestrator::registry::AgentHandler;
+use crate::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph, Precondition};
 
 pub struct ScaffoldAgent;
 
@@ -18,12 +19,17 @@ impl ScaffoldAgent {
             input_schema: "ArchitecturePlan".into(),
             output_schema: "FileStructure".into(),
             default_execution: ExecutionMode::Execute,
+            timeout_secs: Some(120),
+            priority: 0,
+            preconditions: vec![Precondition::ProjectOpen],
+            allowed_tools: vec!["FileTool".into()],
         }
     }
 }
 
+#[async_trait]
 impl AgentHandler for ScaffoldAgent {
-    fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
+    async fn handle_task(&self, _task: AgentTask, ctx: AgentContext) -> AgentResponse {
         println!("[ScaffoldAgent] Writing stubs for architecture...");
 
         // Simulated file stubs
@@ -37,11 +43,11 @@ impl AgentHandler for ScaffoldAgent {
 
         for (path, content) in files {
             if let Some(tool) = ctx.tool_registry.get("FileTool") {
-                let result = futures::executor::block_on(tool.run(json!({
+                let result = tool.run(json!({
                     "action": "write",
                     "path": path,
                     "content": content
-                })));
+                }), &ctx.tool_context()).await;
 
                 match result {
                     Ok(tool_return) if tool_return.status.is_success() => {
@@ -57,8 +63,10 @@ impl AgentHandler for ScaffoldAgent {
             }
         }
 
+        // Outside Execute mode the paths were only recorded in the task's change set
         let output = json!({
             "written": written,
+            "mode": ctx.execution_mode,
             "summary": "Scaffolded initial files based on architecture"
         });
 
@@ -1,8 +1,10 @@
-use serde_json::json;
-use crate::agents::orchestrator::context::AgentContext;
-use crate::agents::orchestrator::protocol::AgentResponse;
-use crate::agents::orchestrator::registry::AgentHandler;
-use crate::agents::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph};
+use async_trait::async_trait;
+use crate::orchestrator::context::AgentContext;
+use crate::orchestrator::protocol::{AgentResponse, ErrorKind};
+use crate::orchestrator::registry::AgentHandler;
+use crate::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph, Precondition};
+use crate::prompt_assembler::PromptAssembler;
+use crate::tools::llm_tool::LLMTool;
 
 pub struct SecurityAgent;
 
@@ -10,7 +12,7 @@ impl SecurityAgent {
     pub fn card() -> AgentCard {
         AgentCard {
             id: "security".into(),
-            description: "Analyzes and recommends security best practices".into(),
+            description: "Reviews code patches and the project for vulnerabilities and recommends fixes".into(),
             skills: SkillGraph {
                 root: Capability::Security,
                 subskills: vec![],
@@ -18,45 +20,33 @@ impl SecurityAgent {
             input_schema: "CodePatch".into(),
             output_schema: "SecurityReview".into(),
             default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(300),
+            priority: 0,
+            preconditions: vec![Precondition::ProjectOpen],
+            allowed_tools: vec![],
         }
     }
 }
 
+#[async_trait]
 impl AgentHandler for SecurityAgent {
-    fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
-        println!("[SecurityAgent] Performing simulated security scan...");
-
-        if let Some(tool) = ctx.tool_registry.get("CodeScanTool") {
-            let input = json!({
-                "scope": "entire_project",
-                "focus": "vulnerabilities",
-            });
-
-            let result = futures::executor::block_on(tool.run(input));
-
-            match result {
-                Ok(tool_return) if tool_return.status.is_success() => {
-                    let output = json!({
-                        "scan": tool_return.result,
-                        "summary": "Security scan completed with no critical issues"
-                    });
-
-                    AgentResponse::success(&output.to_string(), "SecurityAgent")
-                },
-                Ok(tool_return) => {
-                    let output = json!({
-                        "warning": tool_return.result,
-                        "summary": "Security scan completed with warnings"
-                    });
-
-                    AgentResponse::success(&output.to_string(), "SecurityAgent")
-                },
-                Err(e) => {
-                    AgentResponse::error(&format!("Security tool error: {e}"), false)
-                }
-            }
-        } else {
-            AgentResponse::error("CodeScanTool not found in registry", false)
+    async fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
+        println!("[SecurityAgent] Reviewing task: {}", task.task_id);
+
+        // 1. Assemble prompt, including the project chunks relevant to the task
+        let assembled = match PromptAssembler::assemble("security", &task, &ctx.project, ctx.llm.as_ref()).await {
+            Ok(assembled) => assembled,
+            Err(err) => return AgentResponse::error(ErrorKind::Prompt, &format!("Prompt assembly failed: {}", err)),
+        };
+
+        // 2. Query LLM for the review
+        let llm_tool = LLMTool::new(ctx.llm.clone());
+        match llm_tool
+            .query_streaming(assembled.text, &task.task_id, "security", ctx.events.as_ref())
+            .await
+        {
+            Ok(text) => AgentResponse::success(&text, "SecurityAgent").with_trace(assembled.trace),
+            Err(err) => AgentResponse::error(ErrorKind::from(&err), &format!("LLM query failed: {}", err)),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,10 @@
-use serde_json::json;
-use crate::agents::orchestrator::context::AgentContext;
-use crate::agents::orchestrator::protocol::AgentResponse;
-use crate::agents::orchestrator::registry::AgentHandler;
-use crate::agents::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph};
+use async_trait::async_trait;
+use crate::orchestrator::context::AgentContext;
+use crate::orchestrator::protocol::{AgentResponse, ErrorKind};
+use crate::orchestrator::registry::AgentHandler;
+use crate::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, SkillGraph};
+use crate::prompt_assembler::PromptAssembler;
+use crate::tools::llm_tool::LLMTool;
 
 pub struct TestAgent;
 
@@ -10,58 +12,41 @@ impl TestAgent {
     pub fn card() -> AgentCard {
         AgentCard {
             id: "test".into(),
-            description: "Generates and evaluates tests for code patches".into(),
+            description: "Writes tests for the task from the project's requirements and code, streaming the model's answer".into(),
             skills: SkillGraph {
                 root: Capability::Testing,
                 subskills: vec![],
             },
-            input_schema: "CodePatch".into(),
-            output_schema: "TestSuite".into(),
-            default_execution: ExecutionMode::Execute,
+            input_schema: "TaskDescription".into(),
+            output_schema: "Text".into(),
+            default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(900),
+            priority: 0,
+            preconditions: vec![],
+            allowed_tools: vec![],
         }
     }
 }
 
+#[async_trait]
 impl AgentHandler for TestAgent {
-    fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
-        println!("[TestAgent] Generating test stubs...");
-
-        // Simulated test generation
-        let modules = vec!["auth", "events"];
-        let mut generated_tests = vec![];
-
-        for module in modules {
-            if let Some(tool) = ctx.tool_registry.get("TestGenTool") {
-                let input = json!({
-                    "module": module,
-                    "style": "unit",
-                    "lang": "rust"
-                });
-
-                let result = futures::executor::block_on(tool.run(input));
-
-                match result {
-                    Ok(tool_return) if tool_return.status.is_success() => {
-                        generated_tests.push(json!({
-                            "module": module,
-                            "test": tool_return.result
-                        }));
-                    },
-                    Ok(tool_return) => {
-                        println!("[TestAgent] Warning: {:?}", tool_return.status);
-                    },
-                    Err(e) => {
-                        println!("[TestAgent] Error generating tests for {module}: {e}");
-                    }
-                }
-            }
+    async fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
+        println!("[TestAgent] Generating tests for task: {}", task.task_id);
+
+        // 1. Assemble prompt
+        let assembled = match PromptAssembler::assemble("test_agent", &task, &ctx.project, ctx.llm.as_ref()).await {
+            Ok(assembled) => assembled,
+            Err(err) => return AgentResponse::error(ErrorKind::Prompt, &format!("Prompt assembly failed: {}", err)),
+        };
+
+        // 2. Query LLM for the test suite
+        let llm_tool = LLMTool::new(ctx.llm.clone());
+        match llm_tool
+            .query_streaming(assembled.text, &task.task_id, "test", ctx.events.as_ref())
+            .await
+        {
+            Ok(text) => AgentResponse::success(&text, "TestAgent").with_trace(assembled.trace),
+            Err(err) => AgentResponse::error(ErrorKind::from(&err), &format!("LLM query failed: {}", err)),
         }
-
-        let output = json!({
-            "tests": generated_tests,
-            "summary": "Test stubs generated for key modules"
-        });
-
-        AgentResponse::success(&output.to_string(), "TestAgent")
     }
-}
\ No newline at end of file
+}
@@ -31,6 +31,22 @@ pub struct ModelDownloadInfo {
     pub estimated_size_bytes: u64,
 }
 
+/// Settings for the LLM backends. Unset fields fall back to the hardware-picked local model
+/// or an OpenAI-compatible endpoint on localhost.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LlmSettings {
+    /// Path to the llama-server binary
+    pub llama_binary: Option<String>,
+    pub local_model_path: Option<String>,
+    pub context_size: Option<usize>,
+    /// GGUF embedding model used to index the project; BM25 ranking is used without one
+    #[serde(default)]
+    pub embedding_model_path: Option<String>,
+    pub endpoint: Option<String>,
+    pub api_key: Option<String>,
+    pub model: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AppConfig {
     pub mode: Option<WinterMode>,
@@ -39,6 +55,8 @@ pub struct AppConfig {
     pub paths: AppPaths,
     pub model_file_size_estimate: Option<u64>,
     pub cached_model_info: Option<ModelDownloadInfo>,
+    #[serde(default)]
+    pub llm: LlmSettings,
 }
 
 pub fn setup_internal_dirs() -> std::io::Result<PathBuf> {
@@ -63,7 +81,8 @@ pub fn setup_internal_dirs() -> std::io::Result<PathBuf> {
                 uploads: uploads_dir.to_string_lossy().to_string(),
             },
             model_file_size_estimate:None,
-            cached_model_info: None
+            cached_model_info: None,
+            llm: LlmSettings::default(),
         };
 
         let json = serde_json::to_string_pretty(&config)?;
@@ -6,6 +6,7 @@ pub mod orchestrator;
 pub mod agents;
 pub mod model;
 pub mod config;
+pub mod llm;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -0,0 +1,190 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::config::{AppConfig, WinterMode};
+use crate::llm::llama_cpp::LlamaCppBackend;
+use crate::llm::openai::OpenAiCompatBackend;
+
+/// Context window assumed for backends that do not report one
+pub const DEFAULT_CONTEXT_WINDOW: usize = 4096;
+
+/// Stream of text fragments as the model produces them
+pub type TokenStream = BoxStream<'static, Result<String, LlmError>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: &str) -> Self {
+        Self { role: ChatRole::System, content: content.to_string() }
+    }
+    pub fn user(content: &str) -> Self {
+        Self { role: ChatRole::User, content: content.to_string() }
+    }
+    pub fn assistant(content: &str) -> Self {
+        Self { role: ChatRole::Assistant, content: content.to_string() }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompletionOptions {
+    pub max_tokens: Option<u32>,
+    pub temperature: f32,
+    pub repeat_penalty: f32,
+    pub stop: Vec<String>,
+    /// JSON schema the output must follow; backends that support it constrain decoding to it
+    pub json_schema: Option<serde_json::Value>,
+}
+
+impl Default for CompletionOptions {
+    fn default() -> Self {
+        Self {
+            max_tokens: None,
+            temperature: 0.7,
+            repeat_penalty: 1.1,
+            stop: vec![],
+            json_schema: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LlmError {
+    /// Backend is not configured or its model/binary/endpoint cannot be reached
+    Unavailable(String),
+    /// The backend was reached but the request failed
+    Request(String),
+    /// The backend answered with something we could not interpret
+    InvalidResponse(String),
+    Cancelled,
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LlmError::Unavailable(msg) => write!(f, "LLM backend unavailable: {}", msg),
+            LlmError::Request(msg) => write!(f, "LLM request failed: {}", msg),
+            LlmError::InvalidResponse(msg) => write!(f, "Invalid LLM response: {}", msg),
+            LlmError::Cancelled => write!(f, "LLM request cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+/// Common interface over every model backend Winter can talk to.
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Raw text completion of `prompt`
+    async fn complete(&self, prompt: &str, options: &CompletionOptions) -> Result<String, LlmError>;
+
+    /// Chat completion. Backends without a chat endpoint flatten the messages into a prompt.
+    async fn chat(&self, messages: &[ChatMessage], options: &CompletionOptions) -> Result<String, LlmError> {
+        self.complete(&render_chat_prompt(messages), options).await
+    }
+
+    /// Streams the completion of `prompt` fragment by fragment
+    async fn stream(&self, prompt: &str, options: &CompletionOptions) -> Result<TokenStream, LlmError>;
+
+    /// Aborts every request currently in flight on this backend
+    fn cancel(&self);
+
+    /// Tokens the model attends to, prompt and completion together
+    fn context_window(&self) -> usize {
+        DEFAULT_CONTEXT_WINDOW
+    }
+
+    /// Number of tokens `text` takes for this model. Backends without a tokenizer estimate it.
+    async fn count_tokens(&self, text: &str) -> Result<usize, LlmError> {
+        Ok(estimate_tokens(text))
+    }
+}
+
+/// Rough token count, about four characters per token for English text and code
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Takes the next complete line off a server-sent event stream's buffer. Bytes are only
+/// decoded once their line is complete, so characters split across network chunks survive.
+pub fn take_line(buffer: &mut Vec<u8>) -> Option<String> {
+    let newline = buffer.iter().position(|&byte| byte == b'\n')?;
+    let line: Vec<u8> = buffer.drain(..=newline).collect();
+    Some(String::from_utf8_lossy(&line).into_owned())
+}
+
+/// Picks the backend for the configured `WinterMode`. Local is assumed until the user chooses.
+pub fn backend_from_config(config: &AppConfig) -> Arc<dyn LlmBackend> {
+    match config.mode {
+        Some(WinterMode::Cloud) => Arc::new(OpenAiCompatBackend::from_settings(&config.llm)),
+        Some(WinterMode::Local) | None => Arc::new(LlamaCppBackend::from_settings(&config.llm)),
+    }
+}
+
+/// Plain-text chat template used by backends that only expose raw completion
+pub fn render_chat_prompt(messages: &[ChatMessage]) -> String {
+    let mut prompt = String::new();
+    for message in messages {
+        let role = match message.role {
+            ChatRole::System => "System",
+            ChatRole::User => "User",
+            ChatRole::Assistant => "Assistant",
+        };
+        prompt.push_str(&format!("### {}\n{}\n\n", role, message.content.trim()));
+    }
+    prompt.push_str("### Assistant\n");
+    prompt
+}
+
+/// Cancellation signal shared by a backend and its in-flight requests.
+/// Requests remember the epoch they started in and stop once it moves on.
+#[derive(Debug, Clone, Default)]
+pub struct CancelSignal {
+    epoch: Arc<AtomicU64>,
+    notify: Arc<Notify>,
+}
+
+impl CancelSignal {
+    pub fn cancel(&self) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    pub fn is_cancelled_since(&self, epoch: u64) -> bool {
+        self.epoch() != epoch
+    }
+
+    /// Resolves once `cancel` is called after `epoch`
+    pub async fn cancelled_since(&self, epoch: u64) {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_cancelled_since(epoch) {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
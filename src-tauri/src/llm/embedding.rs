@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::config::{load_config, LlmSettings};
+use crate::llm::backend::LlmError;
+use crate::model::llama_server::LlamaServerManager;
+
+/// Default location of the bundled embedding model
+const DEFAULT_EMBEDDING_MODEL: &str = "WinterData/models/embedding/all-MiniLM-L6-v2.Q8_0.gguf";
+const EMBEDDING_CONTEXT_SIZE: usize = 512;
+
+static DEFAULT_EMBEDDER: OnceLock<Option<Arc<dyn Embedder>>> = OnceLock::new();
+
+/// Turns text into vectors for semantic retrieval
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Identifies the model; vectors from different embedders are not comparable
+    fn name(&self) -> String;
+
+    /// Longest input, in tokens, the model embeds without truncating it
+    fn max_tokens(&self) -> usize;
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, LlmError>;
+}
+
+/// Embeds through a llama-server started with `--embedding` on a small local GGUF model
+pub struct LlamaEmbedder {
+    servers: Arc<LlamaServerManager>,
+    model_path: PathBuf,
+    client: reqwest::Client,
+}
+
+impl LlamaEmbedder {
+    /// The configured or bundled embedding model, or None if it is not installed
+    pub fn from_settings(settings: &LlmSettings) -> Option<Self> {
+        let model_path = settings
+            .embedding_model_path
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| dirs::home_dir().expect("No home dir").join(DEFAULT_EMBEDDING_MODEL));
+
+        model_path.exists().then(|| Self {
+            servers: LlamaServerManager::global(settings.llama_binary.as_deref()),
+            model_path,
+            client: reqwest::Client::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl Embedder for LlamaEmbedder {
+    fn name(&self) -> String {
+        format!("llama.cpp:{}", self.model_path.display())
+    }
+
+    fn max_tokens(&self) -> usize {
+        EMBEDDING_CONTEXT_SIZE
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, LlmError> {
+        let lease = self
+            .servers
+            .acquire_embedding(&self.model_path, EMBEDDING_CONTEXT_SIZE)
+            .await?;
+
+        let response = self
+            .client
+            .post(format!("{}/v1/embeddings", lease.base_url))
+            .json(&json!({ "input": texts }))
+            .send()
+            .await
+            .map_err(|e| LlmError::Request(e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(LlmError::Request(format!("{}: {}", status, text)));
+        }
+
+        let value: Value = response
+            .json()
+            .await
+            .map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
+        let vectors: Vec<Vec<f32>> = value["data"]
+            .as_array()
+            .ok_or_else(|| LlmError::InvalidResponse(format!("No data in {}", value)))?
+            .iter()
+            .map(|item| {
+                item["embedding"]
+                    .as_array()
+                    .map(|values| values.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect())
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        if vectors.len() != texts.len() {
+            return Err(LlmError::InvalidResponse(format!(
+                "Expected {} embeddings, got {}",
+                texts.len(),
+                vectors.len()
+            )));
+        }
+        Ok(vectors)
+    }
+}
+
+/// The embedder from the app config, created on first use.
+/// None if no embedding model is installed, in which case retrieval ranks with BM25.
+pub fn default_embedder() -> Option<Arc<dyn Embedder>> {
+    DEFAULT_EMBEDDER
+        .get_or_init(|| {
+            let settings = load_config().map(|config| config.llm).unwrap_or_default();
+            match LlamaEmbedder::from_settings(&settings) {
+                Some(embedder) => {
+                    println!("[Embedding] Using {}", embedder.name());
+                    Some(Arc::new(embedder) as Arc<dyn Embedder>)
+                }
+                None => {
+                    println!("[Embedding] No embedding model installed, falling back to BM25");
+                    None
+                }
+            }
+        })
+        .clone()
+}
@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde_json::{json, Value};
+
+use crate::config::LlmSettings;
+use crate::llm::backend::{take_line, CancelSignal, CompletionOptions, LlmBackend, LlmError, TokenStream};
+use crate::model::llama_server::{LlamaServerManager, ServerLease};
+use crate::model::model_selector::pick_optimal_model;
+
+/// Runs prompts against a GGUF model under WinterData/models through a managed llama-server,
+/// so the weights stay loaded between calls.
+pub struct LlamaCppBackend {
+    servers: Arc<LlamaServerManager>,
+    model_path: PathBuf,
+    context_size: usize,
+    client: reqwest::Client,
+    cancel: CancelSignal,
+}
+
+impl LlamaCppBackend {
+    pub fn new(servers: Arc<LlamaServerManager>, model_path: PathBuf, context_size: usize) -> Self {
+        Self {
+            servers,
+            model_path,
+            context_size,
+            client: reqwest::Client::new(),
+            cancel: CancelSignal::default(),
+        }
+    }
+
+    /// Uses the configured model, or the one `pick_optimal_model` chose for this machine.
+    pub fn from_settings(settings: &LlmSettings) -> Self {
+        let choice = pick_optimal_model();
+
+        let model_path = settings
+            .local_model_path
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                dirs::home_dir()
+                    .expect("No home dir")
+                    .join("WinterData/models")
+                    .join(&choice.model_name)
+                    .join(format!("{}.gguf", choice.quant_level))
+            });
+
+        Self::new(
+            LlamaServerManager::global(settings.llama_binary.as_deref()),
+            model_path,
+            settings.context_size.unwrap_or(choice.context_size),
+        )
+    }
+
+    pub fn model_path(&self) -> &PathBuf {
+        &self.model_path
+    }
+
+    fn request_body(prompt: &str, options: &CompletionOptions, stream: bool) -> Value {
+        let mut body = json!({
+            "prompt": prompt,
+            "temperature": options.temperature,
+            "repeat_penalty": options.repeat_penalty,
+            "stream": stream,
+        });
+        if let Some(max_tokens) = options.max_tokens {
+            body["n_predict"] = json!(max_tokens);
+        }
+        if !options.stop.is_empty() {
+            body["stop"] = json!(options.stop);
+        }
+        // llama-server turns the schema into a GBNF grammar
+        if let Some(schema) = &options.json_schema {
+            body["json_schema"] = schema.clone();
+        }
+        body
+    }
+
+    /// Posts to the model's server. A connection failure means the server died under us,
+    /// so it is restarted once before giving up.
+    async fn post_completion(&self, body: &Value) -> Result<(ServerLease, reqwest::Response), LlmError> {
+        for attempt in 0..2 {
+            let lease = self.servers.acquire(&self.model_path, self.context_size).await?;
+
+            match self
+                .client
+                .post(format!("{}/completion", lease.base_url))
+                .json(body)
+                .send()
+                .await
+            {
+                Ok(response) if response.status().is_success() => return Ok((lease, response)),
+                Ok(response) => {
+                    let status = response.status();
+                    let text = response.text().await.unwrap_or_default();
+                    return Err(LlmError::Request(format!("{}: {}", status, text)));
+                }
+                Err(e) if e.is_connect() && attempt == 0 => {
+                    self.servers.invalidate(lease).await;
+                }
+                Err(e) => return Err(LlmError::Request(e.to_string())),
+            }
+        }
+        Err(LlmError::Unavailable("llama-server is not responding".into()))
+    }
+}
+
+#[async_trait]
+impl LlmBackend for LlamaCppBackend {
+    fn name(&self) -> &'static str {
+        "llama.cpp"
+    }
+
+    async fn complete(&self, prompt: &str, options: &CompletionOptions) -> Result<String, LlmError> {
+        let epoch = self.cancel.epoch();
+        let body = Self::request_body(prompt, options, false);
+
+        let value: Value = tokio::select! {
+            result = async {
+                let (_lease, response) = self.post_completion(&body).await?;
+                response
+                    .json::<Value>()
+                    .await
+                    .map_err(|e| LlmError::InvalidResponse(e.to_string()))
+            } => result?,
+            _ = self.cancel.cancelled_since(epoch) => return Err(LlmError::Cancelled),
+        };
+
+        value["content"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| LlmError::InvalidResponse(format!("No content in {}", value)))
+    }
+
+    async fn stream(&self, prompt: &str, options: &CompletionOptions) -> Result<TokenStream, LlmError> {
+        let epoch = self.cancel.epoch();
+        let body = Self::request_body(prompt, options, true);
+        let (lease, response) = self.post_completion(&body).await?;
+
+        let bytes = response.bytes_stream().boxed();
+        let cancel = self.cancel.clone();
+
+        // Server-sent events: `data: {"content": "...", "stop": false}` per token.
+        // The lease rides along in the state so the request slot stays held until the stream ends.
+        let tokens = stream::unfold(
+            (bytes, lease, Vec::new(), VecDeque::new(), false),
+            move |(mut bytes, lease, mut buffer, mut ready, mut finished)| {
+                let cancel = cancel.clone();
+                async move {
+                    loop {
+                        if let Some(token) = ready.pop_front() {
+                            return Some((token, (bytes, lease, buffer, ready, finished)));
+                        }
+                        if finished {
+                            return None;
+                        }
+
+                        let chunk = tokio::select! {
+                            chunk = bytes.next() => chunk,
+                            _ = cancel.cancelled_since(epoch) => {
+                                return Some((Err(LlmError::Cancelled), (bytes, lease, buffer, ready, true)));
+                            }
+                        };
+
+                        match chunk {
+                            None => finished = true,
+                            Some(Err(e)) => {
+                                ready.push_back(Err(LlmError::Request(e.to_string())));
+                                finished = true;
+                            }
+                            Some(Ok(chunk)) => {
+                                buffer.extend_from_slice(&chunk);
+                                while let Some(line) = take_line(&mut buffer) {
+                                    let Some(data) = line.trim().strip_prefix("data:") else {
+                                        continue;
+                                    };
+                                    match serde_json::from_str::<Value>(data.trim()) {
+                                        Ok(event) => {
+                                            if let Some(text) = event["content"].as_str() {
+                                                if !text.is_empty() {
+                                                    ready.push_back(Ok(text.to_string()));
+                                                }
+                                            }
+                                            if event["stop"].as_bool().unwrap_or(false) {
+                                                finished = true;
+                                                break;
+                                            }
+                                        }
+                                        Err(e) => ready.push_back(Err(LlmError::InvalidResponse(e.to_string()))),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+        );
+
+        Ok(tokens.boxed())
+    }
+
+    fn context_window(&self) -> usize {
+        self.context_size
+    }
+
+    /// Tokenizes with the model's own vocabulary through llama-server's `/tokenize`.
+    /// Tokenizing needs no request slot, so it does not wait for running completions.
+    async fn count_tokens(&self, text: &str) -> Result<usize, LlmError> {
+        let base_url = self.servers.base_url(&self.model_path, self.context_size).await?;
+        let response = self
+            .client
+            .post(format!("{}/tokenize", base_url))
+            .json(&json!({ "content": text }))
+            .send()
+            .await
+            .map_err(|e| LlmError::Request(e.to_string()))?;
+        let value: Value = response
+            .json()
+            .await
+            .map_err(|e| LlmError::InvalidResponse(e.to_string()))?;
+        value["tokens"]
+            .as_array()
+            .map(|tokens| tokens.len())
+            .ok_or_else(|| LlmError::InvalidResponse(format!("No tokens in {}", value)))
+    }
+
+    fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
@@ -0,0 +1,7 @@
+pub mod backend;
+pub mod embedding;
+pub mod llama_cpp;
+pub mod openai;
+pub mod scripted;
+pub mod streaming;
+pub mod structured;
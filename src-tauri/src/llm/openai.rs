@@ -0,0 +1,281 @@
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde_json::{json, Value};
+
+use crate::config::LlmSettings;
+use crate::llm::backend::{
+    take_line, CancelSignal, ChatMessage, CompletionOptions, LlmBackend, LlmError, TokenStream,
+    DEFAULT_CONTEXT_WINDOW,
+};
+
+const DEFAULT_ENDPOINT: &str = "http://127.0.0.1:8080/v1";
+const DEFAULT_MODEL: &str = "default";
+
+/// Talks to any server that implements the OpenAI `/chat/completions` API
+/// (OpenAI itself, llama-server, vLLM, Ollama, or a local mock in tests).
+pub struct OpenAiCompatBackend {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: Option<String>,
+    model: String,
+    context_window: usize,
+    cancel: CancelSignal,
+}
+
+impl OpenAiCompatBackend {
+    pub fn new(endpoint: &str, api_key: Option<String>, model: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_key,
+            model: model.to_string(),
+            context_window: DEFAULT_CONTEXT_WINDOW,
+            cancel: CancelSignal::default(),
+        }
+    }
+
+    /// The remote model's context window is taken from `context_size` in the settings
+    pub fn from_settings(settings: &LlmSettings) -> Self {
+        let mut backend = Self::new(
+            settings.endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT),
+            settings.api_key.clone(),
+            settings.model.as_deref().unwrap_or(DEFAULT_MODEL),
+        );
+        if let Some(context_size) = settings.context_size {
+            backend.context_window = context_size;
+        }
+        backend
+    }
+
+    fn request_body(&self, messages: &[ChatMessage], options: &CompletionOptions, stream: bool) -> Value {
+        let mut body = json!({
+            "model": self.model,
+            "messages": messages,
+            "temperature": options.temperature,
+            "stream": stream,
+        });
+        if let Some(max_tokens) = options.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        if !options.stop.is_empty() {
+            body["stop"] = json!(options.stop);
+        }
+        if let Some(schema) = &options.json_schema {
+            body["response_format"] = json!({
+                "type": "json_schema",
+                "json_schema": { "name": "output", "schema": schema },
+            });
+        }
+        body
+    }
+
+    async fn send(&self, body: &Value) -> Result<reqwest::Response, LlmError> {
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", self.endpoint))
+            .json(body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| LlmError::Unavailable(format!("{}: {}", self.endpoint, e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(LlmError::Request(format!("{}: {}", status, text)));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OpenAiCompatBackend {
+    fn name(&self) -> &'static str {
+        "openai-compatible"
+    }
+
+    async fn complete(&self, prompt: &str, options: &CompletionOptions) -> Result<String, LlmError> {
+        self.chat(&[ChatMessage::user(prompt)], options).await
+    }
+
+    async fn chat(&self, messages: &[ChatMessage], options: &CompletionOptions) -> Result<String, LlmError> {
+        let epoch = self.cancel.epoch();
+        let body = self.request_body(messages, options, false);
+
+        let response = tokio::select! {
+            response = self.send(&body) => response?,
+            _ = self.cancel.cancelled_since(epoch) => return Err(LlmError::Cancelled),
+        };
+
+        let value: Value = tokio::select! {
+            value = response.json() => value.map_err(|e| LlmError::InvalidResponse(e.to_string()))?,
+            _ = self.cancel.cancelled_since(epoch) => return Err(LlmError::Cancelled),
+        };
+
+        value["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| LlmError::InvalidResponse(format!("No message content in {}", value)))
+    }
+
+    async fn stream(&self, prompt: &str, options: &CompletionOptions) -> Result<TokenStream, LlmError> {
+        let epoch = self.cancel.epoch();
+        let body = self.request_body(&[ChatMessage::user(prompt)], options, true);
+        let response = self.send(&body).await?;
+
+        let bytes = response.bytes_stream().boxed();
+        let cancel = self.cancel.clone();
+
+        // Server-sent events: one `data: {json}` line per delta, terminated by `data: [DONE]`
+        let tokens = stream::unfold(
+            (bytes, Vec::new(), VecDeque::new(), false),
+            move |(mut bytes, mut buffer, mut ready, mut finished)| {
+                let cancel = cancel.clone();
+                async move {
+                    loop {
+                        if let Some(token) = ready.pop_front() {
+                            return Some((token, (bytes, buffer, ready, finished)));
+                        }
+                        if finished {
+                            return None;
+                        }
+
+                        let chunk = tokio::select! {
+                            chunk = bytes.next() => chunk,
+                            _ = cancel.cancelled_since(epoch) => {
+                                return Some((Err(LlmError::Cancelled), (bytes, buffer, ready, true)));
+                            }
+                        };
+
+                        match chunk {
+                            None => finished = true,
+                            Some(Err(e)) => {
+                                ready.push_back(Err(LlmError::Request(e.to_string())));
+                                finished = true;
+                            }
+                            Some(Ok(chunk)) => {
+                                buffer.extend_from_slice(&chunk);
+                                while let Some(line) = take_line(&mut buffer) {
+                                    let Some(data) = line.trim().strip_prefix("data:") else {
+                                        continue;
+                                    };
+                                    let data = data.trim();
+                                    if data == "[DONE]" {
+                                        finished = true;
+                                        break;
+                                    }
+                                    match serde_json::from_str::<Value>(data) {
+                                        Ok(event) => {
+                                            if let Some(text) = event["choices"][0]["delta"]["content"].as_str() {
+                                                if !text.is_empty() {
+                                                    ready.push_back(Ok(text.to_string()));
+                                                }
+                                            }
+                                        }
+                                        Err(e) => ready.push_back(Err(LlmError::InvalidResponse(e.to_string()))),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+        );
+
+        Ok(tokens.boxed())
+    }
+
+    fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    fn context_window(&self) -> usize {
+        self.context_window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves one request with an event stream, writing each part as its own network chunk
+    async fn serve_stream(parts: Vec<Vec<u8>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            read_request(&mut socket).await;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            for part in parts {
+                socket.write_all(&part).await.unwrap();
+                socket.flush().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
+        format!("http://{}/v1", address)
+    }
+
+    /// Reads the request headers and body so the client is not cut off mid-send
+    async fn read_request(socket: &mut tokio::net::TcpStream) {
+        let mut request = vec![];
+        let mut buffer = [0; 4096];
+        loop {
+            let read = socket.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some(header_end) = text.find("\r\n\r\n") {
+                let length = text[..header_end]
+                    .lines()
+                    .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                    .unwrap_or(0);
+                if request.len() >= header_end + 4 + length {
+                    return;
+                }
+            }
+            if read == 0 {
+                return;
+            }
+        }
+    }
+
+    fn delta(text: &str) -> String {
+        format!("data: {}\n\n", json!({ "choices": [{ "delta": { "content": text } }] }))
+    }
+
+    #[tokio::test]
+    async fn stream_joins_characters_split_across_chunks() {
+        let events = format!("{}{}data: [DONE]\n\n", delta("héllo "), delta("wörld"));
+        let bytes = events.into_bytes();
+        // Cut inside the two bytes of 'é' and inside the second event's line
+        let first_cut = bytes.iter().position(|&byte| byte == 0xC3).unwrap() + 1;
+        let second_cut = first_cut + 40;
+        let parts = vec![
+            bytes[..first_cut].to_vec(),
+            bytes[first_cut..second_cut].to_vec(),
+            bytes[second_cut..].to_vec(),
+        ];
+
+        let backend = OpenAiCompatBackend::new(&serve_stream(parts).await, None, "mock");
+        let tokens: Vec<Result<String, LlmError>> = backend
+            .stream("hi", &CompletionOptions::default())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        let text: String = tokens.into_iter().map(|token| token.unwrap()).collect();
+        assert_eq!(text, "héllo wörld");
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+
+use crate::llm::backend::{CompletionOptions, LlmBackend, LlmError, TokenStream};
+
+/// Deterministic backend that replays canned responses in order and records every prompt.
+/// Used to exercise agents without a model.
+#[derive(Default)]
+pub struct ScriptedBackend {
+    responses: Mutex<VecDeque<String>>,
+    prompts: Mutex<Vec<String>>,
+}
+
+impl ScriptedBackend {
+    pub fn new(responses: Vec<&str>) -> Self {
+        Self {
+            responses: Mutex::new(responses.into_iter().map(String::from).collect()),
+            prompts: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn push_response(&self, response: &str) {
+        self.responses.lock().unwrap().push_back(response.to_string());
+    }
+
+    /// Prompts received so far, in order
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().unwrap().clone()
+    }
+
+    fn next_response(&self, prompt: &str) -> Result<String, LlmError> {
+        self.prompts.lock().unwrap().push(prompt.to_string());
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| LlmError::InvalidResponse("Scripted backend has no responses left".into()))
+    }
+}
+
+#[async_trait]
+impl LlmBackend for ScriptedBackend {
+    fn name(&self) -> &'static str {
+        "scripted"
+    }
+
+    async fn complete(&self, prompt: &str, _options: &CompletionOptions) -> Result<String, LlmError> {
+        self.next_response(prompt)
+    }
+
+    async fn stream(&self, prompt: &str, _options: &CompletionOptions) -> Result<TokenStream, LlmError> {
+        let response = self.next_response(prompt)?;
+        // Split after each whitespace run so the pieces concatenate back to the response
+        let mut tokens = vec![];
+        let mut current = String::new();
+        for ch in response.chars() {
+            current.push(ch);
+            if ch.is_whitespace() {
+                tokens.push(Ok(std::mem::take(&mut current)));
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(Ok(current));
+        }
+        Ok(stream::iter(tokens).boxed())
+    }
+
+    fn cancel(&self) {}
+}
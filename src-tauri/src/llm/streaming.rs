@@ -0,0 +1,148 @@
+use futures::StreamExt;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::llm::backend::{CompletionOptions, LlmBackend, LlmError};
+use crate::orchestrator::cancellation::{cancel_running_task, register_task, task_token, unregister_task};
+use crate::orchestrator::events::EventSink;
+use tokio_util::sync::CancellationToken;
+
+pub const TOKEN_EVENT: &str = "inference-token";
+pub const COMPLETE_EVENT: &str = "inference-complete";
+
+#[derive(Serialize, Clone)]
+struct TokenEvent<'a> {
+    task_id: &'a str,
+    agent_id: &'a str,
+    index: usize,
+    token: &'a str,
+}
+
+/// Streams a completion, emitting every fragment as an `inference-token` event tagged with
+/// the task and agent, and returns the assembled text once the model is done.
+pub async fn stream_completion(
+    backend: &dyn LlmBackend,
+    prompt: &str,
+    options: &CompletionOptions,
+    task_id: &str,
+    agent_id: &str,
+    events: &dyn EventSink,
+) -> Result<String, LlmError> {
+    // Streams of orchestrated tasks stop with the task; standalone ones get their own token
+    let (cancel, standalone) = match task_token(task_id) {
+        Some(token) => (token, false),
+        None => (register_task(task_id, &CancellationToken::new()), true),
+    };
+
+    let result = async {
+        // Connecting can wait for a model to load or a request slot to free up
+        let mut tokens = tokio::select! {
+            tokens = backend.stream(prompt, options) => tokens?,
+            _ = cancel.cancelled() => return Err(LlmError::Cancelled),
+        };
+        let mut text = String::new();
+        let mut index = 0;
+
+        loop {
+            let next = tokio::select! {
+                next = tokens.next() => next,
+                _ = cancel.cancelled() => return Err(LlmError::Cancelled),
+            };
+
+            let Some(token) = next else { break };
+            let token = token?;
+
+            let event = TokenEvent {
+                task_id,
+                agent_id,
+                index,
+                token: &token,
+            };
+            events.emit(TOKEN_EVENT, json!(event));
+
+            text.push_str(&token);
+            index += 1;
+        }
+
+        Ok(text)
+    }
+    .await;
+
+    if standalone {
+        unregister_task(task_id);
+    }
+
+    events.emit(
+        COMPLETE_EVENT,
+        json!({
+            "task_id": task_id,
+            "agent_id": agent_id,
+            "cancelled": matches!(result, Err(LlmError::Cancelled)),
+            "error": result.as_ref().err().map(|e| e.to_string()),
+        }),
+    );
+
+    result
+}
+
+/// Stops the token stream of a running task, along with the task itself.
+/// Returns false if nothing was running for it.
+pub fn cancel_stream(task_id: &str) -> bool {
+    cancel_running_task(task_id)
+}
+
+#[tauri::command]
+pub fn cancel_inference(task_id: String) -> Result<(), String> {
+    if cancel_stream(&task_id) {
+        Ok(())
+    } else {
+        Err(format!("No inference running for task {}", task_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::backend::TokenStream;
+    use crate::orchestrator::events::NullEventSink;
+    use async_trait::async_trait;
+    use std::time::Duration;
+
+    /// Never gets a connection, like a backend stuck waiting for a busy server
+    struct HangingBackend;
+
+    #[async_trait]
+    impl LlmBackend for HangingBackend {
+        fn name(&self) -> &'static str {
+            "hanging"
+        }
+
+        async fn complete(&self, _prompt: &str, _options: &CompletionOptions) -> Result<String, LlmError> {
+            std::future::pending().await
+        }
+
+        async fn stream(&self, _prompt: &str, _options: &CompletionOptions) -> Result<TokenStream, LlmError> {
+            std::future::pending().await
+        }
+
+        fn cancel(&self) {}
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_a_stream_that_is_still_connecting() {
+        let task_id = format!("stream-{}", uuid::Uuid::new_v4());
+        let stream = {
+            let task_id = task_id.clone();
+            tokio::spawn(async move {
+                stream_completion(&HangingBackend, "prompt", &CompletionOptions::default(), &task_id, "test", &NullEventSink)
+                    .await
+            })
+        };
+
+        while !cancel_stream(&task_id) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let result = tokio::time::timeout(Duration::from_secs(5), stream).await.unwrap().unwrap();
+        assert!(matches!(result, Err(LlmError::Cancelled)), "{:?}", result);
+    }
+}
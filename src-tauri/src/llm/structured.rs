@@ -0,0 +1,222 @@
+use std::future::Future;
+
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::llm::backend::{CompletionOptions, LlmError};
+
+/// How many times a malformed answer is sent back to the model before giving up
+pub const MAX_STRUCTURED_ATTEMPTS: usize = 3;
+/// Characters of a rejected answer, and of its parse error, quoted back in a repair prompt
+const MAX_REJECTED_CHARS: usize = 2000;
+const MAX_ERROR_CHARS: usize = 300;
+const TRUNCATED_MARKER: &str = "[... truncated]";
+
+/// JSON schema derived from the Rust type an agent expects back
+pub fn schema_value<T: JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(T)).unwrap_or(Value::Null)
+}
+
+/// Completion options that constrain decoding to `T`'s schema where the backend supports it
+pub fn structured_options<T: JsonSchema>() -> CompletionOptions {
+    CompletionOptions {
+        temperature: 0.2,
+        json_schema: Some(schema_value::<T>()),
+        ..CompletionOptions::default()
+    }
+}
+
+/// Appends the schema and output rules to an agent prompt
+pub fn with_schema_instructions(prompt: &str, schema: &Value) -> String {
+    format!(
+        "{}\n\nRespond with a single JSON value that validates against this JSON schema. \
+         Do not add explanations or markdown.\n{}\n",
+        prompt.trim_end(),
+        serde_json::to_string_pretty(schema).unwrap_or_default()
+    )
+}
+
+/// Prompt for another attempt after `answer` failed to parse. The rejected answer and the
+/// error are cut to a fixed length, so a repair never adds more than `repair_reserve` does.
+pub fn with_repair_instructions(prompt: &str, answer: &str, error: &str) -> String {
+    format!(
+        "{}\n\nYour previous answer was rejected:\n{}\n\nError: {}\n\
+         Reply again with only the corrected JSON.\n",
+        prompt.trim_end(),
+        truncate_chars(answer.trim(), MAX_REJECTED_CHARS),
+        truncate_chars(error, MAX_ERROR_CHARS)
+    )
+}
+
+/// The longest text `with_repair_instructions` adds to a prompt, for budgeting
+pub fn repair_reserve() -> String {
+    with_repair_instructions("", &"x".repeat(MAX_REJECTED_CHARS + 1), &"x".repeat(MAX_ERROR_CHARS + 1))
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}\n{}", &text[..end], TRUNCATED_MARKER),
+        None => text.to_string(),
+    }
+}
+
+/// Pulls the JSON part out of a model answer: strips code fences and surrounding prose
+pub fn extract_json(text: &str) -> Option<&str> {
+    let text = text.trim();
+    let text = match text.find("```") {
+        Some(start) => {
+            let body = &text[start + 3..];
+            let body = body.trim_start_matches("json").trim_start_matches("JSON");
+            body.find("```").map(|end| &body[..end]).unwrap_or(body).trim()
+        }
+        None => text,
+    };
+
+    let start = text.find(['{', '['])?;
+    let end = text.rfind(['}', ']']).filter(|end| *end >= start);
+    Some(match end {
+        Some(end) => &text[start..=end],
+        None => &text[start..],
+    })
+}
+
+/// Best-effort fixes for the mistakes models commonly make in JSON:
+/// smart quotes, trailing commas and unclosed brackets or strings.
+pub fn repair_json(json: &str) -> String {
+    let json = json
+        .replace(['\u{201c}', '\u{201d}'], "\"")
+        .replace(['\u{2018}', '\u{2019}'], "'");
+
+    let mut repaired = String::with_capacity(json.len());
+    let mut open: Vec<char> = vec![];
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in json.chars() {
+        if in_string {
+            repaired.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' => open.push('}'),
+            '[' => open.push(']'),
+            '}' | ']' => {
+                strip_trailing_comma(&mut repaired);
+                open.pop();
+            }
+            _ => {}
+        }
+        repaired.push(c);
+    }
+
+    if in_string {
+        repaired.push('"');
+    }
+    strip_trailing_comma(&mut repaired);
+    while let Some(close) = open.pop() {
+        repaired.push(close);
+    }
+    repaired
+}
+
+fn strip_trailing_comma(json: &mut String) {
+    let trimmed = json.trim_end().len();
+    if json[..trimmed].ends_with(',') {
+        json.truncate(trimmed - 1);
+    }
+}
+
+/// Parses a model answer into `T`, repairing the JSON if the first parse fails
+pub fn parse_structured<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    let json = extract_json(text).ok_or("Response contains no JSON")?;
+    match serde_json::from_str::<T>(json) {
+        Ok(value) => Ok(value),
+        Err(first) => serde_json::from_str::<T>(&repair_json(json))
+            .map_err(|_| first.to_string()),
+    }
+}
+
+/// Decodes an agent output's content into `T`.
+/// Content may hold the value itself or, from text-producing agents, a string containing JSON.
+pub fn decode_content<T: DeserializeOwned>(content: &Value) -> Result<T, String> {
+    match content {
+        Value::String(text) => parse_structured(text),
+        other => serde_json::from_value(other.clone()).map_err(|e| e.to_string()),
+    }
+}
+
+/// Runs `generate` until its answer parses as `T`. The prompt must already carry the schema
+/// instructions, as `PromptAssembler::assemble_structured` adds them within the budget.
+/// Every failed parse is fed back to the model so the next attempt can correct it.
+pub async fn generate_structured<T, F, Fut>(prompt: &str, generate: F) -> Result<T, LlmError>
+where
+    T: DeserializeOwned + JsonSchema,
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<String, LlmError>>,
+{
+    generate_parsed(prompt, parse_structured::<T>, generate).await
+}
+
+/// Like `generate_structured`, with the answer checked by `parse` rather than a fixed type
+pub async fn generate_parsed<T, P, F, Fut>(prompt: &str, parse: P, mut generate: F) -> Result<T, LlmError>
+where
+    P: Fn(&str) -> Result<T, String>,
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<String, LlmError>>,
+{
+    let mut next_prompt = prompt.to_string();
+    let mut last_error = String::new();
+
+    for attempt in 1..=MAX_STRUCTURED_ATTEMPTS {
+        let answer = generate(next_prompt).await?;
+        match parse(&answer) {
+            Ok(value) => return Ok(value),
+            Err(e) => {
+                println!("[Structured] Attempt {} returned invalid JSON: {}", attempt, e);
+                next_prompt = with_repair_instructions(prompt, &answer, &e);
+                last_error = e;
+            }
+        }
+    }
+
+    Err(LlmError::InvalidResponse(format!(
+        "No valid JSON after {} attempts: {}",
+        MAX_STRUCTURED_ATTEMPTS, last_error
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::backend::LlmBackend;
+    use crate::llm::scripted::ScriptedBackend;
+    use crate::orchestrator::protocol::RequirementList;
+
+    #[tokio::test]
+    async fn repair_prompt_quotes_a_bounded_part_of_the_rejected_answer() {
+        let rejected = format!("{{\"requirements\": \"{}\"", "x".repeat(10 * MAX_REJECTED_CHARS));
+        let backend = ScriptedBackend::new(vec![&rejected, r#"{"requirements": []}"#]);
+
+        let parsed = generate_structured::<RequirementList, _, _>("prompt", |prompt| {
+            let backend = &backend;
+            async move { backend.complete(&prompt, &CompletionOptions::default()).await }
+        })
+        .await
+        .unwrap();
+
+        assert!(parsed.requirements.is_empty());
+        let prompts = backend.prompts();
+        assert_eq!(prompts[0], "prompt");
+        assert!(prompts[1].contains(TRUNCATED_MARKER));
+        assert!(prompts[1].len() <= "prompt".len() + repair_reserve().len());
+    }
+}
@@ -11,14 +11,27 @@ use tauri::{AppHandle, Manager, State};
 use tauri_plugin_shell::ShellExt;
 use tauri_plugin_shell::process::{CommandChild, CommandEvent};
 use portpicker::pick_unused_port;
-use reqwest;
 use tauri::Listener;
 use tauri::Emitter;
+use tokio_util::sync::CancellationToken;
 
+// The binary compiles the library's modules itself and only uses part of each
+#[allow(dead_code)]
 mod config;
+#[allow(dead_code)]
 mod model;
+#[allow(dead_code)]
 mod agents;
+#[allow(dead_code)]
 pub mod orchestrator;
+#[allow(dead_code)]
+mod tools;
+#[allow(dead_code)]
+mod memory;
+#[allow(dead_code)]
+mod llm;
+#[allow(dead_code)]
+mod prompt_assembler;
 
 use crate::config::*;
 use crate::orchestrator::orchestrator::Orchestrator;
@@ -37,31 +50,59 @@ use crate::model::model_installer::{
 };
 use crate::model::model_manager::{get_current_mode, set_current_mode};
 use crate::model::llama_wrapper::run_llama_inference;
-use uuid::uuid;
-use winter_ui_lib::tools::registry::ToolRegistry;
-use crate::memory::task_memory::{TaskMemory, TaskMemoryHandle};
-use crate::memory::session_memory::{SessionMemory, SessionMemoryHandle};
+use crate::model::llama_server::LlamaServerManager;
+use crate::model::model_registry::get_model_download_info;
+use crate::memory::task_memory::TaskMemoryHandle;
+use crate::memory::session_memory::SessionMemoryHandle;
 use crate::memory::project_memory::ProjectMemoryHandle;
-use crate::memory::global_memory::GlobalMemoryHandle;
+use crate::memory::retrieval::refresh_index;
+use crate::memory::global_memory::{
+    add_global_memory, delete_global_memory, list_global_memory_tags, pin_global_memory, query_global_memory, GlobalMemoryHandle,
+};
 use crate::memory::planner_memory::PlannerMemory;
 use crate::orchestrator::protocol::AgentResponse;
-use crate::orchestrator::types::AgentTask;
+use crate::orchestrator::types::{AgentTask, AgentTaskContext, ExecutionMode, TaskStatus};
+use crate::tools::change_set::ChangeSet;
+use crate::tools::path_jail::{JailError, PathJail};
 use crate::orchestrator::agent_loader::register_all_agents;
 use crate::orchestrator::context::AgentContext;
 use crate::orchestrator::tool_loader::register_all_tools;
 use crate::tools::registry::ToolRegistry;
+use crate::llm::backend::backend_from_config;
+use crate::llm::streaming::cancel_inference;
+use crate::tools::change_set::{revert_goal_changes, revert_task_changes};
+use crate::orchestrator::task_index::{compact_task_index, query_task_index};
+use crate::orchestrator::timeline::{get_goal_timeline, list_goal_timelines};
+use crate::orchestrator::cancellation::{cancel_goal, cancel_task};
+use crate::orchestrator::skill_graph::SkillGraphView;
+use crate::orchestrator::approval::{approve_step, list_pending_approvals, reject_step};
+use crate::orchestrator::agent_loader::reload_manifest_agents;
+use crate::orchestrator::manifest::{manifest_dirs, manifest_fingerprint, ManifestReport, AGENT_MANIFESTS_EVENT};
+use crate::orchestrator::checkpoint::{discard_goal_checkpoint, list_resumable_goals, list_unfinished_goals};
+use crate::orchestrator::events::{EventSink, TauriEventSink};
 
 struct BackendState(pub Arc<Mutex<Option<CommandChild>>>);
 static ONCE_INIT: OnceLock<()> = OnceLock::new();
+/// How often the agent manifest directories are checked for changes
+const MANIFEST_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Orchestrator and its shared memory context, created once at startup.
+pub struct OrchestratorState {
+    pub orchestrator: Arc<Orchestrator>,
+    /// Base context cloned into every run; updated when a project is opened
+    pub context: Mutex<AgentContext>,
+}
 
 #[tauri::command]
-pub fn run_orchestrator_task(
+async fn run_orchestrator_task(
     task_type: String,
     payload: String,
-    orchestrator: State<'_, Orchestrator>
+    execution_mode: Option<ExecutionMode>,
+    skip_prerequisites: Option<bool>,
+    state: State<'_, OrchestratorState>
 ) -> Result<String,String>{
-
-    let (orchestrator, context) = setup_orchestrator();
+    let orchestrator = state.orchestrator.clone();
+    let context = state.context.lock().unwrap().clone();
 
     let task = AgentTask{
         task_id: uuid::Uuid::new_v4().to_string(),
@@ -69,29 +110,103 @@ pub fn run_orchestrator_task(
         payload,
         context: AgentTaskContext{
             origin: "user".into(),
-            goal_id: None,
+            // Every user request is a goal, so its timeline, checkpoint and cancellation share one id
+            goal_id: Some(uuid::Uuid::new_v4().to_string()),
             parent_task_id: None,
             retry_of: None,
+            revision_id: None,
+            execution_mode,
+            dependency_outputs: Default::default(),
+            timeout_secs: None,
+            attempt: 0,
+            skip_prerequisites: skip_prerequisites.unwrap_or(false),
         },
+        status: TaskStatus::Pending,
+        depends_on: vec![],
     };
-    match orchestrator.handle(task,context) {
+    match orchestrator.handle(task,context).await {
         AgentResponse::Success(output) => Ok(output.content.to_string()),
-        AgentResponse::Error(err)=> Err(err),
+        AgentResponse::Error(err)=> Err(err.reason),
     }
 }
 
+/// Continues a goal that was interrupted, e.g. by quitting Winter mid-plan
+#[tauri::command]
+async fn resume_goal(
+    goal_id: String,
+    plan_id: Option<String>,
+    state: State<'_, OrchestratorState>,
+) -> Result<String, String> {
+    let orchestrator = state.orchestrator.clone();
+    let context = state.context.lock().unwrap().clone();
+
+    match orchestrator.resume_goal(&goal_id, plan_id.as_deref(), context).await {
+        AgentResponse::Success(output) => Ok(output.content.to_string()),
+        AgentResponse::Error(err) => Err(err.reason),
+    }
+}
+
+/// Reloads the agent manifests of WinterData and the open project
+#[tauri::command]
+fn reload_agent_manifests(app: AppHandle, state: State<'_, OrchestratorState>) -> ManifestReport {
+    let (project_root, tools) = manifest_scope(&state);
+    let report = reload_manifest_agents(&state.orchestrator, project_root.as_deref(), &tools);
+    app.emit(AGENT_MANIFESTS_EVENT, &report).ok();
+    report
+}
+
+/// Open project root and registered tool names, which manifests are validated against
+fn manifest_scope(state: &OrchestratorState) -> (Option<PathBuf>, Vec<String>) {
+    let context = state.context.lock().unwrap();
+    (
+        context.sandbox.as_ref().map(|jail| jail.root().to_path_buf()),
+        context.tool_registry.all(),
+    )
+}
+
+/// Reloads the manifest agents whenever a manifest or template changes or another project is opened
+async fn watch_agent_manifests(app: AppHandle) {
+    let mut last_fingerprint = None;
+    loop {
+        let state = app.state::<OrchestratorState>();
+        let (project_root, tools) = manifest_scope(&state);
+        let fingerprint = manifest_fingerprint(&manifest_dirs(project_root.as_deref()));
+
+        if last_fingerprint.as_ref() != Some(&(project_root.clone(), fingerprint.clone())) {
+            let report = reload_manifest_agents(&state.orchestrator, project_root.as_deref(), &tools);
+            app.emit(AGENT_MANIFESTS_EVENT, &report).ok();
+            last_fingerprint = Some((project_root, fingerprint));
+        }
+
+        tokio::time::sleep(MANIFEST_POLL_INTERVAL).await;
+    }
+}
+
+/// Capability prerequisite graph with the agents registered for each capability
+#[tauri::command]
+fn get_skill_graph(state: State<'_, OrchestratorState>) -> SkillGraphView {
+    state.orchestrator.skill_graph()
+}
+
 #[tauri::command]
 fn list_project_files(project_path: String) -> Result<Vec<String>, String> {
     let root = PathBuf::from(project_path);
     let mut files = vec![];
 
     if root.exists() {
-        for entry in walkdir::WalkDir::new(&root)
+        let jail = PathJail::new(&root).map_err(|e| e.to_string())?;
+        for entry in walkdir::WalkDir::new(jail.root())
             .into_iter()
+            .filter_entry(|e| {
+                e.path()
+                    .strip_prefix(jail.root())
+                    .map(|relative| !jail.is_denied(relative))
+                    .unwrap_or(false)
+            })
             .filter_map(Result::ok)
             .filter(|e| e.file_type().is_file())
         {
-            if let Ok(relative) = entry.path().strip_prefix(&root) {
+            if let Ok(relative) = entry.path().strip_prefix(jail.root()) {
                 files.push(relative.to_string_lossy().to_string());
             }
         }
@@ -102,24 +217,57 @@ fn list_project_files(project_path: String) -> Result<Vec<String>, String> {
 }
 
 #[tauri::command]
-fn read_file(project_path: String, relative_path: String) -> Result<String, String> {
-    let full_path = PathBuf::from(&project_path).join(&relative_path);
-    fs::read_to_string(&full_path).map_err(|e| e.to_string())
+fn read_file(project_path: String, relative_path: String) -> Result<String, JailError> {
+    let full_path = PathJail::new(&project_path)?.resolve(&relative_path)?;
+    Ok(fs::read_to_string(&full_path)?)
 }
 
 #[tauri::command]
-fn write_file(project_path: String, relative_path: String, content: String) -> Result<(), String> {
-    let full_path = PathBuf::from(&project_path).join(&relative_path);
-    fs::write(&full_path, content).map_err(|e| e.to_string())
+fn write_file(project_path: String, relative_path: String, content: String) -> Result<(), JailError> {
+    let full_path = PathJail::new(&project_path)?.resolve(&relative_path)?;
+    Ok(fs::write(&full_path, content)?)
 }
 
 #[tauri::command]
-fn open_project(project_path: String) -> Result<(), String> {
+fn open_project(project_path: String, state: State<'_, OrchestratorState>) -> Result<(), String> {
+    let jail = PathJail::new(&project_path).map_err(|e| e.to_string())?;
     set_last_opened_project(project_path.clone())
         .map_err(|e| format!("Failed to update config: {}", e))?;
+
+    let mut context = state.context.lock().unwrap();
+    context
+        .project
+        .open(jail.root())
+        .map_err(|e| format!("Failed to load project memory: {}", e))?;
+
+    // Build or update the retrieval index in the background
+    let project_root = jail.root().to_path_buf();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = refresh_index(&project_root).await {
+            eprintln!("[warn] Failed to index {}: {}", project_root.display(), e);
+        }
+    });
+
+    context.sandbox = Some(Arc::new(jail));
     Ok(())
 }
 
+/// Re-indexes the files of the open project that changed; returns how many were re-indexed
+#[tauri::command]
+async fn reindex_project(state: State<'_, OrchestratorState>) -> Result<usize, String> {
+    let project_root = state
+        .context
+        .lock()
+        .unwrap()
+        .sandbox
+        .as_ref()
+        .map(|jail| jail.root().to_path_buf())
+        .ok_or("No project is open")?;
+    refresh_index(&project_root)
+        .await
+        .map_err(|e| format!("Failed to index project: {}", e))
+}
+
 #[tauri::command]
 fn get_recent_projects() -> Result<Vec<String>, String> {
     let config = load_config().map_err(|e| format!("Failed to load config: {}", e))?;
@@ -133,7 +281,7 @@ fn mark_last_opened_project(path: String) -> Result<(), String> {
 
 #[tauri::command]
 fn get_recommended_model() -> ModelChoice {
-    model_selector::pick_optimal_model()
+    crate::model::model_selector::pick_optimal_model()
 }
 
 async fn wait_for_backend_ready_async(app_handle: AppHandle, port: u16) {
@@ -181,6 +329,7 @@ async fn wait_for_backend_ready_async(app_handle: AppHandle, port: u16) {
     let _ = app_handle.emit("sidecar-log", "⚠️ Backend did not respond.".to_string());
 }
 
+#[allow(dead_code)] // Disabled in setup until the embedded server is bundled again
 fn spawn_and_monitor_embedded_server(app_handle: AppHandle, port: u16) -> Result<(), String> {
     let cloned_handle = app_handle.clone();
     let state = app_handle.state::<BackendState>();
@@ -233,21 +382,53 @@ fn shutdown_sidecar(app_handle: AppHandle) -> Result<String, String> {
     }
 }
 
-pub fn setup_orchestrator() -> (Orchestrator, AgentContext) {
+pub fn setup_orchestrator(events: Arc<dyn EventSink>) -> (Orchestrator, AgentContext) {
     let mut orchestrator = Orchestrator::new();
     let mut raw_tool_registry = ToolRegistry::new();
 
+    let config = load_config().unwrap_or_else(|_| AppConfig {
+        mode: None,
+        last_opened_project: None,
+        recent_projects: vec![],
+        paths: AppPaths {
+            projects: "".into(),
+            uploads: "".into(),
+        },
+        model_file_size_estimate: None,
+        cached_model_info: None,
+        llm: LlmSettings::default(),
+    });
+    let llm = backend_from_config(&config);
+
     // Register tools before creating context
-    register_all_tools(&mut raw_tool_registry);
+    register_all_tools(&mut raw_tool_registry, llm.clone());
     register_all_agents(&mut orchestrator);
 
+    let sandbox = config
+        .last_opened_project
+        .as_ref()
+        .and_then(|path| PathJail::new(path).ok())
+        .map(Arc::new);
+    let project = ProjectMemoryHandle::new();
+    if let Some(jail) = &sandbox {
+        if let Err(e) = project.open(jail.root()) {
+            eprintln!("[warn] Failed to load project memory: {}", e);
+        }
+    }
+
     let context = AgentContext {
-        task: TaskMemoryHandle::new(),
-        session: SessionMemoryHandle::new(),
-        project: ProjectMemoryHandle::new(),
-        global: GlobalMemoryHandle::new(),
+        task: TaskMemoryHandle::from(&orchestrator.task_memory),
+        session: SessionMemoryHandle::from(&orchestrator.session_memory),
+        project,
+        global: GlobalMemoryHandle::shared(),
         tool_registry: Arc::new(raw_tool_registry), // Now fully initialized
         planner_memory: PlannerMemory::new(),
+        llm,
+        events,
+        execution_mode: ExecutionMode::Simulate,
+        changes: ChangeSet::new(),
+        sandbox,
+        cancel: CancellationToken::new(),
     };
 
     (orchestrator, context)
@@ -271,10 +452,30 @@ fn main() {
                 },
                 model_file_size_estimate: None,
                 cached_model_info: None,
+                llm: LlmSettings::default(),
             });
 
             app.manage(config.clone());
 
+            // Startup Orchestrator
+            let (orchestrator, context) =
+                setup_orchestrator(Arc::new(TauriEventSink(app.handle().clone())));
+            app.manage(OrchestratorState {
+                orchestrator: Arc::new(orchestrator),
+                context: Mutex::new(context),
+            });
+
+            // Load manifest-defined agents and keep them in sync with their files
+            tauri::async_runtime::spawn(watch_agent_manifests(app.handle().clone()));
+
+            // The frontend asks for them with `list_resumable_goals` once its window is ready
+            match list_unfinished_goals() {
+                Ok(goals) if !goals.is_empty() => {
+                    println!("[Winter] {} unfinished goal(s) can be resumed", goals.len());
+                }
+                _ => {}
+            }
+
             if config.mode.is_none() {
                 // First launch – show install screen
                 if let Some(install_window) = app.get_webview_window("install") {
@@ -308,9 +509,6 @@ fn main() {
             app.manage(BackendState(Arc::new(Mutex::new(None))));
             app.manage(port);
 
-            // Startup Orchestrator
-            let orchestrator = setup_orchestrator();
-
            // spawn_and_monitor_embedded_server(app.handle().clone(), port)?;
             Ok(())
         })
@@ -348,10 +546,43 @@ fn main() {
             set_current_mode,
             get_free_disk_space,
             run_llama_inference,
+            run_orchestrator_task,
+            cancel_inference,
+            revert_task_changes,
+            revert_goal_changes,
+            query_task_index,
+            compact_task_index,
+            get_goal_timeline,
+            list_goal_timelines,
+            list_resumable_goals,
+            resume_goal,
+            discard_goal_checkpoint,
+            cancel_task,
+            cancel_goal,
+            get_skill_graph,
+            reload_agent_manifests,
+            reindex_project,
+            query_global_memory,
+            add_global_memory,
+            list_global_memory_tags,
+            pin_global_memory,
+            delete_global_memory,
+            approve_step,
+            reject_step,
+            list_pending_approvals,
         ])
         .build(tauri::generate_context!())
         .expect("error while running Tauri application")
-        .run(|app, _| {
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Abort in-flight model requests before their servers go away
+                if let Some(state) = app.try_state::<OrchestratorState>() {
+                    state.context.lock().unwrap().llm.cancel();
+                }
+                LlamaServerManager::shutdown_global();
+                return;
+            }
+
             if let Some(config) = app.try_state::<AppConfig>() {
                 if let Some(last_path) = &config.last_opened_project {
                     if let Some(main) = app.get_webview_window("main") {
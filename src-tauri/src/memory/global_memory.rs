@@ -1,41 +1,528 @@
 use serde::{Serialize, Deserialize};
-use std::sync::{Arc, Mutex};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::orchestrator::types::now_timestamp;
+
+/// Cross-project memory lives in `WinterData/memory/`
+const MEMORY_DIR: &str = "WinterData/memory";
+const ENTRIES_FILE: &str = "global_memory.jsonl";
+
+static SHARED: OnceLock<GlobalMemoryHandle> = OnceLock::new();
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalMemoryEntry {
+    #[serde(default = "new_entry_id")]
+    pub id: String,
     pub tags: Vec<String>,
     pub source: String,
     pub content: String,
+    /// Unix seconds, as a string
     pub timestamp: String,
+    /// Where the entry came from, e.g. `project:/path/to/app#goal:<id>#task:<id>`
+    pub context_link: Option<String>,
+    /// Pinned entries are listed first and cannot be deleted until unpinned
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+fn new_entry_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+impl GlobalMemoryEntry {
+    pub fn new(tags: Vec<String>, source: &str, content: &str, context_link: Option<String>) -> Self {
+        Self {
+            id: new_entry_id(),
+            tags,
+            source: source.to_string(),
+            content: content.to_string(),
+            timestamp: now_timestamp().to_string(),
+            context_link,
+            pinned: false,
+        }
+    }
+
+    fn created_at(&self) -> u64 {
+        self.timestamp.parse().unwrap_or(0)
+    }
+
+    fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// Provenance link for an entry learned while working on a project task
+pub fn context_link(project_root: Option<&str>, goal_id: Option<&str>, task_id: Option<&str>) -> Option<String> {
+    let parts: Vec<String> = [("project", project_root), ("goal", goal_id), ("task", task_id)]
+        .into_iter()
+        .filter_map(|(kind, value)| value.map(|value| format!("{}:{}", kind, value)))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("#"))
+}
+
+/// Compound tag filter, e.g. `rust AND (cli OR tauri) AND NOT draft`.
+/// NOT binds tightest, then AND, then OR; adjacent terms are ANDed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagQuery {
+    Tag(String),
+    And(Vec<TagQuery>),
+    Or(Vec<TagQuery>),
+    Not(Box<TagQuery>),
+}
+
+impl TagQuery {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize_query(input);
+        let mut parser = QueryParser { tokens, position: 0 };
+        let query = parser.or()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(query),
+            Some(token) => Err(format!("Unexpected '{}' in tag query", token)),
+        }
+    }
+
+    pub fn matches(&self, entry: &GlobalMemoryEntry) -> bool {
+        match self {
+            TagQuery::Tag(tag) => entry.has_tag(tag),
+            TagQuery::And(queries) => queries.iter().all(|q| q.matches(entry)),
+            TagQuery::Or(queries) => queries.iter().any(|q| q.matches(entry)),
+            TagQuery::Not(query) => !query.matches(entry),
+        }
+    }
+}
+
+fn tokenize_query(input: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    for ch in input.chars() {
+        if ch == '(' || ch == ')' || ch.is_whitespace() {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if !ch.is_whitespace() {
+                tokens.push(ch.to_string());
+            }
+        } else {
+            current.push(ch);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+struct QueryParser {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl QueryParser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|t| t.as_str())
+    }
+
+    fn or(&mut self) -> Result<TagQuery, String> {
+        let mut terms = vec![self.and()?];
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("OR")) {
+            self.position += 1;
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { TagQuery::Or(terms) })
+    }
+
+    fn and(&mut self) -> Result<TagQuery, String> {
+        let mut terms = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                Some(t) if t.eq_ignore_ascii_case("AND") => {
+                    self.position += 1;
+                    terms.push(self.unary()?);
+                }
+                Some(t) if t != ")" && !t.eq_ignore_ascii_case("OR") => terms.push(self.unary()?),
+                _ => break,
+            }
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { TagQuery::And(terms) })
+    }
+
+    fn unary(&mut self) -> Result<TagQuery, String> {
+        let token = self
+            .peek()
+            .map(String::from)
+            .ok_or_else(|| "Tag query ends unexpectedly".to_string())?;
+        self.position += 1;
+        match token.as_str() {
+            "(" => {
+                let query = self.or()?;
+                if self.peek() != Some(")") {
+                    return Err("Missing ')' in tag query".into());
+                }
+                self.position += 1;
+                Ok(query)
+            }
+            ")" => Err("Unexpected ')' in tag query".into()),
+            t if t.eq_ignore_ascii_case("NOT") => Ok(TagQuery::Not(Box::new(self.unary()?))),
+            t if t.eq_ignore_ascii_case("AND") || t.eq_ignore_ascii_case("OR") => {
+                Err(format!("Expected a tag before '{}'", t))
+            }
+            tag => Ok(TagQuery::Tag(tag.to_string())),
+        }
+    }
+}
+
+/// Filters for browsing global memory; every set field must match
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct MemoryQuery {
+    /// Compound tag query, see `TagQuery`
+    pub tags: Option<String>,
+    /// Words that must all appear in the content, case-insensitive
+    pub text: Option<String>,
+    /// Unix seconds, inclusive
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// Prefix of the provenance link, e.g. `project:/path/to/app`
     pub context_link: Option<String>,
+    pub pinned_only: bool,
+    pub limit: Option<usize>,
+}
+
+/// Long-term memory shared by every project.
+/// Persisted to `WinterData/memory/global_memory.jsonl` when opened with `shared`.
+#[derive(Debug, Default)]
+pub struct GlobalMemory {
+    /// None for a session-only memory
+    path: Option<PathBuf>,
+    entries: Vec<GlobalMemoryEntry>,
+}
+
+impl GlobalMemory {
+    fn load() -> io::Result<Self> {
+        let folder = dirs::home_dir().expect("No home dir").join(MEMORY_DIR);
+        fs::create_dir_all(&folder)?;
+        let path = folder.join(ENTRIES_FILE);
+        let entries = match fs::read_to_string(&path) {
+            Ok(data) => data
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .filter_map(|line| match serde_json::from_str(line) {
+                    Ok(entry) => Some(entry),
+                    Err(e) => {
+                        eprintln!("[warn] Skipping invalid global memory entry: {}", e);
+                        None
+                    }
+                })
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path: Some(path), entries })
+    }
+
+    fn append(&self, entry: &GlobalMemoryEntry) -> io::Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Writes `entries` in place of the file and then adopts them, so a failed write leaves
+    /// both the file and the entries in memory as they were
+    fn replace(&mut self, entries: Vec<GlobalMemoryEntry>) -> io::Result<()> {
+        if let Some(path) = &self.path {
+            let mut data = String::new();
+            for entry in &entries {
+                data.push_str(&serde_json::to_string(entry)?);
+                data.push('\n');
+            }
+            let temp = path.with_extension("jsonl.tmp");
+            fs::write(&temp, data)?;
+            fs::rename(temp, path)?;
+        }
+        self.entries = entries;
+        Ok(())
+    }
 }
 
 #[derive(Default, Debug, Clone)]
-pub struct GlobalMemoryHandle(pub Arc<Mutex<Vec<GlobalMemoryEntry>>>);
+pub struct GlobalMemoryHandle(pub Arc<Mutex<GlobalMemory>>);
 
 impl GlobalMemoryHandle {
+    /// Session-only memory that is never written to disk
     pub fn new() -> Self {
-        GlobalMemoryHandle(Arc::new(Mutex::new(Vec::new())))
+        Self::default()
     }
 
-    pub fn insert(&self, entry: GlobalMemoryEntry) {
-        if let Ok(mut vec) = self.0.lock() {
-            vec.push(entry);
+    /// The persistent memory under WinterData, loaded once and shared by every holder
+    pub fn shared() -> Self {
+        SHARED
+            .get_or_init(|| {
+                let memory = GlobalMemory::load().unwrap_or_else(|e| {
+                    eprintln!("[warn] Failed to load global memory, keeping it in memory only: {}", e);
+                    GlobalMemory::default()
+                });
+                println!("[GlobalMemory] Loaded {} entries", memory.entries.len());
+                GlobalMemoryHandle(Arc::new(Mutex::new(memory)))
+            })
+            .clone()
+    }
+
+    /// Stores the entry and returns its id. An entry with the same content is not stored twice;
+    /// its tags are merged into the existing entry, which keeps its id and provenance.
+    pub fn insert(&self, entry: GlobalMemoryEntry) -> io::Result<String> {
+        let mut memory = self.0.lock().unwrap();
+        let normalized = normalize(&entry.content);
+        if let Some(index) = memory
+            .entries
+            .iter()
+            .position(|existing| normalize(&existing.content) == normalized)
+        {
+            let mut entries = memory.entries.clone();
+            let existing = &mut entries[index];
+            let id = existing.id.clone();
+            let mut changed = false;
+            for tag in entry.tags {
+                if !existing.has_tag(&tag) {
+                    existing.tags.push(tag);
+                    changed = true;
+                }
+            }
+            if existing.context_link.is_none() && entry.context_link.is_some() {
+                existing.context_link = entry.context_link;
+                changed = true;
+            }
+            if changed {
+                memory.replace(entries)?;
+            }
+            return Ok(id);
         }
+
+        memory.append(&entry)?;
+        let id = entry.id.clone();
+        memory.entries.push(entry);
+        Ok(id)
     }
 
     pub fn search_by_tag(&self, tag: &str) -> Vec<GlobalMemoryEntry> {
         self.0.lock()
-            .map(|vec| {
-                vec.iter()
-                    .filter(|e| e.tags.contains(&tag.to_string()))
+            .map(|memory| {
+                memory
+                    .entries
+                    .iter()
+                    .filter(|e| e.has_tag(tag))
                     .cloned()
                     .collect()
             })
             .unwrap_or_default()
     }
 
+    /// Entries matching the query, pinned first and then newest first
+    pub fn query(&self, query: &MemoryQuery) -> Result<Vec<GlobalMemoryEntry>, String> {
+        let tags = query.tags.as_deref().filter(|t| !t.trim().is_empty()).map(TagQuery::parse).transpose()?;
+        let words: Vec<String> = query
+            .text
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|word| word.to_lowercase())
+            .collect();
+
+        let memory = self.0.lock().unwrap();
+        let mut entries: Vec<GlobalMemoryEntry> = memory
+            .entries
+            .iter()
+            .filter(|e| !query.pinned_only || e.pinned)
+            .filter(|e| tags.as_ref().is_none_or(|tags| tags.matches(e)))
+            .filter(|e| query.since.is_none_or(|since| e.created_at() >= since))
+            .filter(|e| query.until.is_none_or(|until| e.created_at() <= until))
+            .filter(|e| {
+                query.context_link.as_deref().is_none_or(|prefix| {
+                    e.context_link.as_deref().is_some_and(|link| link.starts_with(prefix))
+                })
+            })
+            .filter(|e| {
+                let content = e.content.to_lowercase();
+                words.iter().all(|word| content.contains(word.as_str()))
+            })
+            .cloned()
+            .collect();
+
+        entries.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(b.created_at().cmp(&a.created_at())));
+        if let Some(limit) = query.limit {
+            entries.truncate(limit);
+        }
+        Ok(entries)
+    }
+
+    pub fn set_pinned(&self, id: &str, pinned: bool) -> Result<(), String> {
+        let mut memory = self.0.lock().unwrap();
+        let mut entries = memory.entries.clone();
+        let entry = entries
+            .iter_mut()
+            .find(|e| e.id == id)
+            .ok_or_else(|| format!("No global memory entry {}", id))?;
+        entry.pinned = pinned;
+        memory
+            .replace(entries)
+            .map_err(|e| format!("Failed to save global memory: {}", e))
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let mut memory = self.0.lock().unwrap();
+        let index = memory
+            .entries
+            .iter()
+            .position(|e| e.id == id)
+            .ok_or_else(|| format!("No global memory entry {}", id))?;
+        if memory.entries[index].pinned {
+            return Err(format!("Global memory entry {} is pinned; unpin it first", id));
+        }
+        let mut entries = memory.entries.clone();
+        entries.remove(index);
+        memory
+            .replace(entries)
+            .map_err(|e| format!("Failed to save global memory: {}", e))
+    }
+
+    /// Every tag in use, for building queries
+    pub fn tags(&self) -> Vec<String> {
+        let memory = self.0.lock().unwrap();
+        let mut seen = HashSet::new();
+        let mut tags: Vec<String> = memory
+            .entries
+            .iter()
+            .flat_map(|e| e.tags.iter())
+            .filter(|tag| seen.insert(tag.to_lowercase()))
+            .cloned()
+            .collect();
+        tags.sort_by_key(|tag| tag.to_lowercase());
+        tags
+    }
+
     pub fn all(&self) -> Option<Vec<GlobalMemoryEntry>> {
-        self.0.lock().ok().map(|v| v.clone())
+        self.0.lock().ok().map(|memory| memory.entries.clone())
     }
-}
\ No newline at end of file
+}
+
+/// Content compared for deduplication: trimmed, lowercase, whitespace collapsed
+fn normalize(content: &str) -> String {
+    content.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+#[tauri::command]
+pub fn query_global_memory(query: Option<MemoryQuery>) -> Result<Vec<GlobalMemoryEntry>, String> {
+    GlobalMemoryHandle::shared().query(&query.unwrap_or_default())
+}
+
+/// Stores a note from the user; returns the id of the new or matching entry
+#[tauri::command]
+pub fn add_global_memory(tags: Vec<String>, content: String, context_link: Option<String>) -> Result<String, String> {
+    if content.trim().is_empty() {
+        return Err("Global memory entry is empty".into());
+    }
+    GlobalMemoryHandle::shared()
+        .insert(GlobalMemoryEntry::new(tags, "user", &content, context_link))
+        .map_err(|e| format!("Failed to save global memory: {}", e))
+}
+
+#[tauri::command]
+pub fn list_global_memory_tags() -> Vec<String> {
+    GlobalMemoryHandle::shared().tags()
+}
+
+#[tauri::command]
+pub fn pin_global_memory(id: String, pinned: bool) -> Result<(), String> {
+    GlobalMemoryHandle::shared().set_pinned(&id, pinned)
+}
+
+#[tauri::command]
+pub fn delete_global_memory(id: String) -> Result<(), String> {
+    GlobalMemoryHandle::shared().delete(&id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str) -> TagQuery {
+        TagQuery::Tag(name.into())
+    }
+
+    fn entry(tags: &[&str]) -> GlobalMemoryEntry {
+        GlobalMemoryEntry::new(tags.iter().map(|t| t.to_string()).collect(), "test", "content", None)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            TagQuery::parse("a OR b AND c").unwrap(),
+            TagQuery::Or(vec![tag("a"), TagQuery::And(vec![tag("b"), tag("c")])])
+        );
+        assert_eq!(
+            TagQuery::parse("a b or c").unwrap(),
+            TagQuery::Or(vec![TagQuery::And(vec![tag("a"), tag("b")]), tag("c")])
+        );
+    }
+
+    #[test]
+    fn not_binds_tightest() {
+        assert_eq!(
+            TagQuery::parse("NOT a AND b").unwrap(),
+            TagQuery::And(vec![TagQuery::Not(Box::new(tag("a"))), tag("b")])
+        );
+        assert_eq!(
+            TagQuery::parse("not not a").unwrap(),
+            TagQuery::Not(Box::new(TagQuery::Not(Box::new(tag("a")))))
+        );
+    }
+
+    #[test]
+    fn parentheses_group() {
+        let query = TagQuery::parse("rust AND (cli OR tauri) AND NOT draft").unwrap();
+        assert_eq!(
+            query,
+            TagQuery::And(vec![
+                tag("rust"),
+                TagQuery::Or(vec![tag("cli"), tag("tauri")]),
+                TagQuery::Not(Box::new(tag("draft"))),
+            ])
+        );
+        assert!(query.matches(&entry(&["Rust", "tauri"])));
+        assert!(!query.matches(&entry(&["rust", "cli", "draft"])));
+        assert!(!query.matches(&entry(&["rust"])));
+    }
+
+    #[test]
+    fn malformed_queries_are_rejected() {
+        for input in ["", "(a OR b", "a)", "AND a", "a OR", "NOT", "()"] {
+            assert!(TagQuery::parse(input).is_err(), "{:?} should not parse", input);
+        }
+    }
+
+    #[test]
+    fn failed_write_keeps_entries_unchanged() {
+        let dir = std::env::temp_dir().join(format!("winter-global-memory-{}", uuid::Uuid::new_v4()));
+        let memory = GlobalMemory {
+            // The file cannot be written because its directory does not exist
+            path: Some(dir.join(ENTRIES_FILE)),
+            entries: vec![entry(&["a"])],
+        };
+        let id = memory.entries[0].id.clone();
+        let handle = GlobalMemoryHandle(Arc::new(Mutex::new(memory)));
+
+        assert!(handle.set_pinned(&id, true).is_err());
+        assert!(handle.delete(&id).is_err());
+        let entries = handle.all().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(!entries[0].pinned);
+    }
+}
@@ -3,4 +3,5 @@ pub mod session_memory;
 pub mod project_memory;
 pub mod global_memory;
 pub mod planner_memory;
-pub mod runtime_memory;
\ No newline at end of file
+pub mod retrieval;
+pub mod summarizer;
@@ -1,42 +1,287 @@
-use std::fs;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use anyhow::{Result, Context};
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
 
-pub struct ProjectMemory{
-    pub root: PathBuf, // e.g ~/WinterData/projects/{project_id}/
+use crate::llm::backend::LlmBackend;
+use crate::memory::summarizer::{self, content_hash};
+use crate::tools::path_jail::PathJail;
+
+const MEMORY_DIR: &str = ".winter";
+const ARCHITECTURE_FILE: &str = "architecture.json";
+const REQUIREMENTS_FILE: &str = "requirements.json";
+const DECISIONS_FILE: &str = "decisions.jsonl";
+const FILE_SUMMARIES_FILE: &str = "file_summaries.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesignDecision {
+    pub id: String,
+    pub summary: String,
+    pub made_by: String,
+    pub rationale: String,
+    pub timestamp: String,
+}
+
+/// A cached summary of a memory file for one scope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSummary {
+    pub scope: String,
+    pub summary: String,
+    /// SHA-256 of the content that was summarized; a different hash means the summary is stale
+    pub content_hash: String,
 }
 
-impl ProjectMemory{
-    /// Read a memory file as string
-    pub fn read(&self, relative_path: &str) -> Result<String> {
-        let full_path = self.root.join(relative_path);
-        fs::read_to_string(&full_path)
-            .with_context(|| format!("Failed to read memory file: {}", full_path.display()))
+/// What Winter knows about the open project. Persisted under `<project>/.winter/`
+/// once a project is bound; before that it only lives for the session.
+#[derive(Debug, Default, Clone)]
+pub struct ProjectMemory {
+    /// `<project>/.winter`, None until a project is opened
+    pub root: Option<PathBuf>,
+    pub architecture: Option<String>,
+    pub requirements: Option<String>,
+    pub decisions: Vec<DesignDecision>,
+    /// Summaries keyed by `path` or `path#scope`
+    pub file_summaries: HashMap<String, FileSummary>,
+}
+
+impl ProjectMemory {
+    /// Loads the memory stored in `<project_root>/.winter/`; missing files start out empty
+    pub fn load(project_root: &Path) -> io::Result<Self> {
+        let root = project_root.join(MEMORY_DIR);
+        fs::create_dir_all(&root)?;
+
+        let read = |name: &str| fs::read_to_string(root.join(name)).ok();
+        let decisions = read(DECISIONS_FILE)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        let file_summaries = read(FILE_SUMMARIES_FILE)
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+
+        Ok(Self {
+            architecture: read(ARCHITECTURE_FILE),
+            requirements: read(REQUIREMENTS_FILE),
+            decisions,
+            file_summaries,
+            root: Some(root),
+        })
     }
-    /// Return a stubbed summary of a memory file
-    pub fn summarize(&self,relative_path: &str,_scope: &str)-> Result<String>{
-        let full_path = self.root.join(relative_path);
-        let content = fs::read_to_string(&full_path)
-            .with_context(|| format!("Failed to summarize file: {}",full_path.display()))?;
 
-        // ✨ In future: Use SummarizeTool (LLM). For now, return first N lines.
-        let summary: String = content.lines().take(10).collect::<Vec<_>>().join("\n");
-        Ok(format!("(Stub Summary)\n{}", summary))
+    /// Resolves a path relative to `.winter/`, rejecting any that lead outside it
+    fn resolve(root: &Path, relative_path: &str) -> io::Result<PathBuf> {
+        PathJail::new(root)
+            .and_then(|jail| jail.resolve(relative_path))
+            .map_err(|e| io::Error::new(io::ErrorKind::PermissionDenied, e.to_string()))
     }
 
-    /// Chunk a memory file into parts by line count(approx)
-    pub fn chunk(&self,relative_path: &str, lines_per_chunk: usize) -> Result<Vec<String>> {
-        let full_path = self.root.join(relative_path);
-        let content = fs::read_to_string(&full_path)
-            .with_context(|| format!("Failed to chunk file: {}", full_path.display()))?;
+    /// Writes a file under `.winter/`; a no-op while no project is bound
+    fn persist(&self, relative_path: &str, content: &str) -> io::Result<()> {
+        match &self.root {
+            Some(root) => {
+                let path = Self::resolve(root, relative_path)?;
+                if let Some(folder) = path.parent() {
+                    fs::create_dir_all(folder)?;
+                }
+                fs::write(path, content)
+            }
+            None => Ok(()),
+        }
+    }
+}
 
-        let lines: Vec<_> = content.lines().collect();
-        let mut chunks = vec![];
+/// Shared handle to the project memory, cloned into every `AgentContext`.
+/// `open` rebinds it in place, so every holder sees the newly opened project.
+#[derive(Clone, Default)]
+pub struct ProjectMemoryHandle(pub Arc<Mutex<ProjectMemory>>);
+
+impl ProjectMemoryHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds the memory to a project and loads what was stored for it
+    pub fn open(&self, project_root: &Path) -> io::Result<()> {
+        let memory = ProjectMemory::load(project_root)?;
+        println!(
+            "[ProjectMemory] Loaded {} decision(s) and {} file summaries for {}",
+            memory.decisions.len(),
+            memory.file_summaries.len(),
+            project_root.display()
+        );
+        *self.0.lock().unwrap() = memory;
+        Ok(())
+    }
 
-        for chunk in lines.chunks(lines_per_chunk){
-            chunks.push(chunk.join("\n"));
+    /// `<project>/.winter`, if a project is open
+    pub fn root(&self) -> Option<PathBuf> {
+        self.0.lock().unwrap().root.clone()
+    }
+
+    pub fn architecture(&self) -> Option<String> {
+        self.0.lock().unwrap().architecture.clone()
+    }
+
+    pub fn set_architecture(&self, architecture: &str) -> io::Result<()> {
+        let mut memory = self.0.lock().unwrap();
+        memory.architecture = Some(architecture.to_string());
+        memory.persist(ARCHITECTURE_FILE, architecture)
+    }
+
+    pub fn requirements(&self) -> Option<String> {
+        self.0.lock().unwrap().requirements.clone()
+    }
+
+    pub fn set_requirements(&self, requirements: &str) -> io::Result<()> {
+        let mut memory = self.0.lock().unwrap();
+        memory.requirements = Some(requirements.to_string());
+        memory.persist(REQUIREMENTS_FILE, requirements)
+    }
+
+    pub fn decisions(&self) -> Vec<DesignDecision> {
+        self.0.lock().unwrap().decisions.clone()
+    }
+
+    /// Appends the decision to `decisions.jsonl`
+    pub fn write_decision(&self, decision: DesignDecision) -> io::Result<()> {
+        let mut memory = self.0.lock().unwrap();
+        if let Some(root) = &memory.root {
+            let mut line = serde_json::to_string(&decision)?;
+            line.push('\n');
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(root.join(DECISIONS_FILE))?
+                .write_all(line.as_bytes())?;
         }
-        Ok(chunks)
+        memory.decisions.push(decision);
+        Ok(())
     }
 
-}
\ No newline at end of file
+    pub fn file_summary(&self, path: &str, scope: &str) -> Option<FileSummary> {
+        self.0.lock().unwrap().file_summaries.get(&summary_key(path, scope)).cloned()
+    }
+
+    pub fn update_file_summary(&self, path: &str, summary: FileSummary) -> io::Result<()> {
+        let mut memory = self.0.lock().unwrap();
+        memory
+            .file_summaries
+            .insert(summary_key(path, &summary.scope), summary);
+        let data = serde_json::to_string_pretty(&memory.file_summaries)?;
+        memory.persist(FILE_SUMMARIES_FILE, &data)
+    }
+
+    pub fn all(&self) -> ProjectMemory {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Read a memory file under `.winter/` as string
+    pub fn read(&self, relative_path: &str) -> io::Result<String> {
+        let root = self
+            .root()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No project is open"))?;
+        fs::read_to_string(ProjectMemory::resolve(&root, relative_path)?)
+    }
+
+    /// Writes a memory file under `.winter/`, e.g. `feedback_queue.json`
+    pub fn save(&self, relative_path: &str, content: &str) -> io::Result<()> {
+        if self.root().is_none() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "No project is open"));
+        }
+        self.0.lock().unwrap().persist(relative_path, content)
+    }
+
+    /// Summarizes a memory file for a scope ("module", "testing", "infra") through the model.
+    /// Summaries are kept in `file_summaries` and reused while the file content is unchanged.
+    pub async fn summarize(&self, relative_path: &str, scope: &str, llm: &dyn LlmBackend) -> io::Result<String> {
+        let content = self.read(relative_path)?;
+        let hash = content_hash(&content);
+        if let Some(cached) = self.file_summary(relative_path, scope) {
+            if cached.content_hash == hash {
+                return Ok(cached.summary);
+            }
+        }
+
+        let summary = summarizer::summarize(llm, relative_path, &content, scope)
+            .await
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        self.update_file_summary(
+            relative_path,
+            FileSummary {
+                scope: scope.to_string(),
+                summary: summary.clone(),
+                content_hash: hash,
+            },
+        )?;
+        Ok(summary)
+    }
+
+    /// Chunk a memory file into parts by line count(approx)
+    pub fn chunk(&self, relative_path: &str, lines_per_chunk: usize) -> io::Result<Vec<String>> {
+        let content = self.read(relative_path)?;
+        Ok(chunk_lines(&content, lines_per_chunk)
+            .into_iter()
+            .map(|chunk| chunk.text)
+            .collect())
+    }
+}
+
+fn summary_key(path: &str, scope: &str) -> String {
+    if scope.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}#{}", path, scope)
+    }
+}
+
+/// A run of lines from a file; line numbers are 1-based and inclusive
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineChunk {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+}
+
+/// Splits text into chunks of at most `lines_per_chunk` lines
+pub fn chunk_lines(content: &str, lines_per_chunk: usize) -> Vec<LineChunk> {
+    let lines: Vec<_> = content.lines().collect();
+    lines
+        .chunks(lines_per_chunk.max(1))
+        .enumerate()
+        .map(|(i, chunk)| LineChunk {
+            start_line: i * lines_per_chunk.max(1) + 1,
+            end_line: i * lines_per_chunk.max(1) + chunk.len(),
+            text: chunk.join("\n"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_files_stay_inside_the_memory_dir() {
+        let project = std::env::temp_dir().join(format!("winter-memory-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("secret.txt"), "secret").unwrap();
+        let memory = ProjectMemoryHandle::new();
+        memory.open(&project).unwrap();
+
+        memory.save("notes/feedback_queue.json", "[]").unwrap();
+        assert_eq!(memory.read("notes/feedback_queue.json").unwrap(), "[]");
+
+        for path in ["../secret.txt", "notes/../../secret.txt"] {
+            assert!(memory.read(path).is_err(), "{} should be rejected", path);
+            assert!(memory.save(path, "overwritten").is_err(), "{} should be rejected", path);
+        }
+        let outside = project.join("secret.txt");
+        assert!(memory.read(outside.to_str().unwrap()).is_err());
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "secret");
+
+        fs::remove_dir_all(project).unwrap();
+    }
+}
@@ -0,0 +1,647 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use walkdir::WalkDir;
+
+use crate::llm::backend::estimate_tokens;
+use crate::llm::embedding::{default_embedder, Embedder};
+use crate::memory::project_memory::chunk_lines;
+use crate::tools::path_jail::PathJail;
+
+/// Where the index is stored, relative to the project root
+const INDEX_DIR: &str = ".winter/index";
+const INDEX_FILE: &str = "index.json";
+const LINES_PER_CHUNK: usize = 40;
+/// Larger files are generated or data and are not indexed
+const MAX_FILE_BYTES: u64 = 256 * 1024;
+const EMBED_BATCH: usize = 32;
+const SKIP_DIRS: [&str; 3] = ["target", "node_modules", "dist"];
+
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+/// How long prompt assembly trusts an index before comparing it with the files on disk again
+const REFRESH_DEBOUNCE: Duration = Duration::from_secs(30);
+
+static INDEXES: OnceLock<Mutex<HashMap<PathBuf, RetrievalIndex>>> = OnceLock::new();
+/// Held by the one refresh that runs at a time; searches never wait for it
+static REFRESHING: OnceLock<Mutex<()>> = OnceLock::new();
+
+/// A chunk of a project or memory file, with its embedding when an embedder is available
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedChunk {
+    /// Project-relative path
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector: Option<Vec<f32>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct IndexedFile {
+    modified: u64,
+    size: u64,
+    chunks: Vec<IndexedChunk>,
+}
+
+/// Chunks of every indexable file in a project, persisted in `<project>/.winter/index/index.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RetrievalIndex {
+    /// Embedder the vectors were made with; None if the index only supports BM25
+    embedder: Option<String>,
+    files: HashMap<String, IndexedFile>,
+    /// When the index was last compared with the files on disk; None if never or marked stale
+    #[serde(skip)]
+    checked_at: Option<Instant>,
+    /// Bumped by `mark_stale`, so a refresh that started before the mark does not count as current
+    #[serde(skip)]
+    generation: u64,
+}
+
+/// A retrieved chunk and its relevance to the query
+#[derive(Debug, Clone, Serialize)]
+pub struct RetrievedChunk {
+    pub chunk: IndexedChunk,
+    pub score: f32,
+}
+
+impl RetrievalIndex {
+    fn load(project_root: &Path) -> Self {
+        let path = project_root.join(INDEX_DIR).join(INDEX_FILE);
+        match fs::read_to_string(&path) {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+                eprintln!("[warn] Rebuilding invalid index {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self, project_root: &Path) -> io::Result<()> {
+        let folder = project_root.join(INDEX_DIR);
+        fs::create_dir_all(&folder)?;
+        fs::write(folder.join(INDEX_FILE), serde_json::to_string(self)?)
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.files.values().map(|file| file.chunks.len()).sum()
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.checked_at.is_some_and(|at| at.elapsed() < REFRESH_DEBOUNCE)
+    }
+
+    /// Modification time and size of every indexed file; none if the vectors were made by
+    /// another embedder, so that every file is re-indexed
+    fn fingerprints(&self, embedder_name: &Option<String>) -> HashMap<String, (u64, u64)> {
+        if &self.embedder != embedder_name {
+            return HashMap::new();
+        }
+        self.files
+            .iter()
+            .map(|(path, file)| (path.clone(), (file.modified, file.size)))
+            .collect()
+    }
+
+    /// Takes in the files re-indexed by a refresh; `None` drops a file. Files that are no
+    /// longer in `seen` were deleted. Every chunk is dropped first if the embedder changed.
+    fn merge(
+        &mut self,
+        project_root: &Path,
+        embedder_name: Option<String>,
+        seen: &HashSet<String>,
+        reindexed: Vec<(String, Option<IndexedFile>)>,
+    ) -> io::Result<()> {
+        if self.embedder != embedder_name {
+            self.files.clear();
+            self.embedder = embedder_name;
+        }
+
+        let before = self.files.len();
+        self.files.retain(|path, _| seen.contains(path));
+        let removed = before - self.files.len();
+        let changed = reindexed.len();
+        for (relative, file) in reindexed {
+            match file {
+                Some(file) => self.files.insert(relative, file),
+                None => self.files.remove(&relative),
+            };
+        }
+
+        if changed > 0 || removed > 0 {
+            println!(
+                "[Retrieval] Re-indexed {} file(s), removed {}, {} chunk(s) in {}",
+                changed,
+                removed,
+                self.chunk_count(),
+                project_root.display()
+            );
+            self.save(project_root)?;
+        }
+        Ok(())
+    }
+
+    /// Whether a search can rank by similarity to the embedded query: every chunk has a vector
+    /// made by this embedder
+    fn uses_vectors(&self, embedder: &dyn Embedder) -> bool {
+        self.embedder.as_deref() == Some(embedder.name().as_str())
+            && self.files.values().flat_map(|file| file.chunks.iter()).all(|chunk| chunk.vector.is_some())
+    }
+
+    /// The `k` chunks most relevant to the query. Ranks by cosine similarity when every chunk
+    /// has a vector and the query can be embedded, otherwise by BM25.
+    pub async fn search(&self, query: &str, k: usize, embedder: Option<&dyn Embedder>) -> Vec<RetrievedChunk> {
+        let query_vector = match embedder {
+            Some(embedder) if self.uses_vectors(embedder) => embed_query(embedder, query).await,
+            _ => None,
+        };
+        self.rank(query, k, query_vector)
+    }
+
+    /// Ranks by cosine similarity to `query_vector` if given, otherwise by BM25
+    fn rank(&self, query: &str, k: usize, query_vector: Option<Vec<f32>>) -> Vec<RetrievedChunk> {
+        let chunks: Vec<&IndexedChunk> = self.files.values().flat_map(|file| file.chunks.iter()).collect();
+        if chunks.is_empty() || k == 0 {
+            return vec![];
+        }
+
+        let scores: Vec<f32> = match query_vector {
+            Some(query_vector) => chunks
+                .iter()
+                .map(|chunk| chunk.vector.as_ref().map_or(0.0, |vector| cosine(&query_vector, vector)))
+                .collect(),
+            None => bm25(query, &chunks),
+        };
+
+        let mut ranked: Vec<RetrievedChunk> = chunks
+            .into_iter()
+            .zip(scores)
+            .filter(|(_, score)| *score > 0.0)
+            .map(|(chunk, score)| RetrievedChunk {
+                chunk: chunk.clone(),
+                score,
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+        ranked.truncate(k);
+        ranked
+    }
+}
+
+/// Splits a chunk into pieces of at most `budget` estimated tokens, on line boundaries where
+/// possible. Lines that are too long on their own, e.g. minified code, are cut.
+fn split_chunk(chunk: IndexedChunk, budget: usize) -> Vec<IndexedChunk> {
+    if estimate_tokens(&chunk.text) <= budget {
+        return vec![chunk];
+    }
+
+    let max_chars = budget.max(1) * 4;
+    let mut pieces: Vec<IndexedChunk> = vec![];
+    let mut current: Option<IndexedChunk> = None;
+    for (offset, line) in chunk.text.lines().enumerate() {
+        let line_number = chunk.start_line + offset;
+        let chars: Vec<char> = line.chars().collect();
+        let parts: Vec<String> = if chars.is_empty() {
+            vec![String::new()]
+        } else {
+            chars.chunks(max_chars).map(|part| part.iter().collect()).collect()
+        };
+        for part in parts {
+            let fits = current
+                .as_ref()
+                .is_some_and(|piece| estimate_tokens(&piece.text) + estimate_tokens(&part) < budget);
+            match current.as_mut() {
+                Some(piece) if fits => {
+                    piece.text.push('\n');
+                    piece.text.push_str(&part);
+                    piece.end_line = line_number;
+                }
+                _ => {
+                    pieces.extend(current.take());
+                    current = Some(IndexedChunk {
+                        path: chunk.path.clone(),
+                        start_line: line_number,
+                        end_line: line_number,
+                        text: part,
+                        vector: None,
+                    });
+                }
+            }
+        }
+    }
+    pieces.extend(current);
+    pieces
+}
+
+/// Reads, chunks and embeds the given files. A file comes back as `None` if it is not UTF-8
+/// text, or if embedding failed, so the next refresh tries it again.
+async fn index_files(
+    project_root: &Path,
+    files: &[(String, u64, u64)],
+    embedder: Option<&dyn Embedder>,
+) -> Vec<(String, Option<IndexedFile>)> {
+    let mut indexed = vec![];
+    for (relative, modified, size) in files {
+        let Ok(content) = fs::read_to_string(project_root.join(relative)) else {
+            indexed.push((relative.clone(), None));
+            continue;
+        };
+        let mut chunks: Vec<IndexedChunk> = chunk_lines(&content, LINES_PER_CHUNK)
+            .into_iter()
+            .filter(|chunk| !chunk.text.trim().is_empty())
+            .map(|chunk| IndexedChunk {
+                path: relative.clone(),
+                start_line: chunk.start_line,
+                end_line: chunk.end_line,
+                text: chunk.text,
+                vector: None,
+            })
+            .collect();
+
+        if let Some(embedder) = embedder {
+            // Token estimates undercount code, so leave the model some headroom
+            let budget = embedder.max_tokens() * 3 / 4;
+            chunks = chunks.into_iter().flat_map(|chunk| split_chunk(chunk, budget)).collect();
+            if let Err(e) = embed_chunks(embedder, &mut chunks).await {
+                eprintln!("[warn] Failed to embed {}: {}", relative, e);
+                indexed.push((relative.clone(), None));
+                continue;
+            }
+        }
+
+        indexed.push((
+            relative.clone(),
+            Some(IndexedFile {
+                modified: *modified,
+                size: *size,
+                chunks,
+            }),
+        ));
+    }
+    indexed
+}
+
+async fn embed_query(embedder: &dyn Embedder, query: &str) -> Option<Vec<f32>> {
+    match embedder.embed(&[query.to_string()]).await {
+        Ok(mut vectors) => vectors.pop(),
+        Err(e) => {
+            eprintln!("[warn] Failed to embed query, using BM25: {}", e);
+            None
+        }
+    }
+}
+
+async fn embed_chunks(embedder: &dyn Embedder, chunks: &mut [IndexedChunk]) -> Result<(), String> {
+    for batch in chunks.chunks_mut(EMBED_BATCH) {
+        let texts: Vec<String> = batch.iter().map(|chunk| chunk.text.clone()).collect();
+        let vectors = embedder.embed(&texts).await.map_err(|e| e.to_string())?;
+        for (chunk, vector) in batch.iter_mut().zip(vectors) {
+            chunk.vector = Some(vector);
+        }
+    }
+    Ok(())
+}
+
+/// Whether the project has a retrieval index on disk
+pub fn has_index(project_root: &Path) -> bool {
+    project_root.join(INDEX_DIR).join(INDEX_FILE).is_file()
+}
+
+/// Relative paths of the project files retrieval would index
+pub fn project_files(project_root: &Path) -> io::Result<Vec<String>> {
+    Ok(indexable_files(project_root)?.into_iter().map(|(path, _, _)| path).collect())
+}
+
+/// Project files that can be indexed, as (relative path, mtime, size).
+/// Skips deny-listed and build directories; of `.winter/` only the top-level memory files are included.
+fn indexable_files(project_root: &Path) -> io::Result<Vec<(String, u64, u64)>> {
+    let jail = PathJail::new(project_root).map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.to_string()))?;
+    let root = jail.root().to_path_buf();
+
+    let walker = WalkDir::new(&root).into_iter().filter_entry(|entry| {
+        let Ok(relative) = entry.path().strip_prefix(&root) else {
+            return false;
+        };
+        if relative.as_os_str().is_empty() {
+            return true;
+        }
+        let mut components = relative.components();
+        let first = components.next().map(|c| c.as_os_str().to_string_lossy().to_string());
+        match first.as_deref() {
+            // `.winter` itself and the memory files directly inside it
+            Some(".winter") => match components.count() {
+                0 => true,
+                1 => entry.file_type().is_file(),
+                _ => false,
+            },
+            Some(name) if SKIP_DIRS.contains(&name) => false,
+            _ => !jail.is_denied(relative),
+        }
+    });
+
+    let mut files = vec![];
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else { continue };
+        if metadata.len() == 0 || metadata.len() > MAX_FILE_BYTES {
+            continue;
+        }
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs());
+        let Ok(relative) = entry.path().strip_prefix(&root) else { continue };
+        files.push((relative.to_string_lossy().replace('\\', "/"), modified, metadata.len()));
+    }
+    Ok(files)
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|token| token.len() > 1)
+        .map(|token| token.to_lowercase())
+        .collect()
+}
+
+fn bm25(query: &str, chunks: &[&IndexedChunk]) -> Vec<f32> {
+    let query_terms: HashSet<String> = tokenize(query).into_iter().collect();
+    let documents: Vec<Vec<String>> = chunks.iter().map(|chunk| tokenize(&chunk.text)).collect();
+    let count = documents.len() as f32;
+    let average_length = documents.iter().map(|doc| doc.len()).sum::<usize>() as f32 / count.max(1.0);
+
+    let idf: HashMap<&String, f32> = query_terms
+        .iter()
+        .map(|term| {
+            let containing = documents.iter().filter(|doc| doc.contains(term)).count() as f32;
+            (term, ((count - containing + 0.5) / (containing + 0.5) + 1.0).ln())
+        })
+        .collect();
+
+    documents
+        .iter()
+        .map(|doc| {
+            let length_norm = 1.0 - BM25_B + BM25_B * doc.len() as f32 / average_length.max(1.0);
+            idf.iter()
+                .map(|(term, idf)| {
+                    let frequency = doc.iter().filter(|token| token == term).count() as f32;
+                    idf * frequency * (BM25_K1 + 1.0) / (frequency + BM25_K1 * length_norm)
+                })
+                .sum::<f32>()
+        })
+        .collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+fn indexes() -> &'static Mutex<HashMap<PathBuf, RetrievalIndex>> {
+    INDEXES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn refreshing() -> &'static Mutex<()> {
+    REFRESHING.get_or_init(|| Mutex::new(()))
+}
+
+fn index_of<'a>(indexes: &'a mut HashMap<PathBuf, RetrievalIndex>, project_root: &Path) -> &'a mut RetrievalIndex {
+    indexes
+        .entry(project_root.to_path_buf())
+        .or_insert_with(|| RetrievalIndex::load(project_root))
+}
+
+/// Re-chunks the files that were added or changed since they were indexed and drops deleted
+/// ones; every file is re-embedded when the embedder changed. The index lock is only held to
+/// compare and to merge, not while files are read and embedded. Callers hold `refreshing()`.
+/// Returns the number of files re-indexed.
+async fn refresh(project_root: &Path, embedder: Option<&dyn Embedder>) -> io::Result<usize> {
+    let started = Instant::now();
+    let embedder_name = embedder.map(|e| e.name());
+    let (generation, known) = {
+        let mut indexes = indexes().lock().await;
+        let index = index_of(&mut indexes, project_root);
+        (index.generation, index.fingerprints(&embedder_name))
+    };
+
+    let mut seen = HashSet::new();
+    let mut changed = vec![];
+    for (relative, modified, size) in indexable_files(project_root)? {
+        seen.insert(relative.clone());
+        if known.get(&relative) != Some(&(modified, size)) {
+            changed.push((relative, modified, size));
+        }
+    }
+    let reindexed = index_files(project_root, &changed, embedder).await;
+
+    let mut indexes = indexes().lock().await;
+    let index = index_of(&mut indexes, project_root);
+    index.merge(project_root, embedder_name, &seen, reindexed)?;
+    if index.generation == generation {
+        index.checked_at = Some(started);
+    }
+    Ok(changed.len())
+}
+
+/// Brings the project's index up to date with the files on disk
+pub async fn refresh_index(project_root: &Path) -> io::Result<usize> {
+    let embedder: Option<Arc<dyn Embedder>> = default_embedder();
+    let _refreshing = refreshing().lock().await;
+    refresh(project_root, embedder.as_deref()).await
+}
+
+/// Makes the next retrieval compare the index with the files on disk, e.g. after changes were applied
+pub async fn mark_stale(project_root: &Path) {
+    if let Some(index) = indexes().lock().await.get_mut(project_root) {
+        index.checked_at = None;
+        index.generation += 1;
+    }
+}
+
+/// The `k` chunks of the project most relevant to `query`. Changed files are re-indexed first
+/// unless the index was checked within `REFRESH_DEBOUNCE` or a refresh is already running.
+pub async fn retrieve(project_root: &Path, query: &str, k: usize) -> io::Result<Vec<RetrievedChunk>> {
+    let embedder: Option<Arc<dyn Embedder>> = default_embedder();
+    let fresh = index_of(&mut *indexes().lock().await, project_root).is_fresh();
+    if !fresh {
+        if let Ok(_refreshing) = refreshing().try_lock() {
+            refresh(project_root, embedder.as_deref()).await?;
+        }
+    }
+
+    let uses_vectors = match embedder.as_deref() {
+        Some(embedder) => index_of(&mut *indexes().lock().await, project_root).uses_vectors(embedder),
+        None => false,
+    };
+    let query_vector = match embedder.as_deref() {
+        Some(embedder) if uses_vectors => embed_query(embedder, query).await,
+        _ => None,
+    };
+    Ok(index_of(&mut *indexes().lock().await, project_root).rank(query, k, query_vector))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::backend::LlmError;
+    use async_trait::async_trait;
+
+    struct FixedEmbedder;
+
+    #[async_trait]
+    impl Embedder for FixedEmbedder {
+        fn name(&self) -> String {
+            "fixed".into()
+        }
+
+        fn max_tokens(&self) -> usize {
+            16
+        }
+
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, LlmError> {
+            Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect())
+        }
+    }
+
+    /// Blocks in `embed` until released, reporting when it got there
+    #[derive(Default)]
+    struct GatedEmbedder {
+        embedding: tokio::sync::Notify,
+        release: tokio::sync::Notify,
+    }
+
+    #[async_trait]
+    impl Embedder for GatedEmbedder {
+        fn name(&self) -> String {
+            "gated".into()
+        }
+
+        fn max_tokens(&self) -> usize {
+            512
+        }
+
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, LlmError> {
+            self.embedding.notify_one();
+            self.release.notified().await;
+            Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect())
+        }
+    }
+
+    fn temp_project() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("winter-retrieval-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("main.rs"), "fn main() {\n    println!(\"hello\");\n}\n").unwrap();
+        fs::canonicalize(root).unwrap()
+    }
+
+    fn chunk(path: &str, text: &str, vector: Option<Vec<f32>>) -> IndexedChunk {
+        IndexedChunk {
+            path: path.into(),
+            start_line: 1,
+            end_line: text.lines().count(),
+            text: text.into(),
+            vector,
+        }
+    }
+
+    #[test]
+    fn split_chunk_keeps_pieces_within_budget_and_tracks_lines() {
+        let text = (1..=20).map(|i| format!("line number {}", i)).collect::<Vec<_>>().join("\n");
+        let pieces = split_chunk(chunk("a.rs", &text, None), 12);
+
+        assert!(pieces.len() > 1);
+        assert!(pieces.iter().all(|piece| estimate_tokens(&piece.text) <= 12));
+        assert_eq!(pieces.first().unwrap().start_line, 1);
+        assert_eq!(pieces.last().unwrap().end_line, 20);
+        for pair in pieces.windows(2) {
+            assert_eq!(pair[0].end_line + 1, pair[1].start_line);
+        }
+    }
+
+    #[test]
+    fn split_chunk_cuts_overlong_lines() {
+        let pieces = split_chunk(chunk("min.js", &"x".repeat(200), None), 10);
+        assert_eq!(pieces.len(), 5);
+        assert!(pieces.iter().all(|piece| piece.start_line == 1 && piece.end_line == 1));
+    }
+
+    #[tokio::test]
+    async fn search_falls_back_to_bm25_when_vectors_are_missing() {
+        let mut index = RetrievalIndex {
+            embedder: Some("fixed".into()),
+            ..Default::default()
+        };
+        index.files.insert(
+            "a.rs".into(),
+            IndexedFile {
+                chunks: vec![chunk("a.rs", "parse the config file", Some(vec![0.0, 1.0]))],
+                ..Default::default()
+            },
+        );
+        index.files.insert(
+            "b.rs".into(),
+            IndexedFile {
+                chunks: vec![chunk("b.rs", "render the page", None)],
+                ..Default::default()
+            },
+        );
+
+        let results = index.search("render page", 1, Some(&FixedEmbedder)).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk.path, "b.rs");
+    }
+
+    #[tokio::test]
+    async fn refresh_releases_the_index_lock_while_embedding() {
+        let project = temp_project();
+        let embedder = GatedEmbedder::default();
+
+        let (refreshed, _) = tokio::join!(refresh(&project, Some(&embedder)), async {
+            embedder.embedding.notified().await;
+            let locked = tokio::time::timeout(Duration::from_secs(1), indexes().lock()).await;
+            assert!(locked.is_ok(), "the index lock is held while embedding");
+            drop(locked);
+            embedder.release.notify_one();
+        });
+        assert_eq!(refreshed.unwrap(), 1);
+        assert!(index_of(&mut *indexes().lock().await, &project).uses_vectors(&embedder));
+
+        fs::remove_dir_all(project).unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_refreshed_index_is_trusted_until_marked_stale() {
+        let project = temp_project();
+        refresh(&project, None).await.unwrap();
+        assert!(index_of(&mut *indexes().lock().await, &project).is_fresh());
+
+        mark_stale(&project).await;
+        assert!(!index_of(&mut *indexes().lock().await, &project).is_fresh());
+
+        fs::write(project.join("lib.rs"), "pub fn render() {}\n").unwrap();
+        assert_eq!(refresh(&project, None).await.unwrap(), 1);
+        let index = &mut *indexes().lock().await;
+        assert!(index_of(index, &project).is_fresh());
+        assert_eq!(index_of(index, &project).rank("render", 1, None)[0].chunk.path, "lib.rs");
+
+        fs::remove_dir_all(project).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+use sha2::{Digest, Sha256};
+
+use crate::llm::backend::{estimate_tokens, CompletionOptions, LlmBackend, LlmError};
+
+/// Map-reduce summarization through the model backend.
+/// The content is split into parts that fit the context window, each part is summarized
+/// for the scope, and the partial summaries are merged until one summary is left.
+pub async fn summarize(llm: &dyn LlmBackend, path: &str, content: &str, scope: &str) -> Result<String, LlmError> {
+    if content.trim().is_empty() {
+        return Ok(String::new());
+    }
+
+    // Half the window for the input, an eighth for each answer, the rest for instructions
+    let window = llm.context_window();
+    let part_budget = window / 2;
+    let options = CompletionOptions {
+        max_tokens: Some((window / 8) as u32),
+        temperature: 0.2,
+        ..Default::default()
+    };
+
+    let parts = split_to_budget(content.lines().map(String::from).collect(), part_budget);
+    println!("[Summarizer] Summarizing {} for {} in {} part(s)", path, scope_label(scope), parts.len());
+
+    let mut summaries = vec![];
+    for (i, part) in parts.iter().enumerate() {
+        let prompt = format!(
+            "You are summarizing part {} of {} of the project file `{}`.\n{}\n\n### Content\n{}\n\n### Summary\n",
+            i + 1,
+            parts.len(),
+            path,
+            scope_instructions(scope),
+            part
+        );
+        summaries.push(llm.complete(&prompt, &options).await?.trim().to_string());
+    }
+
+    // Merge partial summaries level by level until one is left
+    while summaries.len() > 1 {
+        let groups = split_to_budget(summaries.clone(), part_budget);
+        if groups.len() >= summaries.len() {
+            // The summaries are too long to merge further
+            break;
+        }
+
+        let mut merged = vec![];
+        for group in groups {
+            let prompt = format!(
+                "Combine these partial summaries of the project file `{}` into one summary.\n{}\n\n### Partial Summaries\n{}\n\n### Summary\n",
+                path,
+                scope_instructions(scope),
+                group
+            );
+            merged.push(llm.complete(&prompt, &options).await?.trim().to_string());
+        }
+        summaries = merged;
+    }
+
+    Ok(summaries.join("\n\n"))
+}
+
+/// Hex SHA-256 of the content, used to tell whether a cached summary is still current
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Groups consecutive items into blocks of at most `budget` estimated tokens.
+/// Items larger than the budget, e.g. minified lines, are cut into pieces first.
+fn split_to_budget(items: Vec<String>, budget: usize) -> Vec<String> {
+    let max_chars = budget.max(1) * 4;
+    let items = items.into_iter().flat_map(|item| {
+        let chars: Vec<char> = item.chars().collect();
+        if chars.len() <= max_chars {
+            vec![item]
+        } else {
+            chars.chunks(max_chars).map(|piece| piece.iter().collect()).collect()
+        }
+    });
+
+    let mut blocks = vec![];
+    let mut current = String::new();
+    let mut current_tokens = 0;
+    for item in items {
+        let tokens = estimate_tokens(&item) + 1;
+        if current_tokens + tokens > budget && !current.is_empty() {
+            blocks.push(std::mem::take(&mut current));
+            current_tokens = 0;
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(&item);
+        current_tokens += tokens;
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+fn scope_label(scope: &str) -> &str {
+    if scope.is_empty() {
+        "general use"
+    } else {
+        scope
+    }
+}
+
+/// What the summary should focus on for the agents that read it
+fn scope_instructions(scope: &str) -> &'static str {
+    match scope {
+        "module" => "Focus on the modules and components, their responsibilities, public interfaces and how they depend on each other, so code can be generated against them.",
+        "testing" => "Focus on the behaviour that must be verified: functional requirements, acceptance criteria, edge cases and error conditions, so tests can be written from it.",
+        "infra" => "Focus on what deployment needs: services and their runtimes, external dependencies, configuration, storage, networking and environments.",
+        _ => "Capture the key facts, decisions and structure concisely.",
+    }
+}
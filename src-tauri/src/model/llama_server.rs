@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use portpicker::pick_unused_port;
+use tokio::process::{Child, Command};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+use crate::llm::backend::LlmError;
+
+const DEFAULT_SERVER_BINARY: &str = "./bin/llama.cpp/llama-server";
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const REAPER_INTERVAL: Duration = Duration::from_secs(30);
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+/// Requests served concurrently per model; anything beyond this waits in the queue
+const PARALLEL_SLOTS: usize = 1;
+
+static MANAGER: OnceLock<Arc<LlamaServerManager>> = OnceLock::new();
+
+/// Keeps one long-lived llama-server child per loaded model so the weights are only loaded once.
+/// Servers are started on first use, health-checked, restarted if they die and unloaded when idle.
+pub struct LlamaServerManager {
+    binary: PathBuf,
+    idle_timeout: Duration,
+    servers: Mutex<HashMap<SlotKey, Arc<ServerSlot>>>,
+}
+
+/// A server is started per model, context size and mode, since each is a launch flag
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SlotKey {
+    model_path: PathBuf,
+    context_size: usize,
+    embedding: bool,
+}
+
+struct ServerSlot {
+    model_path: PathBuf,
+    context_size: usize,
+    /// Started with `--embedding` to serve `/v1/embeddings` instead of completions
+    embedding: bool,
+    process: Mutex<Option<RunningServer>>,
+    queue: Arc<Semaphore>,
+    last_used: std::sync::Mutex<Instant>,
+}
+
+struct RunningServer {
+    child: Child,
+    port: u16,
+}
+
+/// Exclusive use of a ready server. Holding it keeps the request slot; dropping it frees the slot.
+pub struct ServerLease {
+    pub base_url: String,
+    slot: Arc<ServerSlot>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl ServerLease {
+    pub fn model_path(&self) -> &Path {
+        &self.slot.model_path
+    }
+}
+
+impl Drop for ServerLease {
+    fn drop(&mut self) {
+        self.slot.touch();
+    }
+}
+
+impl ServerSlot {
+    fn touch(&self) {
+        if let Ok(mut last_used) = self.last_used.lock() {
+            *last_used = Instant::now();
+        }
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_used
+            .lock()
+            .map(|last_used| last_used.elapsed())
+            .unwrap_or_default()
+    }
+
+    fn is_busy(&self) -> bool {
+        self.queue.available_permits() < PARALLEL_SLOTS
+    }
+}
+
+impl LlamaServerManager {
+    pub fn new(binary: PathBuf, idle_timeout: Duration) -> Self {
+        Self {
+            binary,
+            idle_timeout,
+            servers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Process-wide manager. The first caller decides the server binary.
+    pub fn global(binary: Option<&str>) -> Arc<LlamaServerManager> {
+        MANAGER
+            .get_or_init(|| {
+                let manager = Arc::new(LlamaServerManager::new(
+                    PathBuf::from(binary.unwrap_or(DEFAULT_SERVER_BINARY)),
+                    IDLE_TIMEOUT,
+                ));
+                // Tauri's runtime, since the first call can come from the synchronous setup hook
+                tauri::async_runtime::spawn(manager.clone().reap_idle_servers());
+                manager
+            })
+            .clone()
+    }
+
+    /// Waits for a free request slot on the model's server, starting or restarting it if needed.
+    pub async fn acquire(&self, model_path: &Path, context_size: usize) -> Result<ServerLease, LlmError> {
+        self.acquire_slot(model_path, context_size, false).await
+    }
+
+    /// Like `acquire`, for an embedding model
+    pub async fn acquire_embedding(&self, model_path: &Path, context_size: usize) -> Result<ServerLease, LlmError> {
+        self.acquire_slot(model_path, context_size, true).await
+    }
+
+    async fn acquire_slot(&self, model_path: &Path, context_size: usize, embedding: bool) -> Result<ServerLease, LlmError> {
+        let slot = self.slot(model_path, context_size, embedding).await?;
+
+        let permit = slot
+            .queue
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| LlmError::Unavailable("llama-server queue closed".into()))?;
+        slot.touch();
+
+        let port = self.ensure_running(&slot).await?;
+
+        Ok(ServerLease {
+            base_url: format!("http://127.0.0.1:{}", port),
+            slot,
+            _permit: permit,
+        })
+    }
+
+    /// Address of the model's completion server, started if needed, without taking a request
+    /// slot. For cheap requests like `/tokenize` that must not queue behind running completions.
+    pub async fn base_url(&self, model_path: &Path, context_size: usize) -> Result<String, LlmError> {
+        let slot = self.slot(model_path, context_size, false).await?;
+        slot.touch();
+        let port = self.ensure_running(&slot).await?;
+        Ok(format!("http://127.0.0.1:{}", port))
+    }
+
+    async fn slot(&self, model_path: &Path, context_size: usize, embedding: bool) -> Result<Arc<ServerSlot>, LlmError> {
+        if !model_path.exists() {
+            return Err(LlmError::Unavailable(format!(
+                "Model not found: {}",
+                model_path.display()
+            )));
+        }
+
+        let key = SlotKey {
+            model_path: model_path.to_path_buf(),
+            context_size,
+            embedding,
+        };
+        let mut servers = self.servers.lock().await;
+        Ok(servers
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(ServerSlot {
+                    model_path: model_path.to_path_buf(),
+                    context_size,
+                    embedding,
+                    process: Mutex::new(None),
+                    queue: Arc::new(Semaphore::new(PARALLEL_SLOTS)),
+                    last_used: std::sync::Mutex::new(Instant::now()),
+                })
+            })
+            .clone())
+    }
+
+    /// Kills the server behind the lease so the next `acquire` of that slot starts a fresh one.
+    /// Servers of the same model with another context size or mode keep running.
+    pub async fn invalidate(&self, lease: ServerLease) {
+        if let Some(mut server) = lease.slot.process.lock().await.take() {
+            println!("[llama-server] Restarting server for {}", lease.slot.model_path.display());
+            let _ = server.child.kill().await;
+        }
+    }
+
+    /// Stops the model's servers and forgets them
+    pub async fn unload(&self, model_path: &Path) {
+        let slots: Vec<Arc<ServerSlot>> = {
+            let mut servers = self.servers.lock().await;
+            let keys: Vec<SlotKey> = servers.keys().filter(|key| key.model_path == model_path).cloned().collect();
+            keys.iter().filter_map(|key| servers.remove(key)).collect()
+        };
+        for slot in slots {
+            if let Some(mut server) = slot.process.lock().await.take() {
+                println!("[llama-server] Unloading {}", model_path.display());
+                let _ = server.child.kill().await;
+            }
+        }
+    }
+
+    /// Stops the slot's server if it is still idle. Holding a request permit and the process
+    /// lock keeps `acquire` from handing out the server while it is being stopped; the slot
+    /// stays registered, so a caller already waiting on it simply starts a new server.
+    async fn unload_if_idle(&self, slot: &ServerSlot) {
+        let Ok(_permit) = slot.queue.clone().try_acquire_owned() else {
+            return;
+        };
+        let mut process = slot.process.lock().await;
+        if slot.idle_for() < self.idle_timeout {
+            return;
+        }
+        if let Some(mut server) = process.take() {
+            println!("[llama-server] Unloading idle {}", slot.model_path.display());
+            let _ = server.child.kill().await;
+        }
+    }
+
+    /// Stops every server. Safe to call from the non-async shutdown path.
+    pub fn shutdown_all(&self) {
+        let servers = self.servers.blocking_lock();
+        for slot in servers.values() {
+            if let Some(server) = slot.process.blocking_lock().as_mut() {
+                let _ = server.child.start_kill();
+            }
+        }
+    }
+
+    /// Stops the process-wide manager's servers, if it was ever started
+    pub fn shutdown_global() {
+        if let Some(manager) = MANAGER.get() {
+            manager.shutdown_all();
+        }
+    }
+
+    async fn ensure_running(&self, slot: &ServerSlot) -> Result<u16, LlmError> {
+        let mut process = slot.process.lock().await;
+
+        if let Some(server) = process.as_mut() {
+            match server.child.try_wait() {
+                Ok(None) => return Ok(server.port),
+                Ok(Some(status)) => {
+                    println!(
+                        "[llama-server] Server for {} exited ({}), restarting",
+                        slot.model_path.display(),
+                        status
+                    );
+                }
+                Err(e) => println!("[llama-server] Could not poll server: {}", e),
+            }
+            *process = None;
+        }
+
+        let server = self.start(slot).await?;
+        let port = server.port;
+        *process = Some(server);
+        Ok(port)
+    }
+
+    async fn start(&self, slot: &ServerSlot) -> Result<RunningServer, LlmError> {
+        let port = pick_unused_port()
+            .ok_or_else(|| LlmError::Unavailable("No free port for llama-server".into()))?;
+
+        println!(
+            "[llama-server] Loading {} on port {}",
+            slot.model_path.display(),
+            port
+        );
+
+        let mut command = Command::new(&self.binary);
+        command
+            .arg("-m")
+            .arg(&slot.model_path)
+            .args(["-c", &slot.context_size.to_string()])
+            .args(["-np", &PARALLEL_SLOTS.to_string()])
+            .args(["--host", "127.0.0.1"])
+            .args(["--port", &port.to_string()]);
+        if slot.embedding {
+            command.arg("--embedding");
+        }
+
+        let mut child = command
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| LlmError::Unavailable(format!("Failed to start llama-server: {}", e)))?;
+
+        let client = reqwest::Client::new();
+        let health_url = format!("http://127.0.0.1:{}/health", port);
+        let started = Instant::now();
+
+        // /health answers 503 while the model is loading and 200 once it can serve
+        while started.elapsed() < STARTUP_TIMEOUT {
+            if let Ok(Some(status)) = child.try_wait() {
+                return Err(LlmError::Unavailable(format!(
+                    "llama-server exited during startup ({})",
+                    status
+                )));
+            }
+
+            if let Ok(resp) = client.get(&health_url).send().await {
+                if resp.status().is_success() {
+                    println!(
+                        "[llama-server] Ready after {}s",
+                        started.elapsed().as_secs()
+                    );
+                    return Ok(RunningServer { child, port });
+                }
+            }
+
+            tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+        }
+
+        let _ = child.kill().await;
+        Err(LlmError::Unavailable(
+            "llama-server did not become healthy in time".into(),
+        ))
+    }
+
+    /// Background loop that unloads models nobody has used for `idle_timeout`
+    async fn reap_idle_servers(self: Arc<Self>) {
+        loop {
+            tokio::time::sleep(REAPER_INTERVAL).await;
+
+            let idle: Vec<Arc<ServerSlot>> = self
+                .servers
+                .lock()
+                .await
+                .values()
+                .filter(|slot| !slot.is_busy() && slot.idle_for() >= self.idle_timeout)
+                .cloned()
+                .collect();
+
+            // Checked again per slot, as a request may have arrived since
+            for slot in idle {
+                self.unload_if_idle(&slot).await;
+            }
+        }
+    }
+}
@@ -4,4 +4,5 @@ pub mod model_registry;
 pub mod model_installer;
 pub mod model_downloader;
 pub mod model_manager;
-pub mod llama_wrapper;
\ No newline at end of file
+pub mod llama_wrapper;
+pub mod llama_server;
\ No newline at end of file
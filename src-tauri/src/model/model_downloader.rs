@@ -99,28 +99,13 @@ pub async fn download_model_file(app: AppHandle) -> Result<String, String> {
 
         let percent = ((downloaded as f64 / total_size as f64) * 100.0).floor() as u8;
 
-        // Human-readable string like "842.12 MB / 1.92 GB"
-        fn format_bytes(bytes: u64) -> String{
-            let gb = 1_000_000_000_f64;
-            let mb = 1_000_000_f64;
-            if bytes as f64 > gb {
-                format!("{:.2} GB", bytes as f64 / gb)
-            } else {
-                format!("{:.2} MB", bytes as f64 / mb)
-            }
-        }
-
         let elapsed_secs = start_time.elapsed().as_secs().max(1);
         let speed = downloaded / elapsed_secs;
         let remaining = total_size.saturating_sub(downloaded);
-        let eta = if speed > 0 {
-            remaining / speed
-        } else {
-            0
-        };
+        let eta = remaining.checked_div(speed).unwrap_or(0);
 
         let progress = DownloadProgress {
-            downloaded: downloaded,
+            downloaded,
             total: total_size,
             percent,
             speed_bytes_per_sec: speed,
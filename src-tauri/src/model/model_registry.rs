@@ -1,4 +1,4 @@
-use crate::config::{AppConfig, AppPaths, load_config, save_config,ModelDownloadInfo};
+use crate::config::{AppConfig, AppPaths, load_config, save_config, LlmSettings, ModelDownloadInfo};
 
 #[tauri::command]
 pub fn get_model_download_info() -> ModelDownloadInfo {
@@ -36,7 +36,8 @@ pub fn get_model_download_info() -> ModelDownloadInfo {
             uploads:"".into(),
         },
         model_file_size_estimate: None,
-        cached_model_info: None
+        cached_model_info: None,
+        llm: LlmSettings::default(),
     });
 
     config.model_file_size_estimate = Some(info.estimated_size_bytes);
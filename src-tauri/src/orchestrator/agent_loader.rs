@@ -4,7 +4,10 @@ use crate::agents::critique_agent::CritiqueAgent;
 use crate::agents::deployment_agent::DeploymentAgent;
 use crate::agents::doc_agent::DocAgent;
 use crate::agents::hello_agent::HelloAgent;
+use crate::agents::manifest_agent::ManifestAgent;
+use crate::orchestrator::manifest::{manifest_dirs, scan_manifests, LoadedAgent, ManifestReport};
 use crate::orchestrator::orchestrator::Orchestrator;
+use crate::orchestrator::registry::AgentHandler;
 use crate::orchestrator::types::{AgentCard, Capability, ExecutionMode, SkillGraph};
 use crate::agents::planner_agent::PlannerAgent;
 use crate::agents::refactor_agent::RefactorAgent;
@@ -16,6 +19,7 @@ use crate::agents::test_agent::TestAgent;
 use crate::tools::echo_tool::EchoTool;
 use crate::tools::llm_planner::LLMPlannerTool;
 use crate::tools::registry::ToolRegistry;
+use std::path::Path;
 
 pub fn register_all_agents(orchestrator: &mut Orchestrator){
     orchestrator.register_agent(
@@ -25,6 +29,10 @@ pub fn register_all_agents(orchestrator: &mut Orchestrator){
             input_schema: "text".to_string(),
             output_schema: "text".to_string(),
             default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(30),
+            priority: 0,
+            preconditions: vec![],
+            allowed_tools: vec![],
             skills: SkillGraph {
                 root: Capability::Greeting,
                 subskills: vec![]
@@ -81,6 +89,40 @@ pub fn register_all_agents(orchestrator: &mut Orchestrator){
         Box::new(RepoAgent)
     );
 }
+/// Loads the agent manifests from `WinterData/agents/` and the project's `.winter/agents/`
+/// and swaps them into the orchestrator. Invalid manifests are skipped and reported.
+pub fn reload_manifest_agents(orchestrator: &Orchestrator, project_root: Option<&Path>, known_tools: &[String]) -> ManifestReport {
+    let (manifests, errors) = scan_manifests(
+        &manifest_dirs(project_root),
+        &orchestrator.builtin_agent_ids(),
+        known_tools,
+    );
+
+    for error in &errors {
+        eprintln!("[warn] Invalid agent manifest {}", error);
+    }
+
+    let loaded = manifests
+        .iter()
+        .map(|loaded| LoadedAgent {
+            id: loaded.manifest.id.clone(),
+            source: loaded.source.clone(),
+        })
+        .collect::<Vec<_>>();
+    let agents = manifests
+        .into_iter()
+        .map(|loaded| {
+            let card = loaded.manifest.card();
+            let source = loaded.source.clone();
+            let handler: Box<dyn AgentHandler + Send + Sync> = Box::new(ManifestAgent::new(loaded));
+            (card, handler, source)
+        })
+        .collect();
+    orchestrator.replace_manifest_agents(agents);
+
+    println!("[AgentLoader] Loaded {} manifest agent(s)", loaded.len());
+    ManifestReport { loaded, errors }
+}
 pub fn register_all_tools(tool_registry: &mut ToolRegistry) {
     tool_registry.register_tool(Box::new(EchoTool));
     tool_registry.register_tool(Box::new(LLMPlannerTool));
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+
+use crate::orchestrator::types::{now_timestamp, AgentTask};
+use crate::tools::change_set::FileChange;
+
+/// Emitted with an `ApprovalRequest` when a goal pauses for the user
+pub const APPROVAL_REQUESTED_EVENT: &str = "approval-requested";
+/// Emitted with the request id and decision once a pending step is approved or rejected
+pub const APPROVAL_RESOLVED_EVENT: &str = "approval-resolved";
+
+const SETTINGS_PATH: &str = "WinterData/approval_gates.json";
+
+/// Points at which a goal waits for the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApprovalGate {
+    /// A critiqued plan is about to run
+    AfterPlanning,
+    /// A task is about to run in Execute mode
+    BeforeExecute,
+    /// A task's change set writes outside the generated directories
+    FileWrite,
+}
+
+/// Which gates are active, from `WinterData/approval_gates.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApprovalSettings {
+    pub after_planning: bool,
+    pub before_execute: bool,
+    pub file_writes: bool,
+    /// Project-relative directories agents may write to without asking
+    pub generated_dirs: Vec<String>,
+}
+
+impl Default for ApprovalSettings {
+    fn default() -> Self {
+        Self {
+            after_planning: true,
+            before_execute: true,
+            file_writes: true,
+            generated_dirs: vec!["generated".into()],
+        }
+    }
+}
+
+impl ApprovalSettings {
+    pub fn load() -> Self {
+        let path = dirs::home_dir().expect("No home dir").join(SETTINGS_PATH);
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("[warn] Ignoring invalid {}: {}", SETTINGS_PATH, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn is_enabled(&self, gate: ApprovalGate) -> bool {
+        match gate {
+            ApprovalGate::AfterPlanning => self.after_planning,
+            ApprovalGate::BeforeExecute => self.before_execute,
+            ApprovalGate::FileWrite => self.file_writes,
+        }
+    }
+
+    /// Changes that touch files outside the project's generated directories.
+    /// Paths outside the project root always need approval.
+    pub fn gated_changes(&self, changes: &[FileChange], project_root: Option<&Path>) -> Vec<FileChange> {
+        changes
+            .iter()
+            .filter(|change| {
+                let relative = project_root.and_then(|root| Path::new(&change.path).strip_prefix(root).ok());
+                match relative.and_then(|path| path.components().next()) {
+                    Some(first) => !self
+                        .generated_dirs
+                        .iter()
+                        .any(|dir| first.as_os_str() == dir.as_str()),
+                    None => true,
+                }
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// What the user is asked to approve
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub request_id: String,
+    pub gate: ApprovalGate,
+    pub goal_id: Option<String>,
+    pub task_id: Option<String>,
+    pub summary: String,
+    /// The plan waiting to run, for `AfterPlanning`
+    pub plan: Option<Vec<AgentTask>>,
+    /// The changes waiting to be applied, for `FileWrite`
+    pub changes: Option<Vec<FileChange>>,
+    pub requested_at: u64,
+}
+
+impl ApprovalRequest {
+    pub fn new(gate: ApprovalGate, goal_id: Option<String>, task_id: Option<String>, summary: String) -> Self {
+        Self {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            gate,
+            goal_id,
+            task_id,
+            summary,
+            plan: None,
+            changes: None,
+            requested_at: now_timestamp(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalDecision {
+    pub approved: bool,
+    /// The user's note, or why the step was rejected
+    pub reason: Option<String>,
+}
+
+struct Pending {
+    request: ApprovalRequest,
+    respond: oneshot::Sender<ApprovalDecision>,
+}
+
+static PENDING: OnceLock<Mutex<HashMap<String, Pending>>> = OnceLock::new();
+
+fn pending() -> &'static Mutex<HashMap<String, Pending>> {
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Parks the request until `approve_step`/`reject_step` answers it.
+/// Cancelling the waiting task counts as a rejection.
+pub async fn wait_for_approval(request: ApprovalRequest, cancel: &CancellationToken) -> ApprovalDecision {
+    let (respond, decision) = oneshot::channel();
+    let request_id = request.request_id.clone();
+    println!("[Approval] Waiting for {:?} approval {}", request.gate, request_id);
+    pending()
+        .lock()
+        .unwrap()
+        .insert(request_id.clone(), Pending { request, respond });
+
+    let decision = tokio::select! {
+        decision = decision => decision.unwrap_or(ApprovalDecision {
+            approved: false,
+            reason: Some("Approval request was dropped".into()),
+        }),
+        _ = cancel.cancelled() => ApprovalDecision {
+            approved: false,
+            reason: Some("Task was cancelled while awaiting approval".into()),
+        },
+    };
+    pending().lock().unwrap().remove(&request_id);
+    decision
+}
+
+fn resolve(request_id: &str, decision: ApprovalDecision) -> Result<(), String> {
+    let entry = pending()
+        .lock()
+        .unwrap()
+        .remove(request_id)
+        .ok_or_else(|| format!("No pending approval {}", request_id))?;
+    entry
+        .respond
+        .send(decision)
+        .map_err(|_| format!("Approval {} is no longer awaited", request_id))
+}
+
+pub fn pending_approvals() -> Vec<ApprovalRequest> {
+    let mut requests: Vec<ApprovalRequest> = pending()
+        .lock()
+        .unwrap()
+        .values()
+        .map(|entry| entry.request.clone())
+        .collect();
+    requests.sort_by_key(|request| request.requested_at);
+    requests
+}
+
+#[tauri::command]
+pub fn approve_step(request_id: String, note: Option<String>) -> Result<(), String> {
+    resolve(&request_id, ApprovalDecision { approved: true, reason: note })
+}
+
+#[tauri::command]
+pub fn reject_step(request_id: String, reason: Option<String>) -> Result<(), String> {
+    resolve(&request_id, ApprovalDecision { approved: false, reason })
+}
+
+#[tauri::command]
+pub fn list_pending_approvals() -> Vec<ApprovalRequest> {
+    pending_approvals()
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use tokio_util::sync::CancellationToken;
+
+/// Tokens of the tasks currently running and of goals with running tasks.
+/// A task token is a child of its parent task's token, so cancelling a task also
+/// cancels its subtasks; a goal token is watched by every task carrying that goal id.
+#[derive(Default)]
+struct Tokens {
+    tasks: HashMap<String, CancellationToken>,
+    goals: HashMap<String, GoalEntry>,
+}
+
+/// A goal's token and how many of its tasks and plans are running
+#[derive(Default)]
+struct GoalEntry {
+    token: CancellationToken,
+    active: usize,
+}
+
+/// Keeps a goal registered while a task or plan of it runs; the goal is forgotten once the
+/// last one drops its scope
+pub struct GoalScope {
+    goal_id: String,
+    token: CancellationToken,
+}
+
+impl GoalScope {
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+}
+
+impl Drop for GoalScope {
+    fn drop(&mut self) {
+        let mut running = running().lock().unwrap();
+        if let Some(entry) = running.goals.get_mut(&self.goal_id) {
+            entry.active = entry.active.saturating_sub(1);
+            if entry.active == 0 {
+                running.goals.remove(&self.goal_id);
+            }
+        }
+    }
+}
+
+static RUNNING: OnceLock<Mutex<Tokens>> = OnceLock::new();
+
+fn running() -> &'static Mutex<Tokens> {
+    RUNNING.get_or_init(|| Mutex::new(Tokens::default()))
+}
+
+/// Registers a running task and returns its token, which fires when the task or its parent
+/// is cancelled. Goal cancellation is observed separately through `goal_token`.
+pub fn register_task(task_id: &str, parent: &CancellationToken) -> CancellationToken {
+    let token = parent.child_token();
+    running()
+        .lock()
+        .unwrap()
+        .tasks
+        .insert(task_id.to_string(), token.clone());
+    token
+}
+
+/// Registers a running task or plan of the goal and returns its scope
+pub fn enter_goal(goal_id: &str) -> GoalScope {
+    let mut running = running().lock().unwrap();
+    let entry = running.goals.entry(goal_id.to_string()).or_default();
+    entry.active += 1;
+    GoalScope {
+        goal_id: goal_id.to_string(),
+        token: entry.token.clone(),
+    }
+}
+
+/// Token shared by every task of the goal; a fresh one if nothing of the goal is running
+pub fn goal_token(goal_id: &str) -> CancellationToken {
+    running()
+        .lock()
+        .unwrap()
+        .goals
+        .get(goal_id)
+        .map(|entry| entry.token.clone())
+        .unwrap_or_default()
+}
+
+pub fn unregister_task(task_id: &str) {
+    running().lock().unwrap().tasks.remove(task_id);
+}
+
+pub fn task_token(task_id: &str) -> Option<CancellationToken> {
+    running().lock().unwrap().tasks.get(task_id).cloned()
+}
+
+/// Returns false if the task is not running
+pub fn cancel_running_task(task_id: &str) -> bool {
+    match task_token(task_id) {
+        Some(token) => {
+            println!("[Orchestrator] Cancelling task {}", task_id);
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Cancels every running and not yet started task of the goal.
+/// The cancelled token is kept while the goal runs, so later layers of its plan stop too.
+pub fn cancel_running_goal(goal_id: &str) -> bool {
+    match running().lock().unwrap().goals.get(goal_id) {
+        Some(entry) => {
+            println!("[Orchestrator] Cancelling goal {}", goal_id);
+            entry.token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Gives a cancelled goal a fresh token, e.g. before resuming it
+pub fn reset_goal(goal_id: &str) {
+    if let Some(entry) = running().lock().unwrap().goals.get_mut(goal_id) {
+        if entry.token.is_cancelled() {
+            entry.token = CancellationToken::new();
+        }
+    }
+}
+
+#[tauri::command]
+pub fn cancel_task(task_id: String) -> Result<(), String> {
+    if cancel_running_task(&task_id) {
+        Ok(())
+    } else {
+        Err(format!("Task {} is not running", task_id))
+    }
+}
+
+#[tauri::command]
+pub fn cancel_goal(goal_id: String) -> Result<(), String> {
+    if cancel_running_goal(&goal_id) {
+        Ok(())
+    } else {
+        Err(format!("Goal {} has no running tasks", goal_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goal_is_forgotten_when_its_last_scope_ends() {
+        let goal_id = uuid::Uuid::new_v4().to_string();
+        let plan = enter_goal(&goal_id);
+        let task = enter_goal(&goal_id);
+        assert!(cancel_running_goal(&goal_id));
+        assert!(task.token().is_cancelled());
+
+        drop(task);
+        assert!(goal_token(&goal_id).is_cancelled(), "kept while the plan runs");
+        drop(plan);
+        assert!(!cancel_running_goal(&goal_id));
+        assert!(!goal_token(&goal_id).is_cancelled());
+    }
+
+    #[test]
+    fn reset_replaces_only_a_cancelled_token() {
+        let goal_id = uuid::Uuid::new_v4().to_string();
+        let scope = enter_goal(&goal_id);
+        reset_goal(&goal_id);
+        assert!(!scope.token().is_cancelled());
+
+        cancel_running_goal(&goal_id);
+        reset_goal(&goal_id);
+        assert!(!goal_token(&goal_id).is_cancelled());
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::orchestrator::protocol::AgentOutput;
+use crate::orchestrator::types::{now_timestamp, AgentTask, TaskStatus};
+
+/// Snapshot of a goal's plan execution, rewritten after every task so an
+/// interrupted goal can be resumed after a restart.
+/// A goal has one checkpoint per plan it runs, so plans nested in a goal keep their own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalCheckpoint {
+    pub goal_id: String,
+    /// Planner plan the tasks come from; None for a task graph given directly
+    #[serde(default)]
+    pub plan_id: Option<String>,
+    pub plan: Vec<AgentTask>,
+    pub statuses: HashMap<String, TaskStatus>,
+    /// Outputs of the tasks that succeeded, keyed by task id
+    pub outputs: HashMap<String, AgentOutput>,
+    pub started_at: u64,
+    pub updated_at: u64,
+}
+
+/// What the frontend needs to offer a resume
+#[derive(Debug, Clone, Serialize)]
+pub struct ResumableGoal {
+    pub goal_id: String,
+    pub plan_id: Option<String>,
+    pub completed: usize,
+    pub total: usize,
+    pub updated_at: u64,
+}
+
+impl GoalCheckpoint {
+    pub fn new(goal_id: &str, plan_id: Option<&str>, plan: &[AgentTask]) -> Self {
+        Self {
+            goal_id: goal_id.to_string(),
+            plan_id: plan_id.map(String::from),
+            plan: plan.to_vec(),
+            statuses: plan
+                .iter()
+                .map(|task| (task.task_id.clone(), TaskStatus::Pending))
+                .collect(),
+            outputs: HashMap::new(),
+            started_at: now_timestamp(),
+            updated_at: now_timestamp(),
+        }
+    }
+
+    pub fn is_succeeded(&self, task_id: &str) -> bool {
+        matches!(self.statuses.get(task_id), Some(TaskStatus::Succeeded)) && self.outputs.contains_key(task_id)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.plan.iter().all(|task| self.is_succeeded(&task.task_id))
+    }
+
+    pub fn set_status(&mut self, task_id: &str, status: TaskStatus) {
+        self.statuses.insert(task_id.to_string(), status);
+        self.updated_at = now_timestamp();
+    }
+
+    pub fn record_output(&mut self, task_id: &str, output: AgentOutput) {
+        self.outputs.insert(task_id.to_string(), output);
+        self.set_status(task_id, TaskStatus::Succeeded);
+    }
+
+    /// Rebuilds the plan for a resume. Completed tasks are kept as they are; every other
+    /// task is re-issued under a new id linked to the interrupted one through `retry_of`
+    /// and starts out as `Retried`. Dependencies are rewired to the new ids.
+    pub fn prepare_resume(self) -> Self {
+        let renamed: HashMap<String, String> = self
+            .plan
+            .iter()
+            .filter(|task| !self.is_succeeded(&task.task_id))
+            .map(|task| (task.task_id.clone(), uuid::Uuid::new_v4().to_string()))
+            .collect();
+
+        let mut resumed = GoalCheckpoint {
+            plan: vec![],
+            statuses: HashMap::new(),
+            outputs: HashMap::new(),
+            updated_at: now_timestamp(),
+            ..self.clone()
+        };
+
+        for mut task in self.plan {
+            for dep in task.depends_on.iter_mut() {
+                if let Some(new_id) = renamed.get(dep) {
+                    *dep = new_id.clone();
+                }
+            }
+
+            match renamed.get(&task.task_id) {
+                Some(new_id) => {
+                    let previous_id = std::mem::replace(&mut task.task_id, new_id.clone());
+                    task.context.retry_of = Some(previous_id.clone());
+                    task.status = TaskStatus::Retried { previous_id };
+                    resumed.statuses.insert(task.task_id.clone(), task.status.clone());
+                }
+                None => {
+                    resumed.statuses.insert(task.task_id.clone(), TaskStatus::Succeeded);
+                    if let Some(output) = self.outputs.get(&task.task_id) {
+                        resumed.outputs.insert(task.task_id.clone(), output.clone());
+                    }
+                }
+            }
+            resumed.plan.push(task);
+        }
+
+        resumed
+    }
+
+    pub fn summary(&self) -> ResumableGoal {
+        ResumableGoal {
+            goal_id: self.goal_id.clone(),
+            plan_id: self.plan_id.clone(),
+            completed: self.plan.iter().filter(|t| self.is_succeeded(&t.task_id)).count(),
+            total: self.plan.len(),
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+fn checkpoint_dir() -> PathBuf {
+    dirs::home_dir()
+        .expect("No home dir")
+        .join("WinterData/checkpoints")
+}
+
+/// Goal ids come from the frontend and become file names, so only UUIDs are accepted
+fn check_goal_id(goal_id: &str) -> std::io::Result<()> {
+    uuid::Uuid::parse_str(goal_id).map(|_| ()).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid goal id: {:?}", goal_id),
+        )
+    })
+}
+
+/// `<goal>.json` for a task graph given directly, `<goal>--<plan>.json` for a planner plan
+fn checkpoint_path(goal_id: &str, plan_id: Option<&str>) -> std::io::Result<PathBuf> {
+    check_goal_id(goal_id)?;
+    let file_name = match plan_id {
+        None => goal_id.to_string(),
+        Some(plan_id) => format!("{}--{}", goal_id, escape_plan_id(plan_id)),
+    };
+    Ok(checkpoint_dir().join(format!("{}.json", file_name)))
+}
+
+/// Plan ids come from the planner; every byte other than an ASCII letter, digit or `-` is
+/// written as `_` and two hex digits, so distinct plan ids never share a file name
+fn escape_plan_id(plan_id: &str) -> String {
+    plan_id
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' => (b as char).to_string(),
+            _ => format!("_{:02x}", b),
+        })
+        .collect()
+}
+
+/// Writes to a temporary file first so a crash mid-write never leaves a torn checkpoint
+pub fn save_checkpoint(checkpoint: &GoalCheckpoint) -> std::io::Result<()> {
+    let path = checkpoint_path(&checkpoint.goal_id, checkpoint.plan_id.as_deref())?;
+    fs::create_dir_all(checkpoint_dir())?;
+
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(checkpoint)?)?;
+    fs::rename(tmp, path)
+}
+
+pub fn load_checkpoint(goal_id: &str, plan_id: Option<&str>) -> std::io::Result<GoalCheckpoint> {
+    let data = fs::read_to_string(checkpoint_path(goal_id, plan_id)?)?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+pub fn remove_checkpoint(goal_id: &str, plan_id: Option<&str>) -> std::io::Result<()> {
+    let path = checkpoint_path(goal_id, plan_id)?;
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Removes the checkpoints of every plan of the goal
+pub fn remove_goal_checkpoints(goal_id: &str) -> std::io::Result<()> {
+    check_goal_id(goal_id)?;
+    for checkpoint in load_checkpoints()? {
+        if checkpoint.goal_id == goal_id {
+            remove_checkpoint(goal_id, checkpoint.plan_id.as_deref())?;
+        }
+    }
+    Ok(())
+}
+
+fn load_checkpoints() -> std::io::Result<Vec<GoalCheckpoint>> {
+    let folder = checkpoint_dir();
+    if !folder.exists() {
+        return Ok(vec![]);
+    }
+
+    Ok(fs::read_dir(folder)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|data| serde_json::from_str::<GoalCheckpoint>(&data).ok())
+        .collect())
+}
+
+/// Plans that did not run to completion, most recently active first
+pub fn list_unfinished_goals() -> std::io::Result<Vec<ResumableGoal>> {
+    let mut goals: Vec<ResumableGoal> = load_checkpoints()?
+        .into_iter()
+        .filter(|checkpoint| !checkpoint.is_finished())
+        .map(|checkpoint| checkpoint.summary())
+        .collect();
+    goals.sort_by_key(|goal| std::cmp::Reverse(goal.updated_at));
+    Ok(goals)
+}
+
+#[tauri::command]
+pub fn list_resumable_goals() -> Result<Vec<ResumableGoal>, String> {
+    list_unfinished_goals().map_err(|e| e.to_string())
+}
+
+/// Forgets one plan of the goal, or all of them if no plan is given
+#[tauri::command]
+pub fn discard_goal_checkpoint(goal_id: String, plan_id: Option<String>) -> Result<(), String> {
+    match plan_id {
+        Some(plan_id) => remove_checkpoint(&goal_id, Some(&plan_id)),
+        None => remove_goal_checkpoints(&goal_id),
+    }
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans_of_a_goal_get_their_own_checkpoint() {
+        let goal_id = uuid::Uuid::new_v4().to_string();
+        let top = checkpoint_path(&goal_id, None).unwrap();
+        let first = checkpoint_path(&goal_id, Some("plan-1")).unwrap();
+        let second = checkpoint_path(&goal_id, Some("plan-2")).unwrap();
+
+        assert_ne!(top, first);
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), Some(checkpoint_dir().as_path()));
+        assert_eq!(
+            checkpoint_path(&goal_id, Some("../x")).unwrap().parent(),
+            Some(checkpoint_dir().as_path())
+        );
+    }
+
+    #[test]
+    fn similar_plan_ids_get_distinct_checkpoints() {
+        let goal_id = uuid::Uuid::new_v4().to_string();
+        let paths: Vec<PathBuf> = ["a/b", "a_b", "a_2fb", "a.b"]
+            .iter()
+            .map(|plan_id| checkpoint_path(&goal_id, Some(plan_id)).unwrap())
+            .collect();
+
+        for (i, path) in paths.iter().enumerate() {
+            assert!(!paths[i + 1..].contains(path), "{} is shared", path.display());
+        }
+    }
+
+    #[test]
+    fn goal_ids_must_be_uuids() {
+        for goal_id in ["", "..", "../escape", "goal/1", "not-a-uuid"] {
+            assert!(checkpoint_path(goal_id, None).is_err(), "{:?} should be rejected", goal_id);
+            assert!(load_checkpoint(goal_id, None).is_err());
+            assert!(remove_checkpoint(goal_id, None).is_err());
+        }
+    }
+}
@@ -1,11 +1,18 @@
-use crate::agents::memory::{
+use crate::memory::{
     task_memory::TaskMemoryHandle,
     session_memory::SessionMemoryHandle,
     project_memory::ProjectMemoryHandle,
     global_memory::GlobalMemoryHandle,
 };
-use crate::agents::memory::planner_memory::PlannerMemory;
-use crate::agents::tools::registry::ToolRegistry;
+use crate::memory::planner_memory::PlannerMemory;
+use crate::tools::registry::ToolRegistry;
+use crate::llm::backend::LlmBackend;
+use crate::orchestrator::events::EventSink;
+use crate::orchestrator::types::ExecutionMode;
+use crate::tools::change_set::ChangeSet;
+use crate::tools::path_jail::PathJail;
+use crate::tools::tool::ToolContext;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Clone)]
 pub struct AgentContext {
@@ -15,4 +22,26 @@ pub struct AgentContext {
     pub global: GlobalMemoryHandle,
     pub tool_registry: std::sync::Arc<ToolRegistry>,
     pub planner_memory: PlannerMemory,
+    pub llm: std::sync::Arc<dyn LlmBackend>,
+    pub events: std::sync::Arc<dyn EventSink>,
+    /// Effective mode of the task being handled, resolved by the orchestrator
+    pub execution_mode: ExecutionMode,
+    /// File effects produced by the task being handled
+    pub changes: ChangeSet,
+    /// Jail of the currently open project, None until a project is opened
+    pub sandbox: Option<std::sync::Arc<PathJail>>,
+    /// Fires when the task being handled is cancelled; subtasks get child tokens
+    pub cancel: CancellationToken,
+}
+
+impl AgentContext {
+    /// Tool settings for the task being handled
+    pub fn tool_context(&self) -> ToolContext {
+        ToolContext {
+            mode: self.execution_mode,
+            changes: self.changes.clone(),
+            sandbox: self.sandbox.clone(),
+            cancel: self.cancel.clone(),
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::orchestrator::types::Capability;
+
+const DEADLINE_PATH: &str = "WinterData/deadlines.json";
+
+/// Default task deadlines in seconds by capability, for tasks whose agent card and context
+/// set none. Built in and overridable per capability in `WinterData/deadlines.json`,
+/// e.g. `{"CodeGen": 1200}`.
+#[derive(Debug, Clone)]
+pub struct Deadlines {
+    default: Option<u64>,
+    by_capability: HashMap<Capability, u64>,
+}
+
+impl Deadlines {
+    pub fn builtin() -> Self {
+        let mut by_capability = HashMap::new();
+        by_capability.insert(Capability::Requirements, 300);
+        by_capability.insert(Capability::Planning, 300);
+        by_capability.insert(Capability::CodeGen, 600);
+        by_capability.insert(Capability::Testing, 900);
+        by_capability.insert(Capability::Deployment, 900);
+
+        Self {
+            default: Some(600),
+            by_capability,
+        }
+    }
+
+    /// Built-in deadlines overlaid with the user's deadline file, if any
+    pub fn load() -> Self {
+        let mut deadlines = Self::builtin();
+        let path = dirs::home_dir().expect("No home dir").join(DEADLINE_PATH);
+
+        if let Ok(content) = fs::read_to_string(&path) {
+            match serde_json::from_str::<HashMap<Capability, u64>>(&content) {
+                Ok(overrides) => deadlines.by_capability.extend(overrides),
+                Err(e) => eprintln!("[warn] Ignoring invalid {}: {}", DEADLINE_PATH, e),
+            }
+        }
+        deadlines
+    }
+
+    pub fn for_capability(&self, capability: &Capability) -> Option<u64> {
+        self.by_capability.get(capability).copied().or(self.default)
+    }
+}
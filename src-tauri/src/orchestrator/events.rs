@@ -0,0 +1,23 @@
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+/// Where the orchestrator and agents publish progress for the frontend.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &str, payload: Value);
+}
+
+/// Forwards events to the webview as Tauri events
+pub struct TauriEventSink(pub AppHandle);
+
+impl EventSink for TauriEventSink {
+    fn emit(&self, event: &str, payload: Value) {
+        let _ = self.0.emit(event, payload);
+    }
+}
+
+/// Drops every event. Used before the app handle exists and in headless runs.
+pub struct NullEventSink;
+
+impl EventSink for NullEventSink {
+    fn emit(&self, _event: &str, _payload: Value) {}
+}
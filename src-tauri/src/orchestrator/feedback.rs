@@ -1,10 +1,9 @@
-use crate::agents::orchestrator::protocol::EvaluationNote;
-use crate::agents::orchestrator::types::AgentTask;
+use crate::orchestrator::protocol::EvaluationNote;
+use crate::orchestrator::types::AgentTask;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::fs::OpenOptions;
 use std::io::BufReader;
-use std::path::PathBuf;
 
 const FEEDBACK_PATH: &str = "WinterData/feedback_queue.json";
 const PLAN_QUEUE_PATH: &str = "WinterData/plan_feedback_queue.json";
@@ -53,7 +52,7 @@ pub fn load_feedback_queue() -> std::io::Result<Vec<FeedbackItem>> {
     Ok(items)
 }
 pub fn load_plan_feedback_queue() -> std::io::Result<Vec<PlanFeedbackItem>> {
-    let path = PathBuf::from(dirs::home_dir().unwrap()).join(PLAN_QUEUE_PATH);
+    let path = dirs::home_dir().unwrap().join(PLAN_QUEUE_PATH);
     if !path.exists() {
         return Ok(vec![]);
     }
@@ -65,7 +64,7 @@ pub fn write_plan_feedback_item(item: &PlanFeedbackItem) -> std::io::Result<()>
     let mut queue = load_plan_feedback_queue().unwrap_or_default();
     queue.push(item.clone());
 
-    let path = PathBuf::from(dirs::home_dir().unwrap()).join(PLAN_QUEUE_PATH);
+    let path = dirs::home_dir().unwrap().join(PLAN_QUEUE_PATH);
     fs::create_dir_all(path.parent().unwrap())?;
     let data = serde_json::to_string_pretty(&queue)?;
     fs::write(path, data)
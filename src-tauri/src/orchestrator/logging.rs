@@ -3,9 +3,8 @@ use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Serialize, Deserialize};
-use uuid::Timestamp;
-use crate::agents::orchestrator::protocol::{AgentResponse};
-use crate::agents::orchestrator::types::{AgentTask,TaskStatus};
+use crate::orchestrator::protocol::{AgentResponse};
+use crate::orchestrator::types::AgentTask;
 
 #[derive(Serialize, Deserialize)]
 pub struct TaskLogEntry{
@@ -30,4 +29,7 @@ pub fn log_task_to_disk(task: &AgentTask, response: &AgentResponse){
 
     let content = serde_json::to_string(&log).unwrap_or_else(|_| "{}".to_string());
     let _ =fs::write(path, content);
-}
\ No newline at end of file
+}
+fn get_logs_dir() -> PathBuf {
+    dirs::home_dir().expect("No home dir").join("WinterData/logs")
+}
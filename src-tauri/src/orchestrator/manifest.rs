@@ -0,0 +1,284 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::orchestrator::types::{AgentCard, Capability, ExecutionMode, Precondition, SkillGraph};
+
+/// Emitted with a `ManifestReport` whenever the manifest agents are (re)loaded
+pub const AGENT_MANIFESTS_EVENT: &str = "agent-manifests";
+
+/// Highest priority a project manifest gets, so it never outranks a built-in agent (priority 0)
+const PROJECT_MAX_PRIORITY: i32 = -1;
+
+/// Where a manifest was found. Project manifests come with the repository and are not trusted:
+/// they rank below built-in agents and cannot default to Execute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestScope {
+    Global,
+    Project,
+}
+
+/// A prompt-only agent declared in `WinterData/agents/` or `<project>/.winter/agents/`
+/// as a `.toml` or `.json` file, e.g.
+///
+/// ```toml
+/// id = "changelog"
+/// description = "Writes changelog entries for finished work"
+/// capability = "Documentation"
+/// prompt_template = "changelog.md"
+/// allowed_tools = ["FileTool"]
+/// execution_mode = "Simulate"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentManifest {
+    pub id: String,
+    pub description: String,
+    pub capability: Capability,
+    #[serde(default)]
+    pub subskills: Vec<Capability>,
+    #[serde(default = "default_schema")]
+    pub input_schema: String,
+    #[serde(default = "default_schema")]
+    pub output_schema: String,
+    /// Prompt file, relative to the manifest and inside the manifest's directory. `{{payload}}`, `{{task_type}}`, `{{goal_id}}`
+    /// and `{{dependencies}}` are filled in per task.
+    pub prompt_template: PathBuf,
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    #[serde(default = "default_execution_mode")]
+    pub execution_mode: ExecutionMode,
+    #[serde(default = "default_timeout")]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub preconditions: Vec<Precondition>,
+}
+
+fn default_schema() -> String {
+    "text".into()
+}
+
+fn default_execution_mode() -> ExecutionMode {
+    ExecutionMode::Simulate
+}
+
+fn default_timeout() -> Option<u64> {
+    Some(300)
+}
+
+impl AgentManifest {
+    pub fn card(&self) -> AgentCard {
+        AgentCard {
+            id: self.id.clone(),
+            description: self.description.clone(),
+            skills: SkillGraph {
+                root: self.capability.clone(),
+                subskills: self.subskills.clone(),
+            },
+            input_schema: self.input_schema.clone(),
+            output_schema: self.output_schema.clone(),
+            default_execution: self.execution_mode,
+            timeout_secs: self.timeout_secs,
+            priority: self.priority,
+            preconditions: self.preconditions.clone(),
+            allowed_tools: self.allowed_tools.clone(),
+        }
+    }
+}
+
+/// A validated manifest together with its prompt template
+#[derive(Debug, Clone)]
+pub struct LoadedManifest {
+    pub manifest: AgentManifest,
+    pub template: String,
+    pub source: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedAgent {
+    pub id: String,
+    pub source: PathBuf,
+}
+
+/// Outcome of a manifest (re)load, sent to the UI
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ManifestReport {
+    pub loaded: Vec<LoadedAgent>,
+    pub errors: Vec<ManifestError>,
+}
+
+fn global_manifest_dir() -> PathBuf {
+    dirs::home_dir().expect("No home dir").join("WinterData/agents")
+}
+
+/// Global manifests first, then the project's, so a project can override a global agent
+pub fn manifest_dirs(project_root: Option<&Path>) -> Vec<PathBuf> {
+    let mut folders = vec![global_manifest_dir()];
+    if let Some(root) = project_root {
+        folders.push(root.join(".winter/agents"));
+    }
+    folders
+}
+
+fn manifest_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "toml" || ext == "json")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Modification times of every file in the manifest directories (manifests and the
+/// templates next to them); a change means a reload is due
+pub fn manifest_fingerprint(dirs: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut files: Vec<(PathBuf, Option<SystemTime>)> = dirs
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let modified = entry.metadata().and_then(|m| m.modified()).ok();
+            (entry.path(), modified)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Parses and validates one manifest. `reserved_ids` are ids of built-in agents.
+pub fn load_manifest(
+    path: &Path,
+    scope: ManifestScope,
+    reserved_ids: &HashSet<String>,
+    known_tools: &[String],
+) -> Result<LoadedManifest, ManifestError> {
+    let error = |message: String| ManifestError {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let content = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    let mut manifest: AgentManifest = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(|e| error(e.to_string()))?,
+        _ => serde_json::from_str(&content).map_err(|e| error(e.to_string()))?,
+    };
+
+    if manifest.id.is_empty()
+        || !manifest
+            .id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(error(format!(
+            "Invalid id `{}`: use letters, digits, `-` and `_`",
+            manifest.id
+        )));
+    }
+    if reserved_ids.contains(&manifest.id) {
+        return Err(error(format!("Id `{}` is used by a built-in agent", manifest.id)));
+    }
+    if let Some(tool) = manifest.allowed_tools.iter().find(|tool| !known_tools.contains(tool)) {
+        return Err(error(format!("Unknown tool `{}`", tool)));
+    }
+
+    if scope == ManifestScope::Project {
+        if manifest.execution_mode == ExecutionMode::Execute {
+            return Err(error("Project manifests cannot default to Execute".into()));
+        }
+        manifest.priority = manifest.priority.min(PROJECT_MAX_PRIORITY);
+    }
+
+    // The template must stay inside the manifest's directory, symlinks and `..` included
+    let manifest_dir = fs::canonicalize(path.parent().unwrap_or(Path::new(".")))
+        .map_err(|e| error(format!("Cannot resolve manifest directory: {}", e)))?;
+    let template_path = fs::canonicalize(manifest_dir.join(&manifest.prompt_template)).map_err(|e| {
+        error(format!(
+            "Cannot read prompt template {}: {}",
+            manifest.prompt_template.display(),
+            e
+        ))
+    })?;
+    if !template_path.starts_with(&manifest_dir) {
+        return Err(error(format!(
+            "Prompt template {} is outside the manifest directory",
+            manifest.prompt_template.display()
+        )));
+    }
+    let template = fs::read_to_string(&template_path).map_err(|e| {
+        error(format!(
+            "Cannot read prompt template {}: {}",
+            template_path.display(),
+            e
+        ))
+    })?;
+    if template.trim().is_empty() {
+        return Err(error(format!("Prompt template {} is empty", template_path.display())));
+    }
+
+    Ok(LoadedManifest {
+        manifest,
+        template,
+        source: path.to_path_buf(),
+    })
+}
+
+/// Loads every manifest in `dirs`. A later directory overrides an earlier one's agent with the
+/// same id; a duplicate id within one directory is an error.
+pub fn scan_manifests(dirs: &[PathBuf], reserved_ids: &HashSet<String>, known_tools: &[String]) -> (Vec<LoadedManifest>, Vec<ManifestError>) {
+    let mut loaded: Vec<LoadedManifest> = vec![];
+    let mut errors = vec![];
+
+    let global_dir = global_manifest_dir();
+    for dir in dirs {
+        let scope = if *dir == global_dir {
+            ManifestScope::Global
+        } else {
+            ManifestScope::Project
+        };
+        let mut seen_here = HashSet::new();
+        for path in manifest_files(dir) {
+            match load_manifest(&path, scope, reserved_ids, known_tools) {
+                Ok(manifest) => {
+                    if !seen_here.insert(manifest.manifest.id.clone()) {
+                        errors.push(ManifestError {
+                            path,
+                            message: format!("Duplicate agent id `{}`", manifest.manifest.id),
+                        });
+                        continue;
+                    }
+                    loaded.retain(|existing| existing.manifest.id != manifest.manifest.id);
+                    loaded.push(manifest);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+
+    (loaded, errors)
+}
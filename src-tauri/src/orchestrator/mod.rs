@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod orchestrator;
 pub mod registry;
 pub mod protocol;
@@ -14,3 +15,12 @@ pub mod planning;
 pub mod hash;
 pub mod prompt_assembler;
 pub mod timeline;
+pub mod task_graph;
+pub mod events;
+pub mod checkpoint;
+pub mod cancellation;
+pub mod retry;
+pub mod deadline;
+pub mod skill_graph;
+pub mod manifest;
+pub mod approval;
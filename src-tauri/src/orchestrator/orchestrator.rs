@@ -1,85 +1,600 @@
+use crate::llm::structured::decode_content;
 use crate::memory::planner_memory::{PlannerMemory, PlannerMemoryEntry};
-use crate::memory::runtime_memory::DesignDecision;
-use crate::memory::session_memory::SessionMemoryHandle;
+use crate::memory::project_memory::DesignDecision;
+use crate::memory::retrieval;
 use crate::memory::task_memory::TaskMemoryHandle;
 use crate::memory::{
-    global_memory::GlobalMemoryHandle, project_memory::ProjectMemory,
+    global_memory::{context_link, GlobalMemoryEntry, GlobalMemoryHandle},
     session_memory::SessionMemory, task_memory::TaskMemory,
 };
+use crate::orchestrator::approval::{
+    wait_for_approval, ApprovalGate, ApprovalRequest, ApprovalSettings, APPROVAL_REQUESTED_EVENT,
+    APPROVAL_RESOLVED_EVENT,
+};
+use crate::orchestrator::cancellation::{enter_goal, goal_token, register_task, reset_goal, unregister_task};
+use crate::orchestrator::deadline::Deadlines;
+use crate::orchestrator::checkpoint::{load_checkpoint, remove_checkpoint, save_checkpoint, GoalCheckpoint};
 use crate::orchestrator::context::AgentContext;
 use crate::orchestrator::feedback::load_feedback_queue;
 use crate::orchestrator::hash::calculate_plan_hash;
 use crate::orchestrator::protocol::{
-    AgentError, AgentOutput, AgentResponse, PlannerOutput,
+    AgentError, AgentOutput, AgentResponse, ErrorKind, PlannerOutput,
 };
-use crate::orchestrator::registry::{AgentHandler, AgentMetadata, AgentRegistry};
+use crate::orchestrator::retry::RetryPolicies;
+use crate::orchestrator::skill_graph::{satisfied_by_project, CapabilityGraph, SkillGraphView};
+use crate::orchestrator::registry::{AgentHandler, AgentMetadata, AgentOrigin, AgentRegistry, Route};
 use crate::orchestrator::task_index::{append_to_task_index, TaskIndexEntry};
+use crate::orchestrator::task_graph::{blocking_dependency, TaskGraph};
 use crate::orchestrator::task_log::write_task_log;
-use crate::orchestrator::timeline::{append_timeline_event, TimelineEvent};
+use crate::orchestrator::timeline::{append_timeline_event, TimelineEvent, TIMELINE_EVENT};
 use crate::orchestrator::types::{
-    now_timestamp, AgentCard, AgentTask, AgentTaskContext, Capability, TaskStatus,
+    now_timestamp, AgentCard, AgentTask, Capability, ExecutionMode, TaskStatus,
 };
-use crate::tools::registry::ToolRegistry;
-use std::sync::{Arc, Mutex};
+use crate::tools::change_set::{record_applied, ChangeSet};
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
-use uuid::{uuid, Uuid};
+use uuid::Uuid;
 
-const MAX_RETRIES: usize = 3;
-const MAX_PLANNER_REVISIONS: u8 = 3;
+const MAX_PLANNER_REVISIONS: u32 = 3;
 const PLANNER_RETRY_THRESHOLD: u8 = 7;
 
 pub struct Orchestrator {
-    registry: AgentRegistry,
-    tool_registry: Arc<ToolRegistry>,
+    /// Built-in agents plus manifest agents, which are swapped on reload while tasks run
+    registry: RwLock<AgentRegistry>,
+    retry_policies: RetryPolicies,
+    deadlines: Deadlines,
+    capabilities: CapabilityGraph,
+    approvals: ApprovalSettings,
     pub task_memory: TaskMemory,
     pub session_memory: SessionMemory,
-    pub project_memory: ProjectMemoryHandle,
     pub global_memory: GlobalMemoryHandle,
 }
 
+impl Default for Orchestrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Orchestrator {
     pub fn new() -> Self {
         Self {
-            registry: AgentRegistry::new(),
-            tool_registry: Arc::new(ToolRegistry::new()),
+            registry: RwLock::new(AgentRegistry::new()),
+            retry_policies: RetryPolicies::load(),
+            deadlines: Deadlines::load(),
+            capabilities: CapabilityGraph::builtin(),
+            approvals: ApprovalSettings::load(),
             task_memory: TaskMemory::new(),
             session_memory: SessionMemory::new(),
-            project_memory: ProjectMemory::default(),
-            global_memory: GlobalMemoryHandle::default(),
+            global_memory: GlobalMemoryHandle::shared(),
         }
     }
+    /// Capabilities, their agents and prerequisite edges, for visualization
+    pub fn skill_graph(&self) -> SkillGraphView {
+        self.capabilities.view(&self.available_agents())
+    }
     /// register Agents into the orchestrator
     pub fn register_agent(
         &mut self,
         card: AgentCard,
         handler: Box<dyn AgentHandler + Send + Sync>,
     ) {
-        self.registry.register(card, handler);
+        self.registry.get_mut().unwrap().register(card, handler);
+    }
+    /// Swaps the manifest-defined agents for `agents`. Tasks already running keep their agent.
+    pub fn replace_manifest_agents(&self, agents: Vec<(AgentCard, Box<dyn AgentHandler + Send + Sync>, PathBuf)>) {
+        let mut registry = self.registry.write().unwrap();
+        registry.remove_manifest_agents();
+        for (card, handler, source) in agents {
+            registry.register_from(card, handler, AgentOrigin::Manifest(source));
+        }
     }
-    pub fn execute_task_graph(
+    /// Ids a manifest agent may not take
+    pub fn builtin_agent_ids(&self) -> HashSet<String> {
+        self.registry.read().unwrap().builtin_ids()
+    }
+    /// Executes a planner task graph. Each task starts as soon as the tasks in its `depends_on`
+    /// have succeeded, independent branches run concurrently, and each task receives
+    /// the outputs of its dependencies. Dependents of a failed task are skipped.
+    /// Progress is checkpointed per goal and plan so the graph can be resumed after a restart;
+    /// `plan_id` names the planner plan the graph comes from, if any.
+    /// Missing prerequisite capabilities not yet in project memory are added as tasks before the graph runs.
+    pub async fn execute_task_graph(
         &self,
         task_graph: Vec<AgentTask>,
+        plan_id: Option<&str>,
         ctx: AgentContext,
     ) -> AgentResponse {
-        for task in task_graph {
-            println!("Executing planner-subtask: {}", task.task_type);
+        // Goal ids name the checkpoint files, so a graph with any other id is refused up front
+        // rather than running without checkpoints
+        if let Some(invalid) = task_graph
+            .iter()
+            .filter_map(|task| task.context.goal_id.as_deref())
+            .find(|goal_id| Uuid::parse_str(goal_id).is_err())
+        {
+            println!("[Orchestrator] Rejecting task graph: goal id {:?} is not a UUID", invalid);
+            return AgentResponse::error(
+                ErrorKind::InvalidInput,
+                &format!("Goal id {:?} is not a UUID", invalid),
+            );
+        }
+        let goal_id = task_graph
+            .iter()
+            .find_map(|task| task.context.goal_id.clone())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let task_graph: Vec<AgentTask> = task_graph
+            .into_iter()
+            .map(|mut task| {
+                task.context.goal_id.get_or_insert_with(|| goal_id.clone());
+                task
+            })
+            .collect();
+
+        // Add missing prerequisite tasks, then reject plans that still run a capability too early.
+        // Prerequisites the project already satisfies, in memory or on disk, count as done.
+        let satisfied = satisfied_by_project(&ctx.project);
+        let (task_graph, inserted) = self.capabilities.resolve_plan(task_graph, &satisfied);
+        if !inserted.is_empty() {
+            println!("[Orchestrator] Added {} prerequisite task(s) to goal {}", inserted.len(), goal_id);
+        }
+        let violations = self.capabilities.validate_plan(&task_graph, &satisfied);
+        if !violations.is_empty() {
+            let reasons: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            println!("[Orchestrator] Rejecting task graph: {}", reasons.join("; "));
+            return AgentResponse::error(ErrorKind::InvalidInput, &reasons.join("; "));
+        }
+
+        let checkpoint = GoalCheckpoint::new(&goal_id, plan_id, &task_graph);
+        self.run_checkpointed(checkpoint, ctx).await
+    }
+
+    /// Continues an interrupted goal from its checkpoint. Completed tasks keep their outputs;
+    /// the rest run again as retries of the interrupted tasks.
+    pub async fn resume_goal(&self, goal_id: &str, plan_id: Option<&str>, ctx: AgentContext) -> AgentResponse {
+        let checkpoint = match load_checkpoint(goal_id, plan_id) {
+            Ok(checkpoint) => checkpoint.prepare_resume(),
+            Err(e) => return AgentResponse::error(ErrorKind::Storage, &format!("No checkpoint for goal {}: {}", goal_id, e)),
+        };
+        reset_goal(goal_id);
+        println!(
+            "[Orchestrator] Resuming goal {} ({}/{} tasks done)",
+            goal_id,
+            checkpoint.outputs.len(),
+            checkpoint.plan.len()
+        );
+        self.run_checkpointed(checkpoint, ctx).await
+    }
+
+    async fn run_checkpointed(&self, checkpoint: GoalCheckpoint, ctx: AgentContext) -> AgentResponse {
+        let graph = match TaskGraph::build(checkpoint.plan.clone()) {
+            Ok(graph) => graph,
+            Err(err) => {
+                println!("[Orchestrator] Rejecting task graph: {}", err);
+                return AgentResponse::error(ErrorKind::InvalidInput, &err.to_string());
+            }
+        };
+
+        let goal_id = checkpoint.goal_id.clone();
+        let plan_id = checkpoint.plan_id.clone();
+        let mut outputs: HashMap<String, AgentOutput> = checkpoint.outputs.clone();
+        let mut failed: HashMap<String, TaskStatus> = HashMap::new();
+        let checkpoint = Mutex::new(checkpoint);
+        let save = |checkpoint: &GoalCheckpoint| {
+            if let Err(e) = save_checkpoint(checkpoint) {
+                eprintln!("[warn] Failed to checkpoint goal {}: {}", checkpoint.goal_id, e);
+            }
+        };
+        save(&checkpoint.lock().unwrap());
+
+        // The goal stays registered for `cancel_goal` until its graph is done
+        let goal_scope = enter_goal(&goal_id);
+        let goal_cancel = goal_scope.token().clone();
+        let run_task = |task: AgentTask| {
+            let ctx = ctx.clone();
+            let checkpoint = &checkpoint;
+            async move {
+                println!("Executing planner-subtask: {}", task.task_type);
+                let task_id = task.task_id.clone();
+                let response = self.handle(task, ctx).await;
+
+                let mut checkpoint = checkpoint.lock().unwrap();
+                match &response {
+                    AgentResponse::Success(output) => checkpoint.record_output(&task_id, output.clone()),
+                    AgentResponse::Error(err) => checkpoint.set_status(&task_id, err.task_status()),
+                }
+                save(&checkpoint);
+                (task_id, response)
+            }
+        };
+
+        // Planner order, every task after its dependencies
+        let mut waiting: Vec<&AgentTask> = vec![];
+        for task in graph.layers().iter().flatten() {
+            if outputs.contains_key(&task.task_id) {
+                println!("Planner-subtask {} already completed", task.task_id);
+            } else {
+                waiting.push(task);
+            }
+        }
 
-            let result = self.handle(task.clone(), ctx.clone());
+        // Each task starts as soon as its own dependencies succeeded, so a slow branch
+        // only holds up the tasks that depend on it
+        let mut running = FuturesUnordered::new();
+        loop {
+            let mut still_waiting = vec![];
+            for task in waiting {
+                if goal_cancel.is_cancelled() || ctx.cancel.is_cancelled() {
+                    checkpoint.lock().unwrap().set_status(&task.task_id, TaskStatus::Cancelled);
+                    failed.insert(task.task_id.clone(), TaskStatus::Cancelled);
+                    continue;
+                }
+                if let Some(blocker) = blocking_dependency(task, |dep| failed.contains_key(dep)) {
+                    println!("Skipping planner-subtask {}: blocked by {}", task.task_id, blocker);
+                    let status = TaskStatus::Skipped {
+                        blocked_by: blocker.clone(),
+                    };
+                    checkpoint.lock().unwrap().set_status(&task.task_id, status.clone());
+                    failed.insert(task.task_id.clone(), status);
+                    continue;
+                }
+                if !task.depends_on.iter().all(|dep| outputs.contains_key(dep)) {
+                    still_waiting.push(task);
+                    continue;
+                }
+
+                let mut task = task.clone();
+                for dep in &task.depends_on {
+                    task.context
+                        .dependency_outputs
+                        .insert(dep.clone(), outputs[dep].clone());
+                }
+                checkpoint.lock().unwrap().set_status(&task.task_id, TaskStatus::Running);
+                running.push(run_task(task));
+            }
+            waiting = still_waiting;
+            save(&checkpoint.lock().unwrap());
+
+            let Some((task_id, result)) = running.next().await else {
+                break;
+            };
             match result {
-                AgentResponse::Error(_) => {
-                    println!("Halting chain due to failure");
-                    return result;
+                AgentResponse::Success(output) => {
+                    println!("Chained task {} succeeded", task_id);
+                    outputs.insert(task_id, output);
                 }
-                AgentResponse::Success(_) => {
-                    println!("Chained task succeeded");
+                AgentResponse::Error(err) => {
+                    println!("Chained task {} failed: {}", task_id, err.reason);
+                    failed.insert(task_id, err.task_status());
                 }
             }
         }
-        AgentResponse::success("All planner tasks executed", "orchestrator")
+
+        if failed.is_empty() {
+            // Nothing left to resume
+            if let Err(e) = remove_checkpoint(&goal_id, plan_id.as_deref()) {
+                eprintln!("[warn] Failed to remove checkpoint of goal {}: {}", goal_id, e);
+            }
+            return AgentResponse::success("All planner tasks executed", "orchestrator");
+        }
+
+        let summary = failed
+            .iter()
+            .map(|(id, status)| format!("{}: {:?}", id, status))
+            .collect::<Vec<_>>()
+            .join("; ");
+        AgentResponse::error(
+            ErrorKind::Internal,
+            &format!(
+                "{} of {} planner tasks did not complete ({})",
+                failed.len(),
+                graph.len(),
+                summary
+            ),
+        )
+    }
+
+    /// Routes an AgentTask to the appropriate agent by capability.
+    /// Boxed because planner, critique and subtask handling recurse into `handle`.
+    /// The task is registered for `cancel_task` while it runs; its subtasks inherit its token.
+    /// Failures the capability's retry policy allows are retried after a backoff.
+    pub fn handle<'a>(
+        &'a self,
+        task: AgentTask,
+        ctx: AgentContext,
+    ) -> BoxFuture<'a, AgentResponse> {
+        self.handle_attempt(task, ctx, false)
+    }
+
+    /// One attempt of `handle`. `execute_approved` is set once the user allowed the task to
+    /// run in Execute mode, so later attempts do not ask again.
+    fn handle_attempt<'a>(
+        &'a self,
+        task: AgentTask,
+        mut ctx: AgentContext,
+        mut execute_approved: bool,
+    ) -> BoxFuture<'a, AgentResponse> {
+        Box::pin(async move {
+            let task_id = task.task_id.clone();
+            ctx.cancel = register_task(&task_id, &ctx.cancel);
+            let _goal_scope = task.context.goal_id.as_deref().map(enter_goal);
+            let response = self
+                .handle_registered(task.clone(), ctx.clone(), &mut execute_approved)
+                .await;
+            let response = self.retry_if_allowed(task, ctx, response, execute_approved).await;
+            unregister_task(&task_id);
+            response
+        })
+    }
+
+    /// Runs one agent on the task with its own execution mode, change set, tool view, deadline
+    /// and cancellation. Staged file changes are applied only if the agent succeeded.
+    async fn run_agent(&self, agent: &AgentMetadata, task: &AgentTask, ctx: &mut AgentContext) -> AgentResponse {
+        let task_id = &task.task_id;
+
+        // Resolve the effective execution mode and give the task its own change set
+        ctx.execution_mode =
+            ExecutionMode::resolve(agent.card.default_execution, task.context.execution_mode);
+        ctx.changes = ChangeSet::new();
+
+        let _busy = agent.begin_task();
+        // The agent only sees the tools its card allows. Its view is a copy, so `ctx` keeps
+        // the orchestrator's registry for the critique and plan tasks that follow.
+        let mut agent_ctx = ctx.clone();
+        agent_ctx.tool_registry = Arc::new(ctx.tool_registry.scoped(
+            &agent.card.id,
+            task_id,
+            &agent.card.allowed_tools,
+        ));
+
+        //Execute the Agent task, bounded by its deadline and by task/goal cancellation.
+        // Without a deadline on the card or the task, the capability's default applies.
+        let timeout = [agent.card.timeout_secs, task.context.timeout_secs]
+            .into_iter()
+            .flatten()
+            .min()
+            .or_else(|| {
+                let capability = task.task_type.parse::<Capability>().ok()?;
+                self.deadlines.for_capability(&capability)
+            });
+        let goal_cancel = task
+            .context
+            .goal_id
+            .as_deref()
+            .map(goal_token)
+            .unwrap_or_default();
+        let mut response = tokio::select! {
+            response = run_with_deadline(agent.handler.handle_task(task.clone(), agent_ctx), timeout) => response,
+            _ = ctx.cancel.cancelled() => AgentResponse::cancelled(),
+            _ = goal_cancel.cancelled() => {
+                ctx.cancel.cancel();
+                AgentResponse::cancelled()
+            }
+        };
+
+        // Staged file changes are applied only if the whole task succeeded
+        if let AgentResponse::Success(output) = &mut response {
+            if !ctx.changes.is_empty() {
+                output.change_set = Some(ctx.changes.changes());
+            }
+        }
+        if !ctx.changes.is_empty() && ctx.execution_mode.applies_effects() {
+            response = match response {
+                AgentResponse::Success(output) => {
+                    let project_root = ctx.sandbox.as_ref().map(|jail| jail.root().to_path_buf());
+                    let gated = self
+                        .approvals
+                        .gated_changes(&ctx.changes.changes(), project_root.as_deref());
+                    let approval = if gated.is_empty() {
+                        Ok(())
+                    } else {
+                        let mut request = ApprovalRequest::new(
+                            ApprovalGate::FileWrite,
+                            task.context.goal_id.clone(),
+                            Some(task_id.clone()),
+                            format!("Apply {} file change(s) of agent {}", gated.len(), agent.card.id),
+                        );
+                        request.changes = Some(gated);
+                        self.await_approval(request, ctx).await
+                    };
+
+                    match approval.map(|_| ctx.changes.apply(ctx.sandbox.as_deref())) {
+                        Err(rejected) => {
+                            println!("[Orchestrator] Discarding rejected changes of task {}", task_id);
+                            rejected
+                        }
+                        Ok(Ok(applied)) => {
+                            if let Err(e) =
+                                record_applied(task_id, task.context.goal_id.clone(), applied)
+                            {
+                                eprintln!("[warn] Failed to record applied changes: {}", e);
+                            }
+                            if let Some(project_root) = &project_root {
+                                retrieval::mark_stale(project_root).await;
+                            }
+                            AgentResponse::Success(output)
+                        }
+                        Ok(Err(e)) => {
+                            println!("[Orchestrator] Change set rolled back for {}: {}", task_id, e);
+                            AgentResponse::error(ErrorKind::ChangeConflict, &e)
+                        }
+                    }
+                }
+                error => {
+                    println!("[Orchestrator] Discarding staged changes of failed task {}", task_id);
+                    error
+                }
+            };
+        }
+
+        response
+    }
+
+    /// Pauses for the user if the request's gate is enabled and records the answer as a
+    /// design decision. A rejection comes back as the response to return for the task.
+    async fn await_approval(&self, request: ApprovalRequest, ctx: &AgentContext) -> Result<(), AgentResponse> {
+        if !self.approvals.is_enabled(request.gate) {
+            return Ok(());
+        }
+        self.ask_user(request, ctx).await
+    }
+
+    /// Pauses for the user whether or not the request's gate is enabled
+    async fn ask_user(&self, request: ApprovalRequest, ctx: &AgentContext) -> Result<(), AgentResponse> {
+        let request_id = request.request_id.clone();
+        let goal_id = request.goal_id.clone();
+        let task_id = request.task_id.clone();
+        let gate = request.gate;
+        let summary = request.summary.clone();
+        ctx.events.emit(
+            APPROVAL_REQUESTED_EVENT,
+            serde_json::to_value(&request).unwrap_or_default(),
+        );
+
+        let decision = wait_for_approval(request, &ctx.cancel).await;
+        ctx.events.emit(
+            APPROVAL_RESOLVED_EVENT,
+            serde_json::json!({ "request_id": request_id, "decision": decision }),
+        );
+        record_decision(
+            ctx,
+            goal_id.as_deref(),
+            task_id.as_deref(),
+            DesignDecision {
+                id: format!("approval-{}", request_id),
+                summary: format!(
+                    "{} {:?}: {}",
+                    if decision.approved { "Approved" } else { "Rejected" },
+                    gate,
+                    summary
+                ),
+                made_by: "user".into(),
+                rationale: decision.reason.clone().unwrap_or_else(|| "N/A".into()),
+                timestamp: now_timestamp().to_string(),
+            },
+        );
+
+        if decision.approved {
+            Ok(())
+        } else if ctx.cancel.is_cancelled() {
+            Err(AgentResponse::cancelled())
+        } else {
+            let reason = decision.reason.unwrap_or_else(|| "no reason given".into());
+            Err(AgentResponse::error(
+                ErrorKind::Rejected,
+                &format!("{:?} step rejected: {}", gate, reason),
+            ))
+        }
+    }
+
+    /// Runs the next attempt of a failed task when its policy retries the error kind.
+    /// The attempt is a new task linked through `retry_of`, so each one is logged on its own.
+    async fn retry_if_allowed(
+        &self,
+        task: AgentTask,
+        ctx: AgentContext,
+        response: AgentResponse,
+        execute_approved: bool,
+    ) -> AgentResponse {
+        let AgentResponse::Error(err) = &response else {
+            return response;
+        };
+        let Ok(capability) = task.task_type.parse::<Capability>() else {
+            return response;
+        };
+        let policy = self.retry_policies.for_capability(&capability);
+        let Some(delay) = policy.next_delay(err.kind, task.context.attempt) else {
+            return response;
+        };
+
+        let retry = task.next_attempt();
+        println!(
+            "[Orchestrator] {:?} failure of {}; attempt {}/{} as {} in {:?}",
+            err.kind,
+            task.task_id,
+            retry.context.attempt + 1,
+            policy.max_attempts,
+            retry.task_id,
+            delay
+        );
+        record_timeline(
+            &ctx,
+            task.context.goal_id.as_deref(),
+            TimelineEvent::Retry {
+                task_id: retry.task_id.clone(),
+                retry_of: task.task_id.clone(),
+                reason: format!("{:?}: {}", err.kind, err.reason),
+                timestamp: now_timestamp(),
+            },
+        );
+
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => self.handle_attempt(retry, ctx, execute_approved).await,
+            _ = ctx.cancel.cancelled() => AgentResponse::cancelled(),
+        }
+    }
+
+    /// Asks once per task, before any candidate runs, whether the task may run in Execute mode.
+    /// Only needed when one of the candidates would run in it. A goal override that raises a
+    /// card's default to Execute is always asked about, even with the gate disabled.
+    async fn approve_execute_mode(
+        &self,
+        route: &Route,
+        task: &AgentTask,
+        ctx: &AgentContext,
+        execute_approved: &mut bool,
+    ) -> Result<(), AgentResponse> {
+        if *execute_approved {
+            return Ok(());
+        }
+        let (executing, widened): (Vec<String>, bool) = {
+            let registry = self.registry.read().unwrap();
+            let executing: Vec<Arc<AgentMetadata>> = route
+                .candidates
+                .iter()
+                .filter_map(|candidate| registry.agent(&candidate.agent_id))
+                .filter(|agent| {
+                    ExecutionMode::resolve(agent.card.default_execution, task.context.execution_mode)
+                        .applies_effects()
+                })
+                .collect();
+            (
+                executing.iter().map(|agent| agent.card.id.clone()).collect(),
+                executing.iter().any(|agent| !agent.card.default_execution.applies_effects()),
+            )
+        };
+        if executing.is_empty() {
+            return Ok(());
+        }
+
+        let request = ApprovalRequest::new(
+            ApprovalGate::BeforeExecute,
+            task.context.goal_id.clone(),
+            Some(task.task_id.clone()),
+            format!(
+                "Run {} task in Execute mode with agent {}",
+                task.task_type,
+                executing.join(" or ")
+            ),
+        );
+        if widened {
+            self.ask_user(request, ctx).await?;
+        } else {
+            self.await_approval(request, ctx).await?;
+        }
+        *execute_approved = true;
+        Ok(())
     }
 
-    /// Routes an AgentTask to the appropriate agent by capability
-    pub fn handle(&self, mut task: AgentTask, mut ctx: AgentContext) -> AgentResponse {
+    async fn handle_registered(
+        &self,
+        mut task: AgentTask,
+        mut ctx: AgentContext,
+        execute_approved: &mut bool,
+    ) -> AgentResponse {
         task.status = TaskStatus::Running;
         let task_id = task.task_id.clone();
         let task_type = task.task_type.clone();
@@ -88,29 +603,72 @@ impl Orchestrator {
             task.status = TaskStatus::Failed {
                 reason: "Unknown task type or capability.".into(),
             };
-            return AgentResponse::error("Unknown task type or capability.", false);
+            return AgentResponse::error(ErrorKind::Routing, "Unknown task type or capability.");
         };
 
-        let Some(agent) = self.registry.find_agent_for_task(&capability) else {
+        let route = self.registry.read().unwrap().route(&capability, &task, &ctx);
+        if route.candidates.is_empty() {
             task.status = TaskStatus::Failed {
                 reason: "No agent available for this task.".into(),
             };
-            let _ = ctx.task.lock().unwrap().save(
+            ctx.task.save(
                 &task_id,
-                &format!("Failed: no agent for capability {}", task_type),
+                &format!("Failed: no agent for capability {} ({})", task_type, route.describe()),
             );
-            return AgentResponse::error("No agent available for this task", false);
+            return AgentResponse::error(ErrorKind::Routing, "No agent available for this task");
         };
 
-        ctx.tool_registry = self.tool_registry.clone();
+        // Try the ranked candidates in order; a failed agent falls back to the next one
+        let mut route_trace = vec![route.describe()];
+        let mut response = AgentResponse::error(ErrorKind::Routing, "No agent ran");
+        let mut agent_id = String::new();
+        let approval = self.approve_execute_mode(&route, &task, &ctx, execute_approved).await;
+        let candidates = if approval.is_ok() { route.candidates.as_slice() } else { &[] };
+        if let Err(rejected) = approval {
+            response = rejected;
+        }
+        for candidate in candidates {
+            let agent = self.registry.read().unwrap().agent(&candidate.agent_id);
+            let Some(agent) = agent else {
+                continue;
+            };
+            agent_id = agent.card.id.clone();
+            response = self.run_agent(&agent, &task, &mut ctx).await;
 
-        //Execute the Agent task
-        let response = agent.handle_task(task.clone(), ctx.clone());
+            match &response {
+                AgentResponse::Success(_) => {
+                    route_trace.push(format!("route: handled by {}", agent_id));
+                    break;
+                }
+                AgentResponse::Error(err)
+                    if matches!(err.kind, ErrorKind::Cancelled | ErrorKind::Rejected | ErrorKind::InvalidInput) =>
+                {
+                    break;
+                }
+                AgentResponse::Error(err) => {
+                    println!("[Orchestrator] {} failed ({:?}), trying next candidate", agent_id, err.kind);
+                    route_trace.push(format!("route: {} failed with {:?}: {}", agent_id, err.kind, err.reason));
+                }
+            }
+        }
+
+        match &mut response {
+            AgentResponse::Success(output) => {
+                let mut trace = route_trace;
+                trace.extend(output.trace.take().unwrap_or_default());
+                output.trace = Some(trace);
+            }
+            AgentResponse::Error(err) => {
+                let mut trace = route_trace;
+                trace.extend(err.log_trace.take().unwrap_or_default());
+                err.log_trace = Some(trace);
+            }
+        }
 
         // Check if it's a planner output and requires critique before running sub-tasks
         if let AgentResponse::Success(output) = &response {
             if capability == Capability::Planning {
-                if let Ok(plan) = serde_json::from_value::<PlannerOutput>(output.clone()) {
+                if let Ok(plan) = decode_content::<PlannerOutput>(&output.content) {
                     println!("[Orchestrator] Plan received, routing to CritiqueAgent...");
 
                     let critique_task = AgentTask {
@@ -119,16 +677,30 @@ impl Orchestrator {
                         payload: serde_json::to_string(&output).unwrap_or_default(),
                         context: task.context.clone(),
                         status: TaskStatus::Pending,
+                        depends_on: vec![],
                     };
 
-                    let critique_response = self.handle(critique_task.clone(), ctx.clone());
+                    let critique_response = self.handle(critique_task.clone(), ctx.clone()).await;
 
                     response = match critique_response {
                         AgentResponse::Success(eval_output) => {
                             let score = eval_output.score.unwrap_or(10);
                             let revision = task.context.revision_id.unwrap_or(0);
+                            let retrying = score < PLANNER_RETRY_THRESHOLD && revision < MAX_PLANNER_REVISIONS;
 
-                            if score < PLANNER_RETRY_THRESHOLD && revision < MAX_PLANNER_REVISIONS {
+                            record_timeline(
+                                &ctx,
+                                task.context.goal_id.as_deref(),
+                                TimelineEvent::Critique {
+                                    task_id: critique_task.task_id.clone(),
+                                    plan_id: plan.plan_id.clone(),
+                                    score: eval_output.score,
+                                    approved: !retrying,
+                                    timestamp: now_timestamp(),
+                                },
+                            );
+
+                            if retrying {
                                 println!("[Orchestrator] Critique score {score} < threshold. Retrying Planner...");
 
                                 let mut retry_task = task.clone();
@@ -137,19 +709,26 @@ impl Orchestrator {
                                 retry_task.context.revision_id = Some(revision + 1);
                                 retry_task.status = TaskStatus::Pending;
 
-                                return self.handle(retry_task, ctx);
+                                record_timeline(
+                                    &ctx,
+                                    task.context.goal_id.as_deref(),
+                                    TimelineEvent::Retry {
+                                        task_id: retry_task.task_id.clone(),
+                                        retry_of: task.task_id.clone(),
+                                        reason: format!("Critique score {} below threshold", score),
+                                        timestamp: now_timestamp(),
+                                    },
+                                );
+
+                                return self.handle(retry_task, ctx).await;
                             }
 
                             println!("[Orchestrator] Critique approved. Executing plan...");
                             let entry = PlannerMemoryEntry {
                                 plan_id: plan.plan_id.clone(),
-                                goal_id: task
-                                    .context
-                                    .goal_id
-                                    .clone()
-                                    .unwrap_or_else(|| "unknown".to_string()),
+                                goal_id: task.context.goal_id.clone(),
                                 score: eval_output.score,
-                                status: TaskStatus::Succeeded,
+                                status: format!("{:?}", TaskStatus::Succeeded),
                                 feedback_tags: None,
                                 revision_id: plan.revision_id,
                                 plan_hash: Some(calculate_plan_hash(&plan.task_graph)),
@@ -158,7 +737,8 @@ impl Orchestrator {
                                     .unwrap()
                                     .as_secs(),
                             };
-                            ctx.planner_memory.add_entry(&entry.goal_id, entry);
+                            let goal_key = entry.goal_id.clone().unwrap_or_else(|| "unknown".to_string());
+                            ctx.planner_memory.add_entry(&goal_key, entry);
 
                             let decision = DesignDecision {
                                 id: format!("plan-{}", plan.plan_id),
@@ -171,9 +751,36 @@ impl Orchestrator {
                                 rationale: plan.feedback_notes.unwrap_or_else(|| "N/A".into()),
                                 timestamp: now_timestamp().to_string(),
                             };
-                            ctx.project.lock().unwrap().write_decision(decision);
+                            record_decision(&ctx, task.context.goal_id.as_deref(), Some(&task.task_id), decision);
 
-                            self.execute_task_graph(plan.task_graph, ctx.clone())
+                            // Plan tasks belong to the planning task's goal, whatever goal id the
+                            // planner wrote, and inherit its execution mode override and opt-out
+                            // of prerequisite insertion
+                            let task_graph = plan
+                                .task_graph
+                                .into_iter()
+                                .map(|mut planned| {
+                                    planned.context.goal_id = task.context.goal_id.clone();
+                                    planned.context.execution_mode = planned
+                                        .context
+                                        .execution_mode
+                                        .or(task.context.execution_mode);
+                                    planned.context.skip_prerequisites |= task.context.skip_prerequisites;
+                                    planned
+                                })
+                                .collect::<Vec<AgentTask>>();
+
+                            let mut request = ApprovalRequest::new(
+                                ApprovalGate::AfterPlanning,
+                                task.context.goal_id.clone(),
+                                Some(task.task_id.clone()),
+                                format!("Run plan {} with {} task(s)", plan.plan_id, task_graph.len()),
+                            );
+                            request.plan = Some(task_graph.clone());
+                            match self.await_approval(request, &ctx).await {
+                                Ok(()) => self.execute_task_graph(task_graph, Some(plan.plan_id.as_str()), ctx.clone()).await,
+                                Err(rejected) => rejected,
+                            }
                         }
                         AgentResponse::Error(err) => {
                             println!("[Orchestrator] Plan rejected: {:?}", err.reason);
@@ -181,7 +788,7 @@ impl Orchestrator {
                         }
                     };
                 } else {
-                    response = AgentResponse::error("Planner returned invalid task graph", false);
+                    response = AgentResponse::error(ErrorKind::LlmOutput, "Planner returned invalid task graph");
                 }
             }
         }
@@ -196,7 +803,9 @@ impl Orchestrator {
                     enriched.context.parent_task_id = Some(task.task_id.clone());
                     enriched.context.goal_id =
                         enriched.context.goal_id.or(task.context.goal_id.clone());
-                    let result = self.handle(enriched, ctx.clone());
+                    enriched.context.execution_mode =
+                        enriched.context.execution_mode.or(task.context.execution_mode);
+                    let result = self.handle(enriched, ctx.clone()).await;
                     println!("↪ Subtask result: {:?}", result);
                 }
             }
@@ -205,9 +814,7 @@ impl Orchestrator {
         // Update status
         task.status = match &response {
             AgentResponse::Success(_) => TaskStatus::Succeeded,
-            AgentResponse::Error(err) => TaskStatus::Failed {
-                reason: err.reason.clone(),
-            },
+            AgentResponse::Error(err) => err.task_status(),
         };
 
         log_task_result(&task, &response, ctx.task.clone());
@@ -226,7 +833,7 @@ impl Orchestrator {
 
         let index_entry = TaskIndexEntry {
             task_id: task.task_id.clone(),
-            agent_id: agent.card.id.clone(),
+            agent_id: agent_id.clone(),
             task_type: task.task_type.clone(),
             status: format!("{:?}", task.status),
             goal_id: task.context.goal_id.clone(),
@@ -238,33 +845,51 @@ impl Orchestrator {
         };
 
         let _ = append_to_task_index(&index_entry);
-        append_timeline_event(
-            &task.context.goal_id.clone().unwrap_or("unknown".into()),
+        let (outcome_tag, outcome) = match &response {
+            AgentResponse::Success(_) => (
+                "succeeded",
+                format!("{} task handled by {} succeeded: {}", task.task_type, agent_id, task.payload),
+            ),
+            AgentResponse::Error(err) => (
+                "failed",
+                format!(
+                    "{} task handled by {} ended with {:?}: {} (payload: {})",
+                    task.task_type, agent_id, err.kind, err.reason, task.payload
+                ),
+            ),
+        };
+        remember_globally(
+            &ctx,
+            vec!["task-outcome".into(), task.task_type.clone(), outcome_tag.into()],
+            &agent_id,
+            &outcome,
+            task.context.goal_id.as_deref(),
+            Some(&task.task_id),
+        );
+        record_timeline(
+            &ctx,
+            task.context.goal_id.as_deref(),
             TimelineEvent::Task {
                 task_id: task.task_id.clone(),
                 task_type: task.task_type.clone(),
                 status: format!("{:?}", task.status),
-                agent_id: agent.card.id.clone(),
+                agent_id: agent_id.clone(),
                 timestamp: now_timestamp(),
             },
         );
         response
     }
-    pub fn execute_reviewed_plan(
+    pub async fn execute_reviewed_plan(
         &self,
         planner_output: PlannerOutput,
         ctx: AgentContext,
     ) -> AgentResponse {
         println!("Executing task graph from Planner...");
-        self.execute_task_graph(planner_output.task_graph, ctx)
+        self.execute_task_graph(planner_output.task_graph, Some(planner_output.plan_id.as_str()), ctx)
+            .await
     }
-    pub fn process_feedback_queue(
-        &self,
-        task: TaskMemoryHandle,
-        session: SessionMemoryHandle,
-        project: ProjectMemoryHandle,
-        global: GlobalMemoryHandle,
-    ) {
+    pub async fn process_feedback_queue(&self, base_ctx: AgentContext) {
+        let task = base_ctx.task.clone();
         let Ok(queue) = load_feedback_queue() else {
             println!("⚠️ Could not load feedback queue.");
             return;
@@ -275,32 +900,25 @@ impl Orchestrator {
                 continue;
             }
 
-            let retry_key = format!("retries_for:{}", item.task_id);
-            let retry_count = task
-                .lock()
-                .unwrap()
-                .load(&retry_key)
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(0);
+            let attempt = item.original_task.context.attempt;
+            let max_attempts = item
+                .original_task
+                .task_type
+                .parse::<Capability>()
+                .map(|capability| self.retry_policies.for_capability(&capability).max_attempts)
+                .unwrap_or(1);
 
-            if retry_count >= MAX_RETRIES {
-                let msg = format!("Retry limit reached ({MAX_RETRIES}). Task aborted.");
+            if attempt + 1 >= max_attempts {
+                let msg = format!("Retry limit reached ({max_attempts} attempts). Task aborted.");
                 println!("⚠️ {}", msg);
 
-                task.lock()
-                    .unwrap()
-                    .save(&format!("retry_skipped:{}", item.task_id), &msg);
+                task.save(&format!("retry_skipped:{}", item.task_id), &msg);
 
-                append_to_task_index(TaskIndexEntry {
+                append_to_task_index(&TaskIndexEntry {
                     task_id: item.task_id.clone(),
                     status: "RetrySkipped".into(),
                     agent_id: "Unknown".into(),
-                    goal_id: item
-                        .original_task
-                        .context
-                        .goal_id
-                        .clone()
-                        .unwrap_or_default(),
+                    goal_id: item.original_task.context.goal_id.clone(),
                     timestamp: now_timestamp(),
                     task_type: "".to_string(),
                     revision_id: None,
@@ -310,44 +928,115 @@ impl Orchestrator {
                 continue;
             }
 
-            let mut retry_task = item.original_task.clone();
-            retry_task.task_id = uuid::Uuid::new_v4().to_string();
+            let mut retry_task = item.original_task.next_attempt();
             retry_task.context.retry_of = Some(item.task_id.clone());
-            retry_task.status = TaskStatus::Pending;
-
-            task.lock()
-                .unwrap()
-                .save(&retry_key, &(retry_count + 1).to_string());
 
             println!(
                 "🔁 Retrying task: {} (attempt #{})",
                 retry_task.task_id,
-                retry_count + 1
+                retry_task.context.attempt + 1
             );
 
             let ctx = AgentContext {
-                task: task.clone(),
-                session: session.clone(),
-                project: project.clone(),
-                global: global.clone(),
-                tool_registry: self.tool_registry.clone(),
                 planner_memory: PlannerMemory::new(),
+                ..base_ctx.clone()
             };
+            record_timeline(
+                &ctx,
+                retry_task.context.goal_id.as_deref(),
+                TimelineEvent::Retry {
+                    task_id: retry_task.task_id.clone(),
+                    retry_of: item.task_id.clone(),
+                    reason: format!("Feedback queue retry #{}", retry_task.context.attempt),
+                    timestamp: now_timestamp(),
+                },
+            );
 
-            let response = self.handle(retry_task.clone(), ctx);
+            let response = self.handle(retry_task.clone(), ctx).await;
 
             println!("✅ Retry result: {:?}", response);
         }
     }
     /// Returns a list of all available agent cards
     pub fn available_agents(&self) -> Vec<AgentCard> {
-        self.registry.all_cards()
+        self.registry.read().unwrap().all_cards()
+    }
+}
+/// Runs an agent, turning an overrun of `timeout_secs` into a `TimedOut` error.
+/// Dropping the agent future on timeout also aborts its in-flight LLM and tool calls.
+async fn run_with_deadline(
+    run: impl std::future::Future<Output = AgentResponse>,
+    timeout_secs: Option<u64>,
+) -> AgentResponse {
+    match timeout_secs {
+        Some(secs) => tokio::time::timeout(std::time::Duration::from_secs(secs), run)
+            .await
+            .unwrap_or_else(|_| AgentResponse::timed_out(secs)),
+        None => run.await,
+    }
+}
+
+/// Persists an event to the goal's timeline in the open project and forwards it to the frontend.
+/// Tasks that do not belong to a goal have no timeline.
+fn record_timeline(ctx: &AgentContext, goal_id: Option<&str>, event: TimelineEvent) {
+    let Some(goal_id) = goal_id else { return };
+    let project_root = ctx.sandbox.as_ref().map(|jail| jail.root());
+
+    if let Err(e) = append_timeline_event(project_root, goal_id, &event) {
+        eprintln!("[warn] Failed to append timeline event: {}", e);
+    }
+    ctx.events.emit(
+        TIMELINE_EVENT,
+        serde_json::json!({ "goal_id": goal_id, "event": event }),
+    );
+}
+
+/// Logs a design decision to project memory, global memory and the goal's timeline
+fn record_decision(ctx: &AgentContext, goal_id: Option<&str>, task_id: Option<&str>, decision: DesignDecision) {
+    record_timeline(
+        ctx,
+        goal_id,
+        TimelineEvent::Decision {
+            id: decision.id.clone(),
+            summary: decision.summary.clone(),
+            made_by: decision.made_by.clone(),
+            rationale: decision.rationale.clone(),
+            timestamp: now_timestamp(),
+        },
+    );
+    remember_globally(
+        ctx,
+        vec!["decision".into(), decision.made_by.clone()],
+        &decision.made_by,
+        &format!("{} (rationale: {})", decision.summary, decision.rationale),
+        goal_id,
+        task_id,
+    );
+    if let Err(e) = ctx.project.write_decision(decision) {
+        eprintln!("[warn] Failed to save design decision: {}", e);
+    }
+}
+
+/// Adds an entry to cross-project memory, linked to the project, goal and task it came from
+fn remember_globally(
+    ctx: &AgentContext,
+    tags: Vec<String>,
+    source: &str,
+    content: &str,
+    goal_id: Option<&str>,
+    task_id: Option<&str>,
+) {
+    let project_root = ctx.sandbox.as_ref().map(|jail| jail.root().display().to_string());
+    let link = context_link(project_root.as_deref(), goal_id, task_id);
+    if let Err(e) = ctx.global.insert(GlobalMemoryEntry::new(tags, source, content, link)) {
+        eprintln!("[warn] Failed to save global memory entry: {}", e);
     }
 }
+
 pub fn log_task_result(
     task: &AgentTask,
     response: &AgentResponse,
-    task_memory: Arc<Mutex<TaskMemory>>,
+    task_memory: TaskMemoryHandle,
 ) {
     let task_id = &task.task_id;
     let log = match response {
@@ -357,30 +1046,346 @@ pub fn log_task_result(
             tool_invocations,
             trace,
             evaluation_notes,
+            change_set,
             ..
         }) => {
+            let changes: Vec<String> = change_set
+                .iter()
+                .flatten()
+                .map(|change| format!("{:?} {}", change.kind, change.path))
+                .collect();
             format!(
-                "✅ Task Succeeded\nType: {}\nOutput: {}\nTime: {}ms\nTools: {:?}\nTrace: {:?}\nNotes: {:?}",
+                "✅ Task Succeeded\nType: {}\nOutput: {}\nTime: {}ms\nTools: {:?}\nTrace: {:?}\nNotes: {:?}\nChanges: {:?}",
                 task.task_type,
                 content,
                 execution_time_ms,
                 tool_invocations,
                 trace,
-                evaluation_notes
+                evaluation_notes,
+                changes
             )
         }
 
         AgentResponse::Error(AgentError {
+            kind,
             reason,
-            retryable,
             log_trace,
+            ..
         }) => {
             format!(
-                "❌ Task Failed\nType: {}\nReason: {}\nRetryable: {}\nTrace: {:?}",
-                task.task_type, reason, retryable, log_trace
+                "❌ Task Failed\nType: {}\nKind: {:?}\nReason: {}\nTrace: {:?}",
+                task.task_type, kind, reason, log_trace
             )
         }
     };
 
-    let _ = task_memory.lock().unwrap().save(task_id, &log);
+    task_memory.save(task_id, &log);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::manifest_agent::ManifestAgent;
+    use crate::llm::backend::LlmBackend;
+    use crate::llm::scripted::ScriptedBackend;
+    use crate::memory::project_memory::ProjectMemoryHandle;
+    use crate::memory::session_memory::SessionMemoryHandle;
+    use crate::orchestrator::events::NullEventSink;
+    use crate::orchestrator::manifest::{AgentManifest, LoadedManifest};
+    use crate::orchestrator::types::{AgentTaskContext, SkillGraph};
+    use crate::tools::registry::ToolRegistry;
+    use async_trait::async_trait;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use tokio_util::sync::CancellationToken;
+
+    /// Keeps the task logs, index and checkpoints the orchestrator writes out of the real WinterData
+    fn use_temp_home() {
+        static HOME: OnceLock<()> = OnceLock::new();
+        HOME.get_or_init(|| {
+            let home = std::env::temp_dir().join(format!("winter-home-{}", Uuid::new_v4()));
+            std::fs::create_dir_all(&home).unwrap();
+            std::env::set_var("HOME", home);
+        });
+    }
+
+    fn orchestrator() -> Orchestrator {
+        use_temp_home();
+        Orchestrator {
+            registry: RwLock::new(AgentRegistry::new()),
+            retry_policies: RetryPolicies::builtin(),
+            deadlines: Deadlines::builtin(),
+            capabilities: CapabilityGraph::builtin(),
+            approvals: ApprovalSettings {
+                after_planning: false,
+                before_execute: false,
+                file_writes: false,
+                generated_dirs: vec![],
+            },
+            task_memory: TaskMemory::new(),
+            session_memory: SessionMemory::new(),
+            global_memory: GlobalMemoryHandle::new(),
+        }
+    }
+
+    fn context(llm: Arc<dyn LlmBackend>) -> AgentContext {
+        AgentContext {
+            task: TaskMemoryHandle::from(&TaskMemory::new()),
+            session: SessionMemoryHandle::from(&SessionMemory::new()),
+            project: ProjectMemoryHandle::new(),
+            global: GlobalMemoryHandle::new(),
+            tool_registry: Arc::new(ToolRegistry::new()),
+            planner_memory: PlannerMemory::new(),
+            llm,
+            events: Arc::new(NullEventSink),
+            execution_mode: ExecutionMode::Simulate,
+            changes: ChangeSet::new(),
+            sandbox: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    fn card(id: &str, capability: Capability) -> AgentCard {
+        AgentCard {
+            id: id.into(),
+            description: "test agent".into(),
+            skills: SkillGraph {
+                root: capability,
+                subskills: vec![],
+            },
+            input_schema: String::new(),
+            output_schema: String::new(),
+            default_execution: ExecutionMode::Simulate,
+            timeout_secs: Some(30),
+            priority: 0,
+            preconditions: vec![],
+            allowed_tools: vec![],
+        }
+    }
+
+    fn task(id: &str, task_type: &str, payload: &str, depends_on: &[&str]) -> AgentTask {
+        AgentTask {
+            task_id: id.into(),
+            task_type: task_type.into(),
+            payload: payload.into(),
+            context: AgentTaskContext {
+                origin: "test".into(),
+                goal_id: None,
+                parent_task_id: None,
+                retry_of: None,
+                revision_id: None,
+                execution_mode: None,
+                dependency_outputs: HashMap::new(),
+                timeout_secs: None,
+                attempt: 0,
+                skip_prerequisites: false,
+            },
+            status: TaskStatus::Pending,
+            depends_on: depends_on.iter().map(|dep| dep.to_string()).collect(),
+        }
+    }
+
+    /// Records the execution mode each task ran in
+    struct ModeAgent {
+        modes: Arc<Mutex<Vec<ExecutionMode>>>,
+    }
+
+    #[async_trait]
+    impl AgentHandler for ModeAgent {
+        async fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse {
+            self.modes.lock().unwrap().push(ctx.execution_mode);
+            AgentResponse::success(&task.task_id, "mode")
+        }
+    }
+
+    /// Manifest agent for `capability` whose answers come from the scripted backend
+    fn manifest_agent(id: &str, capability: Capability, template: &str) -> (AgentCard, ManifestAgent) {
+        typed_manifest_agent(id, capability, template, "text")
+    }
+
+    fn typed_manifest_agent(id: &str, capability: Capability, template: &str, output_schema: &str) -> (AgentCard, ManifestAgent) {
+        let manifest: AgentManifest = serde_json::from_value(serde_json::json!({
+            "id": id,
+            "description": "scripted test agent",
+            "capability": capability,
+            "output_schema": output_schema,
+            "prompt_template": "prompt.txt",
+        }))
+        .unwrap();
+        let card = manifest.card();
+        let agent = ManifestAgent::new(LoadedManifest {
+            manifest,
+            template: template.into(),
+            source: PathBuf::from("agent.json"),
+        });
+        (card, agent)
+    }
+
+    /// Sleeps for the number of milliseconds in the payload and records when it finished
+    struct SleepAgent {
+        finished: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl AgentHandler for SleepAgent {
+        async fn handle_task(&self, task: AgentTask, _ctx: AgentContext) -> AgentResponse {
+            let millis = task.payload.parse().unwrap_or(0);
+            tokio::time::sleep(Duration::from_millis(millis)).await;
+            self.finished.lock().unwrap().push(task.task_id.clone());
+            AgentResponse::success(&task.task_id, "sleep")
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_branch_does_not_hold_up_independent_tasks() {
+        let finished = Arc::new(Mutex::new(vec![]));
+        let mut orchestrator = orchestrator();
+        orchestrator.register_agent(
+            card("sleep", Capability::Documentation),
+            Box::new(SleepAgent {
+                finished: finished.clone(),
+            }),
+        );
+
+        let graph = vec![
+            task("slow", "Documentation", "300", &[]),
+            task("after-slow", "Documentation", "0", &["slow"]),
+            task("fast", "Documentation", "0", &[]),
+            task("after-fast", "Documentation", "0", &["fast"]),
+        ];
+        let response = orchestrator
+            .execute_task_graph(graph, None, context(Arc::new(ScriptedBackend::default())))
+            .await;
+        assert!(matches!(response, AgentResponse::Success(_)), "{:?}", response);
+
+        // `after-fast` only waits for `fast`, not for the slow task in the same layer
+        let finished = finished.lock().unwrap().clone();
+        assert_eq!(finished, vec!["fast", "after-fast", "slow", "after-slow"]);
+    }
+
+    #[tokio::test]
+    async fn dependent_task_prompt_carries_the_scripted_answer_it_depends_on() {
+        let mut orchestrator = orchestrator();
+        let (card, agent) = manifest_agent("docs", Capability::Documentation, "Document {{payload}}\n{{dependencies}}");
+        orchestrator.register_agent(card, Box::new(agent));
+
+        let llm = Arc::new(ScriptedBackend::new(vec!["Outline: intro, usage", "Final docs"]));
+        let graph = vec![
+            task("outline", "Documentation", "the CLI", &[]),
+            task("write", "Documentation", "the CLI in full", &["outline"]),
+        ];
+        let response = orchestrator
+            .execute_task_graph(graph, None, context(llm.clone()))
+            .await;
+        assert!(matches!(response, AgentResponse::Success(_)), "{:?}", response);
+
+        let prompts = llm.prompts();
+        assert_eq!(prompts.len(), 2);
+        assert!(prompts[0].contains("Document the CLI\n"), "{}", prompts[0]);
+        assert!(prompts[1].contains("[outline]\nOutline: intro, usage"), "{}", prompts[1]);
+    }
+
+    #[tokio::test]
+    async fn failed_llm_call_skips_the_tasks_that_depend_on_it() {
+        let mut orchestrator = orchestrator();
+        let (card, agent) = manifest_agent("docs", Capability::Documentation, "{{payload}}");
+        orchestrator.register_agent(card, Box::new(agent));
+
+        // No answers scripted: every attempt of the first task fails with an output error
+        let llm = Arc::new(ScriptedBackend::default());
+        let graph = vec![
+            task("first", "Documentation", "intro", &[]),
+            task("second", "Documentation", "usage", &["first"]),
+        ];
+        let response = orchestrator
+            .execute_task_graph(graph, None, context(llm.clone()))
+            .await;
+
+        let AgentResponse::Error(err) = response else {
+            panic!("goal should fail");
+        };
+        assert!(err.reason.contains("2 of 2"), "{}", err.reason);
+        assert!(err.reason.contains("blocked_by: \"first\""), "{}", err.reason);
+        // Retried by the default policy, never run for the skipped task
+        assert!(llm.prompts().iter().all(|prompt| prompt.contains("intro") && !prompt.contains("usage")));
+    }
+
+    #[tokio::test]
+    async fn goal_override_widens_the_mode_only_after_approval() {
+        let modes = Arc::new(Mutex::new(vec![]));
+        let mut orchestrator = orchestrator();
+        orchestrator.register_agent(
+            card("mode", Capability::Documentation),
+            Box::new(ModeAgent { modes: modes.clone() }),
+        );
+
+        let mut task = task(&format!("widen-{}", Uuid::new_v4()), "Documentation", "", &[]);
+        task.context.execution_mode = Some(ExecutionMode::Execute);
+        let task_id = task.task_id.clone();
+        let run = tokio::spawn(async move {
+            orchestrator
+                .handle(task, context(Arc::new(ScriptedBackend::default())))
+                .await
+        });
+
+        // The before-execute gate is disabled, but the card only defaults to Simulate
+        let request = loop {
+            let pending = crate::orchestrator::approval::pending_approvals();
+            if let Some(request) = pending.into_iter().find(|r| r.task_id.as_deref() == Some(task_id.as_str())) {
+                break request;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert!(modes.lock().unwrap().is_empty());
+        crate::orchestrator::approval::approve_step(request.request_id, None).unwrap();
+
+        let response = run.await.unwrap();
+        assert!(matches!(response, AgentResponse::Success(_)), "{:?}", response);
+        assert_eq!(*modes.lock().unwrap(), vec![ExecutionMode::Execute]);
+    }
+
+    #[tokio::test]
+    async fn graph_with_a_non_uuid_goal_id_is_rejected_before_running() {
+        let finished = Arc::new(Mutex::new(vec![]));
+        let mut orchestrator = orchestrator();
+        orchestrator.register_agent(
+            card("sleep", Capability::Documentation),
+            Box::new(SleepAgent {
+                finished: finished.clone(),
+            }),
+        );
+
+        let mut task = task("docs", "Documentation", "0", &[]);
+        task.context.goal_id = Some("goal-1".into());
+        let response = orchestrator
+            .execute_task_graph(vec![task], None, context(Arc::new(ScriptedBackend::default())))
+            .await;
+
+        assert!(matches!(response, AgentResponse::Error(ref err) if err.kind == ErrorKind::InvalidInput));
+        assert!(finished.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn manifest_with_an_output_schema_gets_a_validated_answer() {
+        let mut orchestrator = orchestrator();
+        let (card, agent) =
+            typed_manifest_agent("reqs", Capability::Requirements, "List requirements for {{payload}}", "RequirementList");
+        orchestrator.register_agent(card, Box::new(agent));
+
+        let llm = Arc::new(ScriptedBackend::new(vec![
+            "Sure! Here you go.",
+            r#"{"requirements": [{"id": "R1", "description": "Add todos", "priority": "must"}]}"#,
+        ]));
+        let response = orchestrator
+            .handle(task("reqs", "Requirements", "a todo app", &[]), context(llm.clone()))
+            .await;
+
+        let AgentResponse::Success(output) = response else {
+            panic!("{:?}", response);
+        };
+        assert_eq!(output.content["requirements"][0]["id"], "R1");
+        let prompts = llm.prompts();
+        assert!(prompts[0].contains("JSON schema"));
+        assert!(prompts[1].contains("Your previous answer was rejected"));
+    }
 }
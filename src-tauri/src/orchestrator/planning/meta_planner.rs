@@ -1,5 +1,5 @@
-use crate::agents::memory::planner_memory::PlannerMemoryEntry;
-use crate::agents::orchestrator::protocol::PlanningStrategy;
+use crate::memory::planner_memory::PlannerMemoryEntry;
+use crate::orchestrator::protocol::PlanningStrategy;
 
 pub trait MetaPlanner{
     fn recommend_strategy(&self, goal_id: &str, history: &[PlannerMemoryEntry])->PlanningStrategy;
@@ -8,7 +8,7 @@ pub trait MetaPlanner{
 pub struct HeuristicMetaPlanner;
 
 impl MetaPlanner for HeuristicMetaPlanner {
-    fn recommend_strategy(&self, goal_id: &str, history: &[PlannerMemoryEntry]) -> PlanningStrategy {
+    fn recommend_strategy(&self, _goal_id: &str, history: &[PlannerMemoryEntry]) -> PlanningStrategy {
         if let Some(last) = history.last(){
             if last.score.unwrap_or(0) >= 7 {
                 return PlanningStrategy::ReusePlan{
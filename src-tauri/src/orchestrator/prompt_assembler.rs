@@ -1,6 +1,5 @@
-use crate::agents::orchestrator::context::AgentContext;
-use crate::agents::orchestrator::types::AgentTask;
-use serde_json::Value::String;
+use crate::orchestrator::context::AgentContext;
+use crate::orchestrator::types::AgentTask;
 use std::fmt::Write;
 
 /// Builds structured prompts for agents that need LLM assistance
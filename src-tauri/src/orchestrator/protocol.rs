@@ -1,4 +1,8 @@
-use crate::agents::orchestrator::types::AgentTask;
+use crate::orchestrator::types::{AgentTask, TaskStatus};
+use crate::llm::backend::LlmError;
+use crate::llm::structured::{parse_structured, schema_value};
+use crate::tools::change_set::FileChange;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -14,29 +18,79 @@ pub struct AgentOutput {
     pub produced_by: String,
     pub planned_by: Option<String>,
     pub subtasks: Option<Vec<AgentTask>>,
+    /// File effects of the task; applied as one unit at the end of the task only in Execute mode
+    #[serde(default)]
+    pub change_set: Option<Vec<FileChange>>,
 }
 
-#[derive(Debug, Clone)]
+/// What went wrong, independent of the human-readable `AgentError::reason`.
+/// Retry policies decide per kind whether a failed task is attempted again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ErrorKind {
+    /// The task itself is malformed, e.g. a missing payload field
+    InvalidInput,
+    /// No agent can handle the task type
+    Routing,
+    Prompt,
+    /// The model backend could not be reached or is not configured
+    LlmUnavailable,
+    LlmRequest,
+    /// The model answered, but not in the expected format
+    LlmOutput,
+    Tool,
+    Storage,
+    /// Staged file changes could not be applied
+    ChangeConflict,
+    /// The user rejected the step at an approval gate
+    Rejected,
+    Cancelled,
+    TimedOut,
+    Internal,
+}
+
+impl From<&LlmError> for ErrorKind {
+    fn from(error: &LlmError) -> Self {
+        match error {
+            LlmError::Unavailable(_) => ErrorKind::LlmUnavailable,
+            LlmError::Request(_) => ErrorKind::LlmRequest,
+            LlmError::InvalidResponse(_) => ErrorKind::LlmOutput,
+            LlmError::Cancelled => ErrorKind::Cancelled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentError {
+    pub kind: ErrorKind,
     pub reason: String,
-    pub retryable: bool,
     pub log_trace: Option<Vec<String>>,
+    /// Set when the task did not fail on its own but was cancelled or timed out
+    pub interrupted: Option<TaskStatus>,
+}
+
+impl AgentError {
+    /// Final status of a task that ended with this error
+    pub fn task_status(&self) -> TaskStatus {
+        self.interrupted.clone().unwrap_or_else(|| TaskStatus::Failed {
+            reason: self.reason.clone(),
+        })
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AgentResponse {
     Success(AgentOutput),
     Error(AgentError),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum EvaluationLevel {
     Info,
     Warn,
     Critical,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EvaluationNote {
     pub note: String,
     pub level: EvaluationLevel,
@@ -49,6 +103,12 @@ pub enum ToolStatus {
     Failed,
 }
 
+impl ToolStatus {
+    pub fn is_success(&self) -> bool {
+        matches!(self, ToolStatus::Success)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ToolReturn {
     pub result: Value,
@@ -71,7 +131,7 @@ pub struct ToolInvocationLog {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlannerOutput {
-    pub task_graph: Vec<AgentTask>,     //Linear for now, DAg later
+    pub task_graph: Vec<AgentTask>,     //DAG via AgentTask::depends_on
     pub score: Option<u8>,              //Set by CritiqueAgent(0-10)
     pub feedback_notes: Option<String>, // CritiqueAgent writes reasoning
     pub plan_id: String,
@@ -79,6 +139,49 @@ pub struct PlannerOutput {
     pub strategy_used: PlanningStrategy,
 }
 
+/// `RequirementList`: what RequirementsAgent extracts from a goal
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RequirementList {
+    pub requirements: Vec<Requirement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Requirement {
+    pub id: String,
+    pub description: String,
+    /// e.g. "must", "should", "could"
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub acceptance_criteria: Vec<String>,
+}
+
+/// `CriticList`: CritiqueAgent's review of another agent's output
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CriticList {
+    /// Overall quality, 0-10
+    pub score: Option<u8>,
+    pub notes: Vec<EvaluationNote>,
+}
+
+/// JSON schema for an `AgentCard::output_schema` name, for the types that have one
+pub fn output_schema(name: &str) -> Option<Value> {
+    match name {
+        "RequirementList" => Some(schema_value::<RequirementList>()),
+        "CriticList" => Some(schema_value::<CriticList>()),
+        _ => None,
+    }
+}
+
+/// Parses a model answer as the `output_schema` type `name`, for the types `output_schema` knows
+pub fn parse_output(name: &str, text: &str) -> Result<Value, String> {
+    let value = match name {
+        "RequirementList" => serde_json::to_value(parse_structured::<RequirementList>(text)?),
+        "CriticList" => serde_json::to_value(parse_structured::<CriticList>(text)?),
+        _ => return Err(format!("No schema for output {}", name)),
+    };
+    value.map_err(|e| e.to_string())
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum PlanningStrategy {
     ReusePlan { plan_id: String },
@@ -98,13 +201,51 @@ impl AgentResponse {
             produced_by: agent_id.to_string(),
             planned_by: None,
             subtasks: None,
+            change_set: None,
         })
     }
-    pub fn error(reason: &str, retryable: bool) -> Self {
+    /// Success whose content is the structured value itself rather than text
+    pub fn structured<T: Serialize>(value: &T, agent_id: &str) -> Self {
+        let mut response = Self::success("", agent_id);
+        if let AgentResponse::Success(output) = &mut response {
+            output.content = serde_json::to_value(value).unwrap_or(Value::Null);
+        }
+        response
+    }
+    pub fn error(kind: ErrorKind, reason: &str) -> Self {
         AgentResponse::Error(AgentError {
+            kind,
             reason: reason.to_string(),
-            retryable,
             log_trace: None,
+            interrupted: None,
+        })
+    }
+    /// Appends lines to the trace of the output, or to the log trace of the error
+    pub fn with_trace(mut self, lines: Vec<String>) -> Self {
+        if lines.is_empty() {
+            return self;
+        }
+        let trace = match &mut self {
+            AgentResponse::Success(output) => &mut output.trace,
+            AgentResponse::Error(err) => &mut err.log_trace,
+        };
+        trace.get_or_insert_with(Vec::new).extend(lines);
+        self
+    }
+    pub fn cancelled() -> Self {
+        AgentResponse::Error(AgentError {
+            kind: ErrorKind::Cancelled,
+            reason: "Task was cancelled".into(),
+            log_trace: None,
+            interrupted: Some(TaskStatus::Cancelled),
+        })
+    }
+    pub fn timed_out(after_secs: u64) -> Self {
+        AgentResponse::Error(AgentError {
+            kind: ErrorKind::TimedOut,
+            reason: format!("Task timed out after {}s", after_secs),
+            log_trace: None,
+            interrupted: Some(TaskStatus::TimedOut { after_secs }),
         })
     }
 }
@@ -1,22 +1,112 @@
-use  std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::Serialize;
 use crate::orchestrator::protocol::AgentResponse;
-use crate::orchestrator::types::{AgentCard, AgentTask, Capability};
+use crate::orchestrator::types::{AgentCard, AgentTask, Capability, ExecutionMode, Precondition};
 use crate::orchestrator::context::AgentContext;
 
-/// Trait implemented by all agent handlers
-pub trait AgentHandler{
-    fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse;
+/// Trait implemented by all agent handlers.
+/// Handlers run on the Tauri async runtime, so tool, LLM and download calls must be awaited, never blocked on.
+#[async_trait]
+pub trait AgentHandler: Send + Sync{
+    async fn handle_task(&self, task: AgentTask, ctx: AgentContext) -> AgentResponse;
+}
+
+/// Where a registered agent comes from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum AgentOrigin {
+    BuiltIn,
+    /// Loaded from this manifest file; replaced on every manifest reload
+    Manifest(PathBuf),
 }
 
 /// Full metadata about a registered Agent
 pub struct AgentMetadata{
     pub card: AgentCard,
-    pub handler: Box<dyn AgentHandler + Send + Sync>
+    pub handler: Box<dyn AgentHandler + Send + Sync>,
+    pub origin: AgentOrigin,
+    /// Tasks this agent is currently running; used to spread load between equal candidates
+    in_flight: AtomicUsize,
+}
+
+impl AgentMetadata {
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Counts the agent as busy until the guard is dropped
+    pub fn begin_task(&self) -> InFlightGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(&self.in_flight)
+    }
+}
+
+pub struct InFlightGuard<'a>(&'a AtomicUsize);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// How an agent's skills matched the requested capability. Root matches rank first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum SkillMatch {
+    Subskill,
+    Root,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteCandidate {
+    pub agent_id: String,
+    pub skill_match: SkillMatch,
+    pub priority: i32,
+    pub in_flight: usize,
+}
+
+/// Ranked agents for one task: the first candidate runs, the rest are fallbacks
+#[derive(Debug, Clone, Serialize)]
+pub struct Route {
+    pub capability: Capability,
+    pub candidates: Vec<RouteCandidate>,
+    /// Agents whose skills matched but whose preconditions did not hold, with the reason
+    pub rejected: Vec<(String, String)>,
+}
+
+impl Route {
+    /// One-line summary for task traces
+    pub fn describe(&self) -> String {
+        let ranked: Vec<String> = self
+            .candidates
+            .iter()
+            .map(|c| format!("{} ({:?}, priority {}, busy {})", c.agent_id, c.skill_match, c.priority, c.in_flight))
+            .collect();
+        let mut line = format!("route {:?}: {}", self.capability, ranked.join(" > "));
+        if !self.rejected.is_empty() {
+            let rejected: Vec<String> = self
+                .rejected
+                .iter()
+                .map(|(id, reason)| format!("{} ({})", id, reason))
+                .collect();
+            line.push_str(&format!("; rejected: {}", rejected.join(", ")));
+        }
+        line
+    }
 }
 
-/// Stores all agents that can be routed to
+/// Stores all agents that can be routed to. Entries are shared so a running task keeps
+/// its agent alive while manifest agents are swapped out.
 pub struct AgentRegistry{
-    agents: Vec<AgentMetadata>,
+    agents: Vec<Arc<AgentMetadata>>,
+}
+
+impl Default for AgentRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AgentRegistry{
@@ -26,19 +116,108 @@ impl AgentRegistry{
         }
     }
     pub fn register(&mut self,agent_card: AgentCard, handler: Box<dyn AgentHandler + Send + Sync>){
-        self.agents.push(AgentMetadata{card:agent_card, handler});
+        self.register_from(agent_card, handler, AgentOrigin::BuiltIn);
+    }
+    pub fn register_from(&mut self, agent_card: AgentCard, handler: Box<dyn AgentHandler + Send + Sync>, origin: AgentOrigin) {
+        self.agents.push(Arc::new(AgentMetadata {
+            card: agent_card,
+            handler,
+            origin,
+            in_flight: AtomicUsize::new(0),
+        }));
+    }
+    /// Drops every manifest-defined agent, e.g. before reloading the manifests
+    pub fn remove_manifest_agents(&mut self) {
+        self.agents.retain(|agent| agent.origin == AgentOrigin::BuiltIn);
     }
-    /// Structured and deterministic capability matching
-    pub fn find_agent_for_task(&self, task_type: &Capability)-> Option<&AgentMetadata>{
+    pub fn agent(&self, agent_id: &str) -> Option<Arc<AgentMetadata>> {
+        self.agents.iter().find(|agent| agent.card.id == agent_id).cloned()
+    }
+    pub fn builtin_ids(&self) -> HashSet<String> {
         self.agents
             .iter()
-            .find(|agent| {
-                let skills =&agent.card.skills;
-                skills.root == *task_type || skills.subskills.contains(task_type)
-            })
+            .filter(|agent| agent.origin == AgentOrigin::BuiltIn)
+            .map(|agent| agent.card.id.clone())
+            .collect()
+    }
+    /// Ranks every agent that can handle the capability: root matches before subskill matches,
+    /// then higher `AgentCard::priority`, then fewer tasks in flight, then registration order.
+    /// Agents whose preconditions fail for this task are left out.
+    pub fn route(&self, capability: &Capability, task: &AgentTask, ctx: &AgentContext) -> Route {
+        let mut ranked: Vec<(usize, RouteCandidate)> = vec![];
+        let mut rejected = vec![];
+
+        for (order, agent) in self.agents.iter().enumerate() {
+            let skills = &agent.card.skills;
+            let skill_match = if skills.root == *capability {
+                SkillMatch::Root
+            } else if skills.subskills.contains(capability) {
+                SkillMatch::Subskill
+            } else {
+                continue;
+            };
+
+            if let Some(reason) = agent
+                .card
+                .preconditions
+                .iter()
+                .find_map(|precondition| precondition_failure(precondition, &agent.card, task, ctx))
+            {
+                rejected.push((agent.card.id.clone(), reason));
+                continue;
+            }
+
+            ranked.push((
+                order,
+                RouteCandidate {
+                    agent_id: agent.card.id.clone(),
+                    skill_match,
+                    priority: agent.card.priority,
+                    in_flight: agent.in_flight(),
+                },
+            ));
+        }
+
+        ranked.sort_by(|(a_order, a), (b_order, b)| {
+            b.skill_match
+                .cmp(&a.skill_match)
+                .then(b.priority.cmp(&a.priority))
+                .then(a.in_flight.cmp(&b.in_flight))
+                .then(a_order.cmp(b_order))
+        });
+
+        Route {
+            capability: capability.clone(),
+            candidates: ranked.into_iter().map(|(_, candidate)| candidate).collect(),
+            rejected,
+        }
     }
     pub fn all_cards(&self)-> Vec<AgentCard> {
         self.agents.iter().map(|m| m.card.clone()).collect()
     }
 
-}
\ No newline at end of file
+}
+
+/// Why a precondition does not hold for the task, or None if it does
+fn precondition_failure(
+    precondition: &Precondition,
+    card: &AgentCard,
+    task: &AgentTask,
+    ctx: &AgentContext,
+) -> Option<String> {
+    match precondition {
+        Precondition::ProjectOpen if ctx.sandbox.is_none() => Some("no project open".into()),
+        Precondition::PayloadField(field) => {
+            let present = serde_json::from_str::<serde_json::Value>(&task.payload)
+                .map(|payload| payload.get(field).is_some())
+                .unwrap_or(false);
+            (!present).then(|| format!("payload lacks `{}`", field))
+        }
+        Precondition::ExecutionModeAtLeast(mode) => {
+            // The mode the agent would actually run in, as `run_agent` resolves it
+            let effective = ExecutionMode::resolve(card.default_execution, task.context.execution_mode);
+            (effective < *mode).then(|| format!("requires {:?} mode", mode))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::orchestrator::protocol::ErrorKind;
+use crate::orchestrator::types::Capability;
+
+const POLICY_PATH: &str = "WinterData/retry_policies.json";
+
+/// How often and how patiently a failed task of one capability is retried
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one; 1 disables retries
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub multiplier: f64,
+    /// Fraction of the delay randomised in both directions, 0.0 to 1.0
+    pub jitter: f64,
+    /// Error kinds worth another attempt; everything else fails immediately
+    pub retry_on: Vec<ErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            multiplier: 2.0,
+            jitter: 0.2,
+            retry_on: vec![
+                ErrorKind::LlmUnavailable,
+                ErrorKind::LlmRequest,
+                ErrorKind::LlmOutput,
+                ErrorKind::TimedOut,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn no_retry() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before the next attempt, or None if `kind` is not retried or attempts are used up.
+    /// `attempt` is the zero-based number of the attempt that just failed.
+    pub fn next_delay(&self, kind: ErrorKind, attempt: u32) -> Option<Duration> {
+        if attempt + 1 >= self.max_attempts || !self.retry_on.contains(&kind) {
+            return None;
+        }
+
+        let base = self.initial_backoff_ms as f64 * self.multiplier.powi(attempt as i32);
+        let base = base.min(self.max_backoff_ms as f64);
+        // Uniform in [-jitter, +jitter]; v4 UUIDs are a convenient randomness source
+        let unit = (uuid::Uuid::new_v4().as_u128() % 10_000) as f64 / 10_000.0;
+        let factor = 1.0 + self.jitter.clamp(0.0, 1.0) * (unit * 2.0 - 1.0);
+        Some(Duration::from_millis((base * factor).max(0.0) as u64))
+    }
+}
+
+/// Retry policies by capability. Defaults are built in and can be overridden per
+/// capability in `WinterData/retry_policies.json`, e.g. `{"Deployment": {"max_attempts": 1}}`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicies {
+    default: RetryPolicy,
+    by_capability: HashMap<Capability, RetryPolicy>,
+}
+
+impl RetryPolicies {
+    pub fn builtin() -> Self {
+        let mut by_capability = HashMap::new();
+        // Side-effecting work is not repeated behind the user's back
+        by_capability.insert(Capability::Deployment, RetryPolicy::no_retry());
+        by_capability.insert(Capability::GitOps, RetryPolicy::no_retry());
+        by_capability.insert(
+            Capability::Planning,
+            RetryPolicy {
+                max_attempts: 2,
+                ..RetryPolicy::default()
+            },
+        );
+
+        Self {
+            default: RetryPolicy::default(),
+            by_capability,
+        }
+    }
+
+    /// Built-in policies overlaid with the user's policy file, if any
+    pub fn load() -> Self {
+        let mut policies = Self::builtin();
+        let path = dirs::home_dir().expect("No home dir").join(POLICY_PATH);
+
+        if let Ok(content) = fs::read_to_string(&path) {
+            match serde_json::from_str::<HashMap<Capability, RetryPolicy>>(&content) {
+                Ok(overrides) => policies.by_capability.extend(overrides),
+                Err(e) => eprintln!("[warn] Ignoring invalid {}: {}", POLICY_PATH, e),
+            }
+        }
+        policies
+    }
+
+    pub fn for_capability(&self, capability: &Capability) -> &RetryPolicy {
+        self.by_capability.get(capability).unwrap_or(&self.default)
+    }
+}
@@ -0,0 +1,446 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::memory::project_memory::ProjectMemoryHandle;
+use crate::memory::retrieval;
+use crate::orchestrator::types::{AgentCard, AgentTask, Capability, TaskStatus};
+
+/// Extensions of files that count as existing code for the CodeGen prerequisite
+const SOURCE_EXTENSIONS: [&str; 18] = [
+    "rs", "ts", "tsx", "js", "jsx", "py", "go", "java", "kt", "c", "cc", "cpp", "h", "cs", "rb", "swift", "svelte", "vue",
+];
+
+/// Prerequisite edges between capabilities, e.g. CodeGen requires Architecture requires
+/// Requirements. Agent cards declare which capabilities an agent handles; this graph
+/// declares the order in which capabilities have to run within one goal.
+#[derive(Debug, Clone)]
+pub struct CapabilityGraph {
+    prerequisites: HashMap<Capability, Vec<Capability>>,
+}
+
+/// A task that does not run after a task of one of its prerequisite capabilities
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PrerequisiteViolation {
+    pub task_id: String,
+    pub capability: Capability,
+    pub prerequisite: Capability,
+}
+
+impl fmt::Display for PrerequisiteViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Task {} ({:?}) must run after a {:?} task",
+            self.task_id, self.capability, self.prerequisite
+        )
+    }
+}
+
+/// Graph of capabilities, the agents handling them and their prerequisites, for the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct SkillGraphView {
+    pub nodes: Vec<SkillNode>,
+    pub edges: Vec<SkillEdge>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkillNode {
+    pub capability: Capability,
+    /// Agents with this capability as their root skill
+    pub agents: Vec<String>,
+    /// Agents that handle it as a subskill
+    pub subskill_agents: Vec<String>,
+}
+
+/// `from` requires `to` to have run first
+#[derive(Debug, Clone, Serialize)]
+pub struct SkillEdge {
+    pub from: Capability,
+    pub to: Capability,
+}
+
+impl CapabilityGraph {
+    pub fn builtin() -> Self {
+        let mut graph = Self {
+            prerequisites: HashMap::new(),
+        };
+        graph.require(Capability::Architecture, Capability::Requirements);
+        graph.require(Capability::CodeGen, Capability::Architecture);
+        graph.require(Capability::Scaffolding, Capability::Architecture);
+        graph.require(Capability::Testing, Capability::CodeGen);
+        graph.require(Capability::Deployment, Capability::Testing);
+        graph.require(Capability::Refactoring, Capability::RepoAnalysis);
+        graph
+    }
+
+    fn require(&mut self, capability: Capability, prerequisite: Capability) {
+        self.prerequisites.entry(capability).or_default().push(prerequisite);
+    }
+
+    pub fn prerequisites(&self, capability: &Capability) -> &[Capability] {
+        self.prerequisites.get(capability).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Makes a plan respect the graph. A prerequisite capability with no task in the plan
+    /// gets a new task, inserted ahead of the task that needs it; a prerequisite task that
+    /// exists but is not depended on gets the missing `depends_on` edge, unless that would
+    /// form a cycle. Capabilities in `satisfied` are already covered by the project and get
+    /// no task, and tasks with `skip_prerequisites` get none either, so `validate_plan`
+    /// reports what they lack. Returns the completed plan and the ids of the inserted tasks.
+    pub fn resolve_plan(
+        &self,
+        mut plan: Vec<AgentTask>,
+        satisfied: &HashSet<Capability>,
+    ) -> (Vec<AgentTask>, Vec<String>) {
+        let mut inserted = vec![];
+        let mut index = 0;
+
+        while index < plan.len() {
+            let Some(capability) = capability_of(&plan[index]) else {
+                index += 1;
+                continue;
+            };
+
+            let mut new_tasks = vec![];
+            for prerequisite in self.prerequisites(&capability) {
+                if satisfied.contains(prerequisite) || has_prerequisite(&plan, index, prerequisite) {
+                    continue;
+                }
+
+                let providers: Vec<usize> = (0..plan.len())
+                    .filter(|&i| capability_of(&plan[i]).as_ref() == Some(prerequisite))
+                    .collect();
+                let provider = providers
+                    .iter()
+                    .find(|&&i| !depends_on(&plan, &plan[i].task_id, &plan[index].task_id));
+
+                if let Some(&provider) = provider {
+                    let provider_id = plan[provider].task_id.clone();
+                    plan[index].depends_on.push(provider_id);
+                } else if providers.is_empty() && !plan[index].context.skip_prerequisites {
+                    let task = prerequisite_task(&plan[index], prerequisite);
+                    println!(
+                        "[SkillGraph] Inserting {:?} task {} ahead of {}",
+                        prerequisite, task.task_id, plan[index].task_id
+                    );
+                    plan[index].depends_on.push(task.task_id.clone());
+                    inserted.push(task.task_id.clone());
+                    new_tasks.push(task);
+                }
+                // Otherwise every provider already runs after this task, or the task opted out of
+                // insertion; validate_plan reports it
+            }
+
+            if new_tasks.is_empty() {
+                index += 1;
+            } else {
+                // Visit the inserted tasks next so their own prerequisites are resolved too
+                plan.splice(index..index, new_tasks);
+            }
+        }
+
+        (plan, inserted)
+    }
+
+    /// Every task whose capability has prerequisites must (transitively) depend on a task of
+    /// each, unless the prerequisite is in `satisfied`
+    pub fn validate_plan(&self, plan: &[AgentTask], satisfied: &HashSet<Capability>) -> Vec<PrerequisiteViolation> {
+        let mut violations = vec![];
+
+        for (index, task) in plan.iter().enumerate() {
+            let Some(capability) = capability_of(task) else {
+                continue;
+            };
+            for prerequisite in self.prerequisites(&capability) {
+                if !satisfied.contains(prerequisite) && !has_prerequisite(plan, index, prerequisite) {
+                    violations.push(PrerequisiteViolation {
+                        task_id: task.task_id.clone(),
+                        capability: capability.clone(),
+                        prerequisite: prerequisite.clone(),
+                    });
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// The capability graph together with the agents registered for each capability
+    pub fn view(&self, cards: &[AgentCard]) -> SkillGraphView {
+        let nodes = Capability::ALL
+            .iter()
+            .map(|capability| SkillNode {
+                capability: capability.clone(),
+                agents: cards
+                    .iter()
+                    .filter(|card| card.skills.root == *capability)
+                    .map(|card| card.id.clone())
+                    .collect(),
+                subskill_agents: cards
+                    .iter()
+                    .filter(|card| card.skills.subskills.contains(capability))
+                    .map(|card| card.id.clone())
+                    .collect(),
+            })
+            .collect();
+
+        let edges = Capability::ALL
+            .iter()
+            .flat_map(|capability| {
+                self.prerequisites(capability).iter().map(|prerequisite| SkillEdge {
+                    from: capability.clone(),
+                    to: prerequisite.clone(),
+                })
+            })
+            .collect();
+
+        SkillGraphView { nodes, edges }
+    }
+}
+
+/// Prerequisites the open project already satisfies, so plans need not produce them again:
+/// requirements and architecture on record in project memory, source code and tests on disk,
+/// and a repository analysis in the retrieval index
+pub fn satisfied_by_project(project: &ProjectMemoryHandle) -> HashSet<Capability> {
+    let mut satisfied = HashSet::new();
+    if project.requirements().is_some() {
+        satisfied.insert(Capability::Requirements);
+    }
+    if project.architecture().is_some() {
+        satisfied.insert(Capability::Architecture);
+    }
+
+    let Some(project_root) = project.root().and_then(|root| root.parent().map(Path::to_path_buf)) else {
+        return satisfied;
+    };
+    if retrieval::has_index(&project_root) {
+        satisfied.insert(Capability::RepoAnalysis);
+    }
+    let sources: Vec<String> = retrieval::project_files(&project_root)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|path| is_source_file(path))
+        .collect();
+    if !sources.is_empty() {
+        satisfied.insert(Capability::CodeGen);
+    }
+    if sources.iter().any(|path| is_test_file(path)) {
+        satisfied.insert(Capability::Testing);
+    }
+    satisfied
+}
+
+fn is_source_file(path: &str) -> bool {
+    !path.starts_with(".winter/")
+        && Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| SOURCE_EXTENSIONS.contains(&ext))
+}
+
+/// Files in a `test`/`tests`/`spec` directory or named after tests, e.g. `app.test.ts`
+fn is_test_file(path: &str) -> bool {
+    path.split('/').any(|part| {
+        let part = part.to_lowercase();
+        ["test", "tests", "spec", "__tests__"].contains(&part.as_str())
+            || part.starts_with("test_")
+            || part.contains("_test.")
+            || part.contains(".test.")
+            || part.contains(".spec.")
+    })
+}
+
+fn capability_of(task: &AgentTask) -> Option<Capability> {
+    task.task_type.parse().ok()
+}
+
+/// Whether the task at `index` depends, directly or transitively, on a task of `prerequisite`
+fn has_prerequisite(plan: &[AgentTask], index: usize, prerequisite: &Capability) -> bool {
+    plan.iter().any(|task| {
+        capability_of(task).as_ref() == Some(prerequisite) && depends_on(plan, &plan[index].task_id, &task.task_id)
+    })
+}
+
+/// Whether `task_id` reaches `target` through `depends_on` edges
+fn depends_on(plan: &[AgentTask], task_id: &str, target: &str) -> bool {
+    let by_id: HashMap<&str, &AgentTask> = plan.iter().map(|t| (t.task_id.as_str(), t)).collect();
+    let mut stack = vec![task_id];
+    let mut seen = HashSet::new();
+
+    while let Some(id) = stack.pop() {
+        let Some(task) = by_id.get(id) else {
+            continue;
+        };
+        for dep in &task.depends_on {
+            if dep == target {
+                return true;
+            }
+            if seen.insert(dep.as_str()) {
+                stack.push(dep);
+            }
+        }
+    }
+    false
+}
+
+/// A new task of the prerequisite capability working on the same input as the task needing it
+fn prerequisite_task(dependent: &AgentTask, prerequisite: &Capability) -> AgentTask {
+    let mut context = dependent.context.clone();
+    context.parent_task_id = Some(dependent.task_id.clone());
+    context.retry_of = None;
+    context.attempt = 0;
+    context.dependency_outputs = HashMap::new();
+
+    AgentTask {
+        task_id: uuid::Uuid::new_v4().to_string(),
+        task_type: format!("{:?}", prerequisite),
+        payload: dependent.payload.clone(),
+        context,
+        status: TaskStatus::Pending,
+        depends_on: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::types::AgentTaskContext;
+
+    fn task(id: &str, task_type: &str, depends_on: &[&str]) -> AgentTask {
+        AgentTask {
+            task_id: id.into(),
+            task_type: task_type.into(),
+            payload: "build a todo app".into(),
+            context: AgentTaskContext {
+                origin: "test".into(),
+                goal_id: Some("goal".into()),
+                parent_task_id: None,
+                retry_of: None,
+                revision_id: None,
+                execution_mode: None,
+                dependency_outputs: HashMap::new(),
+                timeout_secs: None,
+                attempt: 0,
+                skip_prerequisites: false,
+            },
+            status: TaskStatus::Pending,
+            depends_on: depends_on.iter().map(|dep| dep.to_string()).collect(),
+        }
+    }
+
+    fn types(plan: &[AgentTask]) -> Vec<&str> {
+        plan.iter().map(|task| task.task_type.as_str()).collect()
+    }
+
+    #[test]
+    fn inserts_missing_prerequisites_in_order() {
+        let graph = CapabilityGraph::builtin();
+        let (plan, inserted) = graph.resolve_plan(vec![task("code", "CodeGen", &[])], &HashSet::new());
+
+        assert_eq!(types(&plan), vec!["Requirements", "Architecture", "CodeGen"]);
+        assert_eq!(inserted.len(), 2);
+        assert_eq!(plan[1].depends_on, vec![plan[0].task_id.clone()]);
+        assert_eq!(plan[2].depends_on, vec![plan[1].task_id.clone()]);
+        assert_eq!(plan[1].context.parent_task_id.as_deref(), Some("code"));
+        assert!(graph.validate_plan(&plan, &HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn links_existing_prerequisite_tasks_instead_of_inserting() {
+        let graph = CapabilityGraph::builtin();
+        let plan = vec![
+            task("code", "CodeGen", &[]),
+            task("arch", "Architecture", &["reqs"]),
+            task("reqs", "Requirements", &[]),
+        ];
+        let (plan, inserted) = graph.resolve_plan(plan, &HashSet::new());
+
+        assert!(inserted.is_empty());
+        assert_eq!(plan[0].depends_on, vec!["arch".to_string()]);
+        assert!(graph.validate_plan(&plan, &HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn reports_prerequisites_that_run_too_late() {
+        let graph = CapabilityGraph::builtin();
+        let plan = vec![task("reqs", "Requirements", &["arch"]), task("arch", "Architecture", &[])];
+        let (plan, inserted) = graph.resolve_plan(plan, &HashSet::new());
+
+        assert!(inserted.is_empty());
+        assert_eq!(
+            graph.validate_plan(&plan, &HashSet::new()),
+            vec![PrerequisiteViolation {
+                task_id: "arch".into(),
+                capability: Capability::Architecture,
+                prerequisite: Capability::Requirements,
+            }]
+        );
+    }
+
+    #[test]
+    fn skips_prerequisites_already_in_project_memory() {
+        let project = ProjectMemoryHandle::new();
+        project.set_requirements("A todo app with due dates").unwrap();
+        project.set_architecture("Tauri frontend, SQLite storage").unwrap();
+        let satisfied = satisfied_by_project(&project);
+        assert_eq!(satisfied, HashSet::from([Capability::Requirements, Capability::Architecture]));
+
+        let graph = CapabilityGraph::builtin();
+        let (plan, inserted) = graph.resolve_plan(vec![task("code", "CodeGen", &[])], &satisfied);
+        assert!(inserted.is_empty());
+        assert_eq!(types(&plan), vec!["CodeGen"]);
+        assert!(graph.validate_plan(&plan, &satisfied).is_empty());
+
+        // Only the architecture is missing once the requirements are known
+        let satisfied = HashSet::from([Capability::Requirements]);
+        let (plan, inserted) = graph.resolve_plan(vec![task("code", "CodeGen", &[])], &satisfied);
+        assert_eq!(inserted.len(), 1);
+        assert_eq!(types(&plan), vec!["Architecture", "CodeGen"]);
+    }
+
+    #[test]
+    fn code_and_tests_on_disk_satisfy_their_prerequisites() {
+        let root = std::env::temp_dir().join(format!("winter-skills-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        let project = ProjectMemoryHandle::new();
+        project.open(&root).unwrap();
+
+        let satisfied = satisfied_by_project(&project);
+        assert!(satisfied.contains(&Capability::CodeGen));
+        assert!(!satisfied.contains(&Capability::Testing));
+
+        std::fs::create_dir_all(root.join("tests")).unwrap();
+        std::fs::write(root.join("tests/cli.rs"), "#[test]\nfn runs() {}\n").unwrap();
+        let satisfied = satisfied_by_project(&project);
+        assert!(satisfied.contains(&Capability::Testing));
+
+        // Deployment of existing, tested code needs no new tasks
+        let graph = CapabilityGraph::builtin();
+        let (plan, inserted) = graph.resolve_plan(vec![task("ship", "Deployment", &[])], &satisfied);
+        assert!(inserted.is_empty());
+        assert_eq!(types(&plan), vec!["Deployment"]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn opted_out_tasks_report_missing_prerequisites_instead() {
+        let graph = CapabilityGraph::builtin();
+        let mut refactor = task("refactor", "Refactoring", &[]);
+        refactor.context.skip_prerequisites = true;
+        let (plan, inserted) = graph.resolve_plan(vec![refactor], &HashSet::new());
+
+        assert!(inserted.is_empty());
+        assert_eq!(
+            graph.validate_plan(&plan, &HashSet::new()),
+            vec![PrerequisiteViolation {
+                task_id: "refactor".into(),
+                capability: Capability::Refactoring,
+                prerequisite: Capability::RepoAnalysis,
+            }]
+        );
+    }
+}
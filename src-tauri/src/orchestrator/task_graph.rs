@@ -0,0 +1,220 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::orchestrator::types::AgentTask;
+
+/// A planner task graph with dependency edges resolved into execution layers.
+/// Every task in a layer only depends on tasks from earlier layers, so a layer can run concurrently.
+#[derive(Debug, Clone)]
+pub struct TaskGraph {
+    layers: Vec<Vec<AgentTask>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskGraphError {
+    DuplicateTaskId(String),
+    UnknownDependency { task_id: String, dependency: String },
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for TaskGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskGraphError::DuplicateTaskId(id) => write!(f, "Duplicate task id in plan: {}", id),
+            TaskGraphError::UnknownDependency { task_id, dependency } => write!(
+                f,
+                "Task {} depends on unknown task {}",
+                task_id, dependency
+            ),
+            TaskGraphError::Cycle(ids) => write!(f, "Dependency cycle between tasks: {}", ids.join(", ")),
+        }
+    }
+}
+
+impl TaskGraph {
+    /// Validates the dependency edges and topologically sorts the tasks (Kahn's algorithm).
+    /// Tasks keep their planner order within a layer.
+    pub fn build(tasks: Vec<AgentTask>) -> Result<Self, TaskGraphError> {
+        let mut ids = HashSet::new();
+        for task in &tasks {
+            if !ids.insert(task.task_id.clone()) {
+                return Err(TaskGraphError::DuplicateTaskId(task.task_id.clone()));
+            }
+        }
+
+        let mut in_degree: HashMap<String, usize> = HashMap::new();
+        let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+
+        for task in &tasks {
+            let mut seen = HashSet::new();
+            for dep in &task.depends_on {
+                if !ids.contains(dep) {
+                    return Err(TaskGraphError::UnknownDependency {
+                        task_id: task.task_id.clone(),
+                        dependency: dep.clone(),
+                    });
+                }
+                if seen.insert(dep) {
+                    dependents.entry(dep.clone()).or_default().push(task.task_id.clone());
+                }
+            }
+            in_degree.insert(task.task_id.clone(), seen.len());
+        }
+
+        let mut remaining = tasks;
+        let mut layers = vec![];
+
+        while !remaining.is_empty() {
+            let (ready, blocked): (Vec<AgentTask>, Vec<AgentTask>) = remaining
+                .into_iter()
+                .partition(|task| in_degree[&task.task_id] == 0);
+
+            if ready.is_empty() {
+                return Err(TaskGraphError::Cycle(
+                    blocked.iter().map(|t| t.task_id.clone()).collect(),
+                ));
+            }
+
+            for task in &ready {
+                for dependent in dependents.get(&task.task_id).into_iter().flatten() {
+                    if let Some(degree) = in_degree.get_mut(dependent) {
+                        *degree -= 1;
+                    }
+                }
+            }
+
+            layers.push(ready);
+            remaining = blocked;
+        }
+
+        Ok(Self { layers })
+    }
+
+    pub fn layers(&self) -> &[Vec<AgentTask>] {
+        &self.layers
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.iter().map(|layer| layer.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+}
+
+/// The first dependency of `task` that ended without succeeding, which keeps it from running.
+/// A skipped task counts as ended, so the skip propagates to everything downstream of it.
+pub fn blocking_dependency(task: &AgentTask, ended_unsuccessfully: impl Fn(&str) -> bool) -> Option<&String> {
+    task.depends_on.iter().find(|dep| ended_unsuccessfully(dep.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::types::{AgentTaskContext, TaskStatus};
+
+    fn task(id: &str, depends_on: &[&str]) -> AgentTask {
+        AgentTask {
+            task_id: id.into(),
+            task_type: "CodeGen".into(),
+            payload: String::new(),
+            context: AgentTaskContext {
+                origin: "test".into(),
+                goal_id: None,
+                parent_task_id: None,
+                retry_of: None,
+                revision_id: None,
+                execution_mode: None,
+                dependency_outputs: Default::default(),
+                timeout_secs: None,
+                attempt: 0,
+                skip_prerequisites: false,
+            },
+            status: TaskStatus::Pending,
+            depends_on: depends_on.iter().map(|dep| dep.to_string()).collect(),
+        }
+    }
+
+    fn layer_ids(graph: &TaskGraph) -> Vec<Vec<&str>> {
+        graph
+            .layers()
+            .iter()
+            .map(|layer| layer.iter().map(|task| task.task_id.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn layers_follow_dependencies_and_keep_planner_order() {
+        let graph = TaskGraph::build(vec![
+            task("c", &["a", "b"]),
+            task("b", &[]),
+            task("a", &[]),
+            task("d", &["c", "c"]),
+        ])
+        .unwrap();
+
+        assert_eq!(layer_ids(&graph), vec![vec!["b", "a"], vec!["c"], vec!["d"]]);
+        assert_eq!(graph.len(), 4);
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let result = TaskGraph::build(vec![task("a", &[]), task("b", &["c"]), task("c", &["b"])]);
+        assert_eq!(result.unwrap_err(), TaskGraphError::Cycle(vec!["b".into(), "c".into()]));
+
+        let result = TaskGraph::build(vec![task("self", &["self"])]);
+        assert!(matches!(result, Err(TaskGraphError::Cycle(_))));
+    }
+
+    #[test]
+    fn rejects_unknown_dependencies_and_duplicate_ids() {
+        assert_eq!(
+            TaskGraph::build(vec![task("a", &["missing"])]).unwrap_err(),
+            TaskGraphError::UnknownDependency {
+                task_id: "a".into(),
+                dependency: "missing".into(),
+            }
+        );
+        assert_eq!(
+            TaskGraph::build(vec![task("a", &[]), task("a", &[])]).unwrap_err(),
+            TaskGraphError::DuplicateTaskId("a".into())
+        );
+    }
+
+    #[test]
+    fn failures_skip_everything_downstream() {
+        let graph = TaskGraph::build(vec![
+            task("a", &[]),
+            task("b", &["a"]),
+            task("c", &["b"]),
+            task("d", &[]),
+            task("e", &["d", "c"]),
+        ])
+        .unwrap();
+
+        // Runs the layers as the orchestrator does, with task `a` failing
+        let mut statuses: HashMap<String, TaskStatus> = HashMap::new();
+        for layer in graph.layers() {
+            for task in layer {
+                let failed = |id: &str| statuses.get(id).is_some_and(|status| *status != TaskStatus::Succeeded);
+                let status = match blocking_dependency(task, failed) {
+                    Some(blocker) => TaskStatus::Skipped {
+                        blocked_by: blocker.clone(),
+                    },
+                    None if task.task_id == "a" => TaskStatus::Failed { reason: "boom".into() },
+                    None => TaskStatus::Succeeded,
+                };
+                statuses.insert(task.task_id.clone(), status);
+            }
+        }
+
+        let skipped = |blocker: &str| TaskStatus::Skipped {
+            blocked_by: blocker.into(),
+        };
+        assert_eq!(statuses["b"], skipped("a"));
+        assert_eq!(statuses["c"], skipped("b"));
+        assert_eq!(statuses["d"], TaskStatus::Succeeded);
+        assert_eq!(statuses["e"], skipped("c"));
+    }
+}
@@ -1,8 +1,16 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::fs::{File, OpenOptions};
-use std::io::{BufReader, BufWriter};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 use serde::{Serialize, Deserialize};
 
+use crate::orchestrator::types::now_timestamp;
+
+/// Entries per JSONL segment before a new segment is started
+const SEGMENT_MAX_ENTRIES: usize = 5000;
+
 /// This allows for a timeline viewer, fast search/filter by task and metadata view without opening rhe full task logs
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TaskIndexEntry{
@@ -15,48 +23,434 @@ pub struct TaskIndexEntry{
     pub revision_id: Option<u32>,
 }
 
-pub fn append_to_task_index(entry: &TaskIndexEntry)-> std::io::Result<()> {
-    let index_path = dirs::home_dir()
-        .expect("No home dir")
-        .join("WinterData/logs/task_index.json");
-
-    let mut entries = if index_path.exists(){
-        let file = File::open(&index_path)?;
-        let reader = BufReader::new(file);
-        serde_json::from_reader::<_,Vec<TaskIndexEntry>>(reader).unwrap_or_default()
-    } else {
-        vec![]
-    };
-    entries.push(entry.clone());
-
-    let  file = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open(&index_path)?;
-    let writer = BufWriter::new(file);
-    serde_json::to_writer_pretty(writer, &entries)?;
+impl TaskIndexEntry {
+    /// Status variant without its fields, e.g. "Failed" for `Failed { reason: .. }`
+    pub fn status_name(&self) -> &str {
+        self.status
+            .split([' ', '{', '('])
+            .next()
+            .unwrap_or(&self.status)
+    }
+}
+
+/// Filter for `query_task_index`. Every field that is set must match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskQuery {
+    pub goal_id: Option<String>,
+    pub agent_id: Option<String>,
+    pub status: Option<String>,
+    /// Inclusive lower bound on the entry timestamp (unix seconds)
+    pub since: Option<u64>,
+    /// Inclusive upper bound on the entry timestamp (unix seconds)
+    pub until: Option<u64>,
+    pub revision_id: Option<u32>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactionReport {
+    pub kept: usize,
+    pub removed: usize,
+    pub segments: usize,
+}
+
+/// Append-only task index stored as JSONL segments under `WinterData/logs/task_store/`.
+/// All entries are loaded once and indexed in memory; appends only touch the last segment.
+pub struct TaskStore {
+    /// None for a store that only lives in memory because the directory could not be opened
+    dir: Option<PathBuf>,
+    entries: Vec<TaskIndexEntry>,
+    by_goal: HashMap<String, Vec<usize>>,
+    by_agent: HashMap<String, Vec<usize>>,
+    by_status: HashMap<String, Vec<usize>>,
+    segment: usize,
+    segment_len: usize,
+}
+
+static TASK_STORE: OnceLock<Mutex<TaskStore>> = OnceLock::new();
+
+/// The process-wide task store; every writer goes through its lock
+pub fn task_store() -> &'static Mutex<TaskStore> {
+    TASK_STORE.get_or_init(|| {
+        let dir = dirs::home_dir()
+            .expect("No home dir")
+            .join("WinterData/logs/task_store");
+        let store = TaskStore::open(dir).unwrap_or_else(|e| {
+            eprintln!("[warn] Failed to open task store, keeping the task index in memory only: {}", e);
+            TaskStore::empty(None)
+        });
+        Mutex::new(store)
+    })
+}
+
+impl TaskStore {
+    fn empty(dir: Option<PathBuf>) -> Self {
+        Self {
+            dir,
+            entries: vec![],
+            by_goal: HashMap::new(),
+            by_agent: HashMap::new(),
+            by_status: HashMap::new(),
+            segment: 0,
+            segment_len: 0,
+        }
+    }
+
+    pub fn open(dir: PathBuf) -> std::io::Result<Self> {
+        recover_compaction(&dir)?;
+        fs::create_dir_all(&dir)?;
+        let segments = segment_files(&dir)?;
+        if let Some((_, last)) = segments.last() {
+            truncate_torn_line(last)?;
+        }
+        let mut store = Self::empty(Some(dir));
+
+        for (number, path) in segments {
+            let reader = BufReader::new(File::open(&path)?);
+            let mut len = 0;
+            for line in reader.lines() {
+                let line = line?;
+                // A torn last line from a crash is skipped rather than failing the whole store
+                if let Ok(entry) = serde_json::from_str::<TaskIndexEntry>(&line) {
+                    store.index(entry);
+                    len += 1;
+                }
+            }
+            store.segment = number;
+            store.segment_len = len;
+        }
+
+        store.migrate_legacy_index()?;
+        Ok(store)
+    }
 
+    /// Imports the old whole-file `task_index.json` once, then renames it out of the way
+    fn migrate_legacy_index(&mut self) -> std::io::Result<()> {
+        let Some(logs) = self.dir.as_ref().and_then(|dir| dir.parent()).map(Path::to_path_buf) else {
+            return Ok(());
+        };
+        let legacy = logs.join("task_index.json");
+        if !legacy.exists() {
+            return Ok(());
+        }
+
+        let content = fs::read_to_string(&legacy)?;
+        let entries: Vec<TaskIndexEntry> = serde_json::from_str(&content).unwrap_or_default();
+        println!("[TaskStore] Migrating {} entries from task_index.json", entries.len());
+        for entry in entries {
+            self.append(entry)?;
+        }
+        fs::rename(&legacy, logs.join("task_index.json.migrated"))
+    }
+
+    fn index(&mut self, entry: TaskIndexEntry) {
+        let position = self.entries.len();
+        if let Some(goal_id) = &entry.goal_id {
+            self.by_goal.entry(goal_id.clone()).or_default().push(position);
+        }
+        self.by_agent.entry(entry.agent_id.clone()).or_default().push(position);
+        self.by_status
+            .entry(entry.status_name().to_string())
+            .or_default()
+            .push(position);
+        self.entries.push(entry);
+    }
+
+    pub fn append(&mut self, entry: TaskIndexEntry) -> std::io::Result<()> {
+        if self.segment_len >= SEGMENT_MAX_ENTRIES {
+            self.segment += 1;
+            self.segment_len = 0;
+        }
+
+        if let Some(dir) = &self.dir {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(segment_path(dir, self.segment))?;
+            let mut line = serde_json::to_string(&entry)?;
+            line.push('\n');
+            file.write_all(line.as_bytes())?;
+        }
+
+        self.segment_len += 1;
+        self.index(entry);
+        Ok(())
+    }
+
+    /// Matching entries, oldest first
+    pub fn query(&self, query: &TaskQuery) -> Vec<TaskIndexEntry> {
+        // Start from the narrowest index that applies
+        let candidates: Box<dyn Iterator<Item = usize> + '_> = match (&query.goal_id, &query.agent_id, &query.status) {
+            (Some(goal), _, _) => Box::new(self.by_goal.get(goal).into_iter().flatten().copied()),
+            (_, Some(agent), _) => Box::new(self.by_agent.get(agent).into_iter().flatten().copied()),
+            (_, _, Some(status)) => Box::new(self.by_status.get(status).into_iter().flatten().copied()),
+            _ => Box::new(0..self.entries.len()),
+        };
+
+        candidates
+            .map(|i| &self.entries[i])
+            .filter(|e| query.goal_id.is_none() || e.goal_id == query.goal_id)
+            .filter(|e| query.agent_id.as_ref().is_none_or(|a| &e.agent_id == a))
+            .filter(|e| query.status.as_ref().is_none_or(|s| e.status_name() == s))
+            .filter(|e| query.since.is_none_or(|t| e.timestamp >= t))
+            .filter(|e| query.until.is_none_or(|t| e.timestamp <= t))
+            .filter(|e| query.revision_id.is_none() || e.revision_id == query.revision_id)
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    pub fn all(&self) -> &[TaskIndexEntry] {
+        &self.entries
+    }
+
+    /// Rewrites the store keeping only the latest entry per task, dropping entries older than
+    /// `max_age_secs` and then the oldest ones beyond `max_entries`.
+    pub fn compact(&mut self, max_age_secs: Option<u64>, max_entries: Option<usize>) -> std::io::Result<CompactionReport> {
+        let before = self.entries.len();
+        let cutoff = max_age_secs.map(|age| now_timestamp().saturating_sub(age));
+
+        let mut seen = HashSet::new();
+        let mut kept: Vec<TaskIndexEntry> = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| seen.insert(e.task_id.clone()))
+            .filter(|e| cutoff.is_none_or(|c| e.timestamp >= c))
+            .cloned()
+            .collect();
+        if let Some(max) = max_entries {
+            kept.truncate(max);
+        }
+        kept.reverse();
+
+        if let Some(dir) = &self.dir {
+            // Write the new segments next to the old ones, then swap them in. A crash at any
+            // point leaves either the old or the new store in place for `open` to recover.
+            let (staging, old) = compaction_paths(dir);
+            if staging.exists() {
+                fs::remove_dir_all(&staging)?;
+            }
+            fs::create_dir_all(&staging)?;
+            for (number, chunk) in kept.chunks(SEGMENT_MAX_ENTRIES).enumerate() {
+                let file = File::create(segment_path(&staging, number))?;
+                let mut writer = BufWriter::new(file);
+                for entry in chunk {
+                    serde_json::to_writer(&mut writer, entry)?;
+                    writer.write_all(b"\n")?;
+                }
+                writer.flush()?;
+                writer.get_ref().sync_all()?;
+            }
+            fs::rename(dir, &old)?;
+            fs::rename(&staging, dir)?;
+            fs::remove_dir_all(&old)?;
+        }
+
+        let mut compacted = Self::empty(self.dir.clone());
+        let segments = kept.len().div_ceil(SEGMENT_MAX_ENTRIES);
+        for entry in kept {
+            compacted.index(entry);
+        }
+        compacted.segment = segments.saturating_sub(1);
+        compacted.segment_len = compacted.entries.len() - compacted.segment * SEGMENT_MAX_ENTRIES;
+        *self = compacted;
+
+        Ok(CompactionReport {
+            kept: self.entries.len(),
+            removed: before - self.entries.len(),
+            segments,
+        })
+    }
+}
+
+fn segment_path(dir: &Path, number: usize) -> PathBuf {
+    dir.join(format!("segment-{:06}.jsonl", number))
+}
+
+/// Segment files sorted by number
+fn segment_files(dir: &Path) -> std::io::Result<Vec<(usize, PathBuf)>> {
+    let mut segments: Vec<(usize, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let number = name.strip_prefix("segment-")?.strip_suffix(".jsonl")?.parse().ok()?;
+            Some((number, entry.path()))
+        })
+        .collect();
+    segments.sort_by_key(|(number, _)| *number);
+    Ok(segments)
+}
+
+/// Directories used while compacting: the new segments, and the live store moved aside
+fn compaction_paths(dir: &Path) -> (PathBuf, PathBuf) {
+    (dir.with_extension("compacting"), dir.with_extension("old"))
+}
+
+/// Finishes or rolls back a compaction that was interrupted by a crash.
+/// Staging is complete once the live store has been moved aside, so it is swapped in then;
+/// otherwise it is discarded and the live store is kept.
+fn recover_compaction(dir: &Path) -> std::io::Result<()> {
+    let (staging, old) = compaction_paths(dir);
+    if old.exists() {
+        if !dir.exists() {
+            if staging.exists() {
+                println!("[TaskStore] Finishing interrupted compaction");
+                fs::rename(&staging, dir)?;
+            } else {
+                fs::rename(&old, dir)?;
+                return Ok(());
+            }
+        }
+        fs::remove_dir_all(&old)?;
+    }
+    if staging.exists() {
+        println!("[TaskStore] Discarding interrupted compaction");
+        fs::remove_dir_all(&staging)?;
+    }
     Ok(())
 }
-pub fn read_task_index()-> std::io::Result<Vec<TaskIndexEntry>>{
-    let path = dirs::home_dir()
-        .expect("No home dir")
-        .join("WinterData/logs/task_index.json");
 
-    if !path.exists(){
-        return Ok(vec![]); // Return emty list if file doesnt exist
+/// Cuts a line torn by a crash off the end of the segment, so the next append starts on a fresh line
+fn truncate_torn_line(path: &Path) -> std::io::Result<()> {
+    let content = fs::read(path)?;
+    if content.last().is_none_or(|&last| last == b'\n') {
+        return Ok(());
     }
+    let keep = content.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    eprintln!("[warn] Dropping torn line at the end of {}", path.display());
+    OpenOptions::new().write(true).open(path)?.set_len(keep as u64)
+}
 
-    let content = fs::read_to_string(path)?;
-    let entries: Vec<TaskIndexEntry> = serde_json::from_str(&content)?;
-    Ok(entries)
+pub fn append_to_task_index(entry: &TaskIndexEntry)-> std::io::Result<()> {
+    task_store().lock().unwrap().append(entry.clone())
+}
+pub fn read_task_index()-> std::io::Result<Vec<TaskIndexEntry>>{
+    Ok(task_store().lock().unwrap().all().to_vec())
 }
 pub fn get_failed_tasks()->std::io::Result<Vec<TaskIndexEntry>>{
-    read_task_index().map(|entries|{
-        entries
-            .into_iter()
-            .filter(|entry| entry.status=="Failed")
-            .collect()
-    })
-}
\ No newline at end of file
+    Ok(query_tasks(&TaskQuery {
+        status: Some("Failed".into()),
+        ..TaskQuery::default()
+    }))
+}
+pub fn query_tasks(query: &TaskQuery) -> Vec<TaskIndexEntry> {
+    task_store().lock().unwrap().query(query)
+}
+
+#[tauri::command]
+pub fn query_task_index(query: TaskQuery) -> Vec<TaskIndexEntry> {
+    query_tasks(&query)
+}
+
+#[tauri::command]
+pub fn compact_task_index(max_age_days: Option<u64>, max_entries: Option<usize>) -> Result<CompactionReport, String> {
+    task_store()
+        .lock()
+        .unwrap()
+        .compact(max_age_days.map(|days| days * 24 * 60 * 60), max_entries)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store_dir() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("winter-tasks-{}", uuid::Uuid::new_v4()))
+            .join("task_store")
+    }
+
+    fn entry(task_id: &str) -> TaskIndexEntry {
+        TaskIndexEntry {
+            task_id: task_id.into(),
+            agent_id: "codegen".into(),
+            task_type: "generate_code".into(),
+            status: "Succeeded".into(),
+            goal_id: Some("goal".into()),
+            timestamp: now_timestamp(),
+            revision_id: None,
+        }
+    }
+
+    #[test]
+    fn append_after_torn_line_starts_a_new_line() {
+        let dir = temp_store_dir();
+        let mut store = TaskStore::open(dir.clone()).unwrap();
+        store.append(entry("a")).unwrap();
+        drop(store);
+
+        let segment = segment_path(&dir, 0);
+        let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+        file.write_all(b"{\"task_id\":\"torn").unwrap();
+
+        let mut store = TaskStore::open(dir.clone()).unwrap();
+        store.append(entry("b")).unwrap();
+        let reopened = TaskStore::open(dir.clone()).unwrap();
+        let ids: Vec<&str> = reopened.all().iter().map(|e| e.task_id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+
+        fs::remove_dir_all(dir.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn open_finishes_compaction_interrupted_after_moving_the_store_aside() {
+        let dir = temp_store_dir();
+        let mut store = TaskStore::open(dir.clone()).unwrap();
+        store.append(entry("a")).unwrap();
+        store.append(entry("a")).unwrap();
+        drop(store);
+
+        // Staging written, live store moved aside, crash before staging was swapped in
+        let (staging, old) = compaction_paths(&dir);
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(segment_path(&staging, 0), format!("{}\n", serde_json::to_string(&entry("a")).unwrap())).unwrap();
+        fs::rename(&dir, &old).unwrap();
+
+        let store = TaskStore::open(dir.clone()).unwrap();
+        assert_eq!(store.all().len(), 1);
+        assert!(!old.exists() && !staging.exists());
+
+        fs::remove_dir_all(dir.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn open_discards_unfinished_staging() {
+        let dir = temp_store_dir();
+        let mut store = TaskStore::open(dir.clone()).unwrap();
+        store.append(entry("a")).unwrap();
+        store.append(entry("b")).unwrap();
+        drop(store);
+
+        let (staging, _) = compaction_paths(&dir);
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(segment_path(&staging, 0), "{\"task_id\"").unwrap();
+
+        let store = TaskStore::open(dir.clone()).unwrap();
+        assert_eq!(store.all().len(), 2);
+        assert!(!staging.exists());
+
+        fs::remove_dir_all(dir.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn compact_keeps_latest_entry_per_task() {
+        let dir = temp_store_dir();
+        let mut store = TaskStore::open(dir.clone()).unwrap();
+        for id in ["a", "b", "a"] {
+            store.append(entry(id)).unwrap();
+        }
+        let report = store.compact(None, None).unwrap();
+        assert_eq!((report.kept, report.removed), (2, 1));
+
+        let (staging, old) = compaction_paths(&dir);
+        assert!(!old.exists() && !staging.exists());
+        assert_eq!(TaskStore::open(dir.clone()).unwrap().all().len(), 2);
+
+        fs::remove_dir_all(dir.parent().unwrap()).ok();
+    }
+}
@@ -2,9 +2,9 @@ use std::fs;
 use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::agents::orchestrator::protocol::{AgentResponse};
+use crate::orchestrator::protocol::{AgentResponse};
 use serde::{Deserialize, Serialize};
-use crate::agents::orchestrator::types::AgentTask;
+use crate::orchestrator::types::AgentTask;
 
 #[derive(Serialize, Deserialize)]
 pub struct TaskLogEntry {
@@ -13,7 +13,7 @@ pub struct TaskLogEntry {
     pub response: AgentResponse,
 }
 
-pub fn write_task_log(task: &AgentTask, response: AgentResponse) -> std::io::Result<()> {
+pub fn write_task_log(task: &AgentTask, response: &AgentResponse) -> std::io::Result<()> {
     let log_entry = TaskLogEntry {
         timestamp: SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Live timeline updates for an open timeline view
+pub const TIMELINE_EVENT: &str = "timeline-event";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "type")]
@@ -11,6 +17,21 @@ pub enum TimelineEvent {
         agent_id: String,
         timestamp: u64,
     },
+    /// A task was re-run, either by the planner revision loop or from the feedback queue
+    Retry {
+        task_id: String,
+        retry_of: String,
+        reason: String,
+        timestamp: u64,
+    },
+    /// CritiqueAgent's verdict on a plan
+    Critique {
+        task_id: String,
+        plan_id: String,
+        score: Option<u8>,
+        approved: bool,
+        timestamp: u64,
+    },
     Decision {
         id: String,
         summary: String,
@@ -19,26 +40,152 @@ pub enum TimelineEvent {
         timestamp: u64,
     },
 }
-pub fn append_timeline_event(goal_id: &str, event: TimelineEvent) {
-    let folder = dirs::home_dir()
-        .expect("no home dir")
-        .join("WinterData/projects")
-        .join(goal_id);
 
-    let path = folder.join(&folder).ok();
+impl TimelineEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TimelineEvent::Task { .. } => "Task",
+            TimelineEvent::Retry { .. } => "Retry",
+            TimelineEvent::Critique { .. } => "Critique",
+            TimelineEvent::Decision { .. } => "Decision",
+        }
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            TimelineEvent::Task { timestamp, .. }
+            | TimelineEvent::Retry { timestamp, .. }
+            | TimelineEvent::Critique { timestamp, .. }
+            | TimelineEvent::Decision { timestamp, .. } => *timestamp,
+        }
+    }
+
+    pub fn task_id(&self) -> Option<&str> {
+        match self {
+            TimelineEvent::Task { task_id, .. }
+            | TimelineEvent::Retry { task_id, .. }
+            | TimelineEvent::Critique { task_id, .. } => Some(task_id),
+            TimelineEvent::Decision { .. } => None,
+        }
+    }
+}
+
+/// Filter and page for `get_goal_timeline`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimelineQuery {
+    /// Event types to include, e.g. ["Task", "Decision"]; all when empty
+    pub kinds: Vec<String>,
+    pub task_id: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelinePage {
+    pub goal_id: String,
+    pub events: Vec<TimelineEvent>,
+    /// Matching events across all pages
+    pub total: usize,
+    pub next_offset: Option<usize>,
+}
 
-    let mut entries: Vec<TimelineEvent> = if path.exists() {
-        fs::read_to_string(&path)
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default()
-    } else {
-        vec![]
-    };
+/// Timelines live in `<project>/.winter/timeline/`, or under WinterData when no project is open
+pub fn timeline_dir(project_root: Option<&Path>) -> PathBuf {
+    match project_root {
+        Some(root) => root.join(".winter/timeline"),
+        None => dirs::home_dir()
+            .expect("no home dir")
+            .join("WinterData/timelines"),
+    }
+}
 
-    entries.push(event);
+fn timeline_path(project_root: Option<&Path>, goal_id: &str) -> PathBuf {
+    let file_name: String = goal_id
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    timeline_dir(project_root).join(format!("{}.jsonl", file_name))
+}
+
+/// Appends one event to the goal's timeline (one JSON object per line)
+pub fn append_timeline_event(project_root: Option<&Path>, goal_id: &str, event: &TimelineEvent) -> std::io::Result<()> {
+    let path = timeline_path(project_root, goal_id);
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Reads a page of the goal's timeline, oldest event first
+pub fn read_timeline(project_root: Option<&Path>, goal_id: &str, query: &TimelineQuery) -> std::io::Result<TimelinePage> {
+    let path = timeline_path(project_root, goal_id);
+    let content = if path.exists() { fs::read_to_string(path)? } else { String::new() };
+
+    let matching: Vec<TimelineEvent> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<TimelineEvent>(line).ok())
+        .filter(|e| query.kinds.is_empty() || query.kinds.iter().any(|k| k == e.kind()))
+        .filter(|e| query.task_id.is_none() || e.task_id() == query.task_id.as_deref())
+        .filter(|e| query.since.is_none_or(|t| e.timestamp() >= t))
+        .filter(|e| query.until.is_none_or(|t| e.timestamp() <= t))
+        .collect();
+
+    let total = matching.len();
+    let limit = query.limit.unwrap_or(total);
+    let events: Vec<TimelineEvent> = matching.into_iter().skip(query.offset).take(limit).collect();
+    let end = query.offset + events.len();
+
+    Ok(TimelinePage {
+        goal_id: goal_id.to_string(),
+        events,
+        total,
+        next_offset: (end < total).then_some(end),
+    })
+}
+
+/// Goal ids that have a timeline in the project
+pub fn list_timelines(project_root: Option<&Path>) -> std::io::Result<Vec<String>> {
+    let folder = timeline_dir(project_root);
+    if !folder.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut goals: Vec<String> = fs::read_dir(folder)?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_suffix(".jsonl").map(String::from)
+        })
+        .collect();
+    goals.sort();
+    Ok(goals)
+}
+
+#[tauri::command]
+pub fn get_goal_timeline(project_path: Option<String>, goal_id: String, query: Option<TimelineQuery>) -> Result<TimelinePage, String> {
+    let root = canonical_project_root(project_path)?;
+    read_timeline(root.as_deref(), &goal_id, &query.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_goal_timelines(project_path: Option<String>) -> Result<Vec<String>, String> {
+    let root = canonical_project_root(project_path)?;
+    list_timelines(root.as_deref()).map_err(|e| e.to_string())
+}
 
-    fs::write(path, serde_json::to_string_pretty(&entries))
-        .unwrap()
-        .ok();
+/// Timelines are written under the canonical project root, so the frontend's path is canonicalized too
+fn canonical_project_root(project_path: Option<String>) -> Result<Option<PathBuf>, String> {
+    project_path
+        .map(|path| fs::canonicalize(&path).map_err(|e| format!("Invalid project path {}: {}", path, e)))
+        .transpose()
 }
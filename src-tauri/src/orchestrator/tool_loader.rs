@@ -1,6 +1,14 @@
-use crate::agents::tools::registry::ToolRegistry;
-use crate::agents::tools::echo_tool::EchoTool;
+use std::sync::Arc;
+use crate::tools::registry::ToolRegistry;
+use crate::tools::echo_tool::EchoTool;
+use crate::llm::backend::LlmBackend;
+use crate::tools::file_tool::FileTool;
+use crate::tools::llm_tool::LLMTool;
+use crate::tools::patch_tool::PatchTool;
 
-pub fn register_all_tools(registry: &mut ToolRegistry){
+pub fn register_all_tools(registry: &mut ToolRegistry, llm: Arc<dyn LlmBackend>){
     registry.register_tool(Box::new(EchoTool));
+    registry.register_tool(Box::new(LLMTool::new(llm)));
+    registry.register_tool(Box::new(FileTool));
+    registry.register_tool(Box::new(PatchTool));
 }
\ No newline at end of file
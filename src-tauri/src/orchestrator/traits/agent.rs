@@ -1,5 +1,5 @@
-use crate::agents::orchestrator::protocol::AgentResponse;
-use crate::agents::orchestrator::types::{AgentTask};
+use crate::orchestrator::protocol::AgentResponse;
+use crate::orchestrator::types::{AgentTask};
 
 pub trait Agent {
     fn id(&self) -> &'static str;
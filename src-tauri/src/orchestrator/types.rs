@@ -1,14 +1,32 @@
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
+use crate::orchestrator::protocol::AgentOutput;
 /// Shared Types
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentTaskContext{
     pub origin: String,
     pub goal_id: Option<String>,
     pub parent_task_id: Option<String>,
     pub retry_of: Option<String>,
     pub revision_id: Option<u32>,
+    /// Goal-level override of the agents' default execution mode, inherited by subtasks
+    #[serde(default)]
+    pub execution_mode: Option<ExecutionMode>,
+    /// Outputs of the tasks listed in `depends_on`, keyed by task id. Filled in by the orchestrator.
+    #[serde(default)]
+    pub dependency_outputs: HashMap<String, AgentOutput>,
+    /// Per-task deadline in seconds; the shorter of this and `AgentCard::timeout_secs` applies
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Zero-based attempt number along the `retry_of` lineage
+    #[serde(default)]
+    pub attempt: u32,
+    /// Set by the goal or the planner when missing prerequisite tasks must not be added to the
+    /// plan; a plan lacking them is rejected instead. Inherited by plan tasks.
+    #[serde(default)]
+    pub skip_prerequisites: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -18,9 +36,26 @@ pub struct AgentTask{
     pub payload: String,
     pub context: AgentTaskContext,
     pub status: TaskStatus,
+    /// Ids of tasks in the same graph that must succeed before this one runs
+    #[serde(default)]
+    pub depends_on: Vec<String>,
 }
 
-#[derive(Debug, Clone,PartialEq, Eq, Hash)]
+impl AgentTask {
+    /// The next attempt of this task: a new id linked back through `retry_of`
+    pub fn next_attempt(&self) -> AgentTask {
+        let mut retry = self.clone();
+        retry.task_id = uuid::Uuid::new_v4().to_string();
+        retry.context.retry_of = Some(self.task_id.clone());
+        retry.context.attempt = self.context.attempt + 1;
+        retry.status = TaskStatus::Retried {
+            previous_id: self.task_id.clone(),
+        };
+        retry
+    }
+}
+
+#[derive(Debug, Clone,PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Capability{
     CodeGen,
     Planning,
@@ -44,13 +79,69 @@ pub enum Capability{
     Critique,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Capability {
+    pub const ALL: [Capability; 20] = [
+        Capability::CodeGen,
+        Capability::Planning,
+        Capability::Evaluation,
+        Capability::FileAccess,
+        Capability::GitOps,
+        Capability::Search,
+        Capability::Research,
+        Capability::Reasoning,
+        Capability::Clarification,
+        Capability::Greeting,
+        Capability::Requirements,
+        Capability::Architecture,
+        Capability::Testing,
+        Capability::Refactoring,
+        Capability::Documentation,
+        Capability::Scaffolding,
+        Capability::Deployment,
+        Capability::Security,
+        Capability::RepoAnalysis,
+        Capability::Critique,
+    ];
+}
+
+/// Parses task types such as "code_gen", "CodeGen" or "repo-analysis"
+impl std::str::FromStr for Capability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| *c != '_' && *c != '-' && !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+        Capability::ALL
+            .iter()
+            .find(|capability| format!("{:?}", capability).to_lowercase() == normalized)
+            .cloned()
+            .ok_or_else(|| format!("Unknown capability: {}", s))
+    }
+}
+
+/// Ordered from least to most side-effecting
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum  ExecutionMode{
     Simulate,
     DryRun,
     Execute,
 }
 
+impl ExecutionMode {
+    /// Effective mode for a task: a goal-level override if given, else the agent card's default.
+    /// An override may widen the mode; running in Execute is then held at the approval gate.
+    pub fn resolve(card_default: ExecutionMode, goal_override: Option<ExecutionMode>) -> ExecutionMode {
+        goal_override.unwrap_or(card_default)
+    }
+
+    pub fn applies_effects(&self) -> bool {
+        *self == ExecutionMode::Execute
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AgentCard{
     pub id: String,
@@ -59,24 +150,49 @@ pub struct AgentCard{
     pub input_schema: String, // Optional JSON schema
     pub output_schema: String, // Optional JSON schema
     pub default_execution: ExecutionMode,
+    /// Deadline for one task of this agent's capability; None means no limit
+    pub timeout_secs: Option<u64>,
+    /// Tie-breaker between agents with the same skill match; higher is preferred
+    #[serde(default)]
+    pub priority: i32,
+    /// Conditions a task must meet for this agent to be routed to
+    #[serde(default)]
+    pub preconditions: Vec<Precondition>,
+    /// Names of the registry tools this agent may call; every other tool is withheld
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+}
+
+/// Routing precondition declared on an `AgentCard`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Precondition {
+    /// A project must be open (the agent works on project files)
+    ProjectOpen,
+    /// The task payload must be a JSON object with this field
+    PayloadField(String),
+    /// The task's execution mode override must not be below this mode
+    ExecutionModeAtLeast(ExecutionMode),
 }
-/// Enables skill matching, dependency resolution, and graph traversal
+/// The capabilities one agent handles. Prerequisites between capabilities live in
+/// `skill_graph::CapabilityGraph`, which combines these per-agent skills for introspection.
 #[derive(Debug,Clone, Serialize, Deserialize)]
 pub struct SkillGraph {
-    /// NOTE: This will be refactored in Phase 4 to a HashMap-based graph for introspection,
-    /// subskill matching, and agent chaining. This simplified version keeps capability
-    /// matching clean while Winter operates in single-layer mode.
     pub root: Capability,
     pub subskills: Vec<Capability>,
 
 }
-#[derive(Debug,Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TaskStatus{
     Pending,
     Running,
     Succeeded,
     Failed { reason: String},
     Retried { previous_id: String},
+    /// Not run because a task it depends on did not succeed
+    Skipped { blocked_by: String },
+    /// Stopped by `cancel_task`/`cancel_goal` or because its parent was cancelled
+    Cancelled,
+    TimedOut { after_secs: u64 },
 }
 pub fn now_timestamp() -> u64 {
     SystemTime::now()
@@ -1,5 +1,5 @@
-use crate::agents::memory::task_memory::TaskMemory;
-use crate::agents::orchestrator::types::AgentTask;
+use crate::memory::task_memory::TaskMemory;
+use crate::orchestrator::types::AgentTask;
 
 pub fn retry_depth(task: &AgentTask, memory: &TaskMemory) -> usize{
     let mut depth = 0;
@@ -1,59 +1,340 @@
-use crate::memory::project_memory::ProjectMemory;
+use crate::llm::backend::{estimate_tokens, LlmBackend};
+use crate::llm::structured::{repair_reserve, with_schema_instructions};
+use crate::memory::project_memory::ProjectMemoryHandle;
+use crate::memory::retrieval;
 use crate::orchestrator::types::AgentTask;
 use anyhow::{Context, Result};
+use serde_json::Value;
 use std::fs;
+use std::io;
+
+/// Number of retrieved project chunks added to each prompt
+const RETRIEVED_CHUNKS: usize = 5;
+/// A quarter of the context window is kept free for the model's answer
+const RESPONSE_RESERVE_DIVISOR: usize = 4;
+const TRIM_MARKER: &str = "[... trimmed to fit the context window]";
+/// Exact recounts allowed per trimmed section before it is dropped
+const MAX_TRIM_ROUNDS: usize = 3;
 
 /// Responsible for assembling the final prompt to be sent to the LLM.
 pub struct PromptAssembler;
 
+/// The final prompt and what budgeting removed from it
+pub struct AssembledPrompt {
+    pub text: String,
+    /// Lines for the task trace: the budget used and every trimmed or dropped section
+    pub trace: Vec<String>,
+}
+
+/// One `### Title` block of the prompt
+struct Section {
+    title: &'static str,
+    /// Sections with a lower number get their share of the budget first
+    priority: u8,
+    /// Parts that can be dropped one by one, least relevant last
+    pieces: Vec<String>,
+}
+
+impl Section {
+    fn new(title: &'static str, priority: u8, pieces: Vec<String>) -> Self {
+        let pieces = pieces.into_iter().filter(|piece| !piece.trim().is_empty()).collect();
+        Self { title, priority, pieces }
+    }
+
+    fn render(&self) -> String {
+        if self.pieces.is_empty() {
+            return String::new();
+        }
+        format!("### {}\n{}\n\n", self.title, self.pieces.join("\n\n").trim())
+    }
+}
+
 impl PromptAssembler {
-    /// Assemble the full prompt from file + memory _ task.
-    pub fn assemble(agent_id: &str, task: &AgentTask, memory: &ProjectMemory) -> Result<String> {
-        // 1. Load static agent prompt
-        let prompt_path = format!("prompts/{}.txt", agent_id);
-        let static_prompt = fs::read_to_string(&prompt_path)
-            .with_context(|| format!("Failed to load static prompt: {}", prompt_path))?;
+    /// Assemble the full prompt from file + memory _ task, fitted to the model's context window.
+    pub async fn assemble(
+        agent_id: &str,
+        task: &AgentTask,
+        memory: &ProjectMemoryHandle,
+        llm: &dyn LlmBackend,
+    ) -> Result<AssembledPrompt> {
+        Self::assemble_reserving(agent_id, Instructions::StaticFile, task, memory, llm, 0).await
+    }
+
+    /// Like `assemble`, for an answer that must validate against `schema`. The schema instructions
+    /// are appended, and their tokens plus room for one repair round are kept out of the budget.
+    pub async fn assemble_structured(
+        agent_id: &str,
+        task: &AgentTask,
+        memory: &ProjectMemoryHandle,
+        llm: &dyn LlmBackend,
+        schema: &Value,
+    ) -> Result<AssembledPrompt> {
+        let reserved = schema_reserve(schema, llm).await;
+        let mut assembled =
+            Self::assemble_reserving(agent_id, Instructions::StaticFile, task, memory, llm, reserved).await?;
+        assembled.text = with_schema_instructions(&assembled.text, schema);
+        Ok(assembled)
+    }
+
+    /// For manifest agents: the template, already rendered with the task, takes the place of
+    /// the static prompt and the task input. With a `schema`, the output rules are appended
+    /// as in `assemble_structured`.
+    pub async fn assemble_template(
+        agent_id: &str,
+        rendered: &str,
+        task: &AgentTask,
+        memory: &ProjectMemoryHandle,
+        llm: &dyn LlmBackend,
+        schema: Option<&Value>,
+    ) -> Result<AssembledPrompt> {
+        let reserved = match schema {
+            Some(schema) => schema_reserve(schema, llm).await,
+            None => 0,
+        };
+        let mut assembled =
+            Self::assemble_reserving(agent_id, Instructions::Rendered(rendered), task, memory, llm, reserved).await?;
+        if let Some(schema) = schema {
+            assembled.text = with_schema_instructions(&assembled.text, schema);
+        }
+        Ok(assembled)
+    }
+
+    /// Assembles the prompt within the budget less `reserved` tokens added after assembly
+    async fn assemble_reserving(
+        agent_id: &str,
+        instructions: Instructions<'_>,
+        task: &AgentTask,
+        memory: &ProjectMemoryHandle,
+        llm: &dyn LlmBackend,
+        reserved: usize,
+    ) -> Result<AssembledPrompt> {
+        // 1. Load static agent prompt, unless a rendered template replaces it and the task input
+        let (static_prompt, task_input) = match instructions {
+            Instructions::StaticFile => {
+                let prompt_path = format!("prompts/{}.txt", agent_id);
+                let static_prompt = fs::read_to_string(&prompt_path)
+                    .with_context(|| format!("Failed to load static prompt: {}", prompt_path))?;
+                (static_prompt, format!("{:#}", task.payload))
+            }
+            Instructions::Rendered(rendered) => (rendered.to_string(), String::new()),
+        };
 
         // 2. Pull relevant memory context
-        let context = Self::gather_context(agent_id, task, memory)?;
+        let context = gather_context(agent_id, task, memory, llm).await?;
 
-        // 3. Add task payload
-        let task_input = format!("{:#}", task.payload);
+        // 3. Retrieve the project chunks most relevant to the task
+        let retrieved = retrieve_context(task, memory).await;
 
-        // 4. Assemble the final prompt
-        let final_prompt = format!(
-            "### Agent Role Prompt\n{}\n### Context\n{}\n\n### Task Input\n{}\n",
-            static_prompt.trim(),
-            context.trim(),
-            task_input.trim(),
-        );
+        // 4. Fit the sections into the context window and assemble the final prompt
+        let mut sections = vec![
+            Section::new("Agent Role Prompt", 0, vec![static_prompt]),
+            Section::new("Context", 2, vec![context]),
+            Section::new("Relevant Project Context", 3, retrieved),
+            Section::new("Task Input", 1, vec![task_input]),
+        ];
+        let trace = fit_to_budget(&mut sections, llm, reserved).await;
+        let final_prompt = sections.iter().map(Section::render).collect::<String>();
 
-        Ok(final_prompt)
+        Ok(AssembledPrompt {
+            text: format!("{}\n", final_prompt.trim_end()),
+            trace,
+        })
     }
 }
 
-/// Gather memory based on agent type.
-fn gather_context(agent_id: &str, _task: &AgentTask, memory: &ProjectMemory) -> Result<String> {
+/// Where an agent's instructions come from
+enum Instructions<'a> {
+    /// `prompts/<agent_id>.txt`, followed by the task payload
+    StaticFile,
+    /// A manifest template rendered with the task's payload
+    Rendered(&'a str),
+}
+
+/// Tokens of the schema instructions plus the longest repair round
+async fn schema_reserve(schema: &Value, llm: &dyn LlmBackend) -> usize {
+    count_tokens(llm, &with_schema_instructions("", schema)).await + count_tokens(llm, &repair_reserve()).await
+}
+
+async fn count_tokens(llm: &dyn LlmBackend, text: &str) -> usize {
+    if text.is_empty() {
+        return 0;
+    }
+    llm.count_tokens(text).await.unwrap_or_else(|e| {
+        eprintln!("[warn] Token counting failed, estimating: {}", e);
+        estimate_tokens(text)
+    })
+}
+
+/// Gives each section, in priority order, as much of the budget as it needs. A section that
+/// does not fit loses its trailing pieces, then the tail of its last piece. Returns the trace.
+/// `reserved` tokens of the budget are kept for text appended after assembly.
+/// Every section is counted exactly once; trimming works on estimates scaled to that count,
+/// and only the trimmed result is counted exactly again.
+async fn fit_to_budget(sections: &mut [Section], llm: &dyn LlmBackend, reserved: usize) -> Vec<String> {
+    let window = llm.context_window();
+    let budget = (window - window / RESPONSE_RESERVE_DIVISOR).saturating_sub(reserved);
+    let mut remaining = budget;
+    let mut trace = vec![];
+
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by_key(|&i| sections[i].priority);
+    for i in order {
+        let section = &mut sections[i];
+        let needed = count_tokens(llm, &section.render()).await;
+        if needed <= remaining {
+            remaining -= needed;
+            continue;
+        }
+
+        let pieces = section.pieces.clone();
+        let scale = needed as f64 / estimate_tokens(&section.render()).max(1) as f64;
+        let mut target = remaining;
+        let mut dropped = 0;
+        let mut kept = 0;
+        for _ in 0..MAX_TRIM_ROUNDS {
+            section.pieces = pieces.clone();
+            dropped = trim_section(section, target, scale);
+            kept = count_tokens(llm, &section.render()).await;
+            if kept <= remaining {
+                break;
+            }
+            // The estimate was optimistic for this text; aim proportionally lower
+            target = target * remaining / kept;
+        }
+        if kept > remaining {
+            section.pieces.clear();
+            kept = 0;
+        }
+
+        remaining -= kept;
+        trace.push(match (kept, dropped) {
+            (0, _) => format!("prompt budget: dropped {} ({} tokens)", section.title, needed),
+            (_, 0) => format!("prompt budget: trimmed {} from {} to {} tokens", section.title, needed, kept),
+            _ => format!(
+                "prompt budget: trimmed {} from {} to {} tokens, dropping {} of {} parts",
+                section.title,
+                needed,
+                kept,
+                dropped,
+                pieces.len()
+            ),
+        });
+    }
+
+    let summary = format!(
+        "prompt budget: {} of {} tokens used (context window {}, {} reserved)",
+        budget - remaining,
+        budget,
+        window,
+        reserved
+    );
+    if !trace.is_empty() {
+        println!("[PromptAssembler] {}; {}", summary, trace.join("; "));
+    }
+    trace.insert(0, summary);
+    trace
+}
+
+fn scaled_estimate(text: &str, scale: f64) -> usize {
+    (estimate_tokens(text) as f64 * scale).ceil() as usize
+}
+
+/// Drops trailing pieces, then the tail of the last one, until the section's scaled estimate
+/// fits `budget`. Returns the number of pieces dropped.
+fn trim_section(section: &mut Section, budget: usize, scale: f64) -> usize {
+    let pieces = section.pieces.len();
+    while section.pieces.len() > 1 && scaled_estimate(&section.render(), scale) > budget {
+        section.pieces.pop();
+    }
+    let dropped = pieces - section.pieces.len();
+    if scaled_estimate(&section.render(), scale) > budget {
+        truncate_last_piece(section, budget, scale);
+    }
+    dropped
+}
+
+/// Keeps the longest run of leading lines of the last piece that fits, or empties the section
+fn truncate_last_piece(section: &mut Section, budget: usize, scale: f64) {
+    let Some(piece) = section.pieces.pop() else { return };
+    let lines: Vec<&str> = piece.lines().collect();
+
+    // Binary search for the number of lines to keep
+    let (mut low, mut high) = (0, lines.len());
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        section.pieces.push(format!("{}\n{}", lines[..mid].join("\n"), TRIM_MARKER));
+        let fits = scaled_estimate(&section.render(), scale) <= budget;
+        section.pieces.pop();
+        if fits {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    if low > 0 {
+        section.pieces.push(format!("{}\n{}", lines[..low].join("\n"), TRIM_MARKER));
+    } else {
+        section.pieces.clear();
+    }
+}
+
+/// Top chunks of the open project for the task payload, most relevant first; empty if no project is open
+async fn retrieve_context(task: &AgentTask, memory: &ProjectMemoryHandle) -> Vec<String> {
+    let Some(project_root) = memory.root().and_then(|root| root.parent().map(|p| p.to_path_buf())) else {
+        return vec![];
+    };
+    match retrieval::retrieve(&project_root, &task.payload, RETRIEVED_CHUNKS).await {
+        Ok(chunks) => chunks
+            .iter()
+            .map(|hit| {
+                format!(
+                    "// {} (lines {}-{})\n{}",
+                    hit.chunk.path, hit.chunk.start_line, hit.chunk.end_line, hit.chunk.text
+                )
+            })
+            .collect(),
+        Err(e) => {
+            eprintln!("[warn] Retrieval failed for task {}: {}", task.task_id, e);
+            vec![]
+        }
+    }
+}
+
+/// The cached or freshly made summary of a memory file, or an empty string if the file does not
+/// exist yet or could not be summarized
+async fn summary_or_empty(memory: &ProjectMemoryHandle, path: &str, scope: &str, llm: &dyn LlmBackend) -> String {
+    match memory.summarize(path, scope, llm).await {
+        Ok(summary) => summary,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            eprintln!("[warn] Failed to summarize {} for {}: {}", path, scope, e);
+            String::new()
+        }
+    }
+}
+
+/// Gather memory based on agent type. Memory files live in the project's `.winter/` directory.
+async fn gather_context(
+    agent_id: &str,
+    _task: &AgentTask,
+    memory: &ProjectMemoryHandle,
+    llm: &dyn LlmBackend,
+) -> Result<String> {
     match agent_id {
         "requirements_agent" => {
-            let goals = memory.read("project_memory/goals.md").unwrap_or_default();
-            let rules = memory.read("project/design_rules.md").unwrap_or_default();
+            let goals = memory.read("goals.md").unwrap_or_default();
+            let rules = memory.read("design_rules.md").unwrap_or_default();
             Ok(format!("{}\n\n{}", goals, rules))
         }
         "architecture_agent" => {
-            let requirements = memory
-                .read("project_memory/requirements.json")
-                .unwrap_or_default();
-            let rules = memory
-                .read("project_memory/design_rules.md")
-                .unwrap_or_default();
+            let requirements = memory.requirements().unwrap_or_default();
+            let rules = memory.read("design_rules.md").unwrap_or_default();
             Ok(format!("{}\n\n{}", requirements, rules))
         }
         "codegen_agent" => {
-            let architecture = memory.summarize("project_memory/architecture.yaml", "module")?;
-            let rules = memory
-                .read("project_memory/design_rules.md")
-                .unwrap_or_default();
+            let architecture = summary_or_empty(memory, "architecture.json", "module", llm).await;
+            let rules = memory.read("design_rules.md").unwrap_or_default();
             Ok(format!("{}\n\n{}", architecture, rules))
         }
         "critique_agent" => {
@@ -61,17 +342,15 @@ fn gather_context(agent_id: &str, _task: &AgentTask, memory: &ProjectMemory) ->
             Ok("Review the following output carefully.".to_string())
         }
         "refactor_agent" => {
-            let rules = memory
-                .read("project_memory/design_rules.md")
-                .unwrap_or_default();
+            let rules = memory.read("design_rules.md").unwrap_or_default();
             Ok(rules)
         }
         "test_agent" => {
-            let requirements = memory.summarize("project_memory/requirements.json", "testing")?;
+            let requirements = summary_or_empty(memory, "requirements.json", "testing", llm).await;
             Ok(requirements)
         }
         "deployment_agent" => {
-            let architecture = memory.summarize("project_memory/architecture.yaml", "infra")?;
+            let architecture = summary_or_empty(memory, "architecture.json", "infra", llm).await;
             Ok(architecture)
         }
         _ => {
@@ -79,3 +358,85 @@ fn gather_context(agent_id: &str, _task: &AgentTask, memory: &ProjectMemory) ->
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::backend::{CompletionOptions, LlmError, TokenStream};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts three characters per token, so the four-character estimate runs low
+    struct CountingBackend {
+        window: usize,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LlmBackend for CountingBackend {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        async fn complete(&self, _prompt: &str, _options: &CompletionOptions) -> Result<String, LlmError> {
+            Err(LlmError::Unavailable("test backend".into()))
+        }
+
+        async fn stream(&self, _prompt: &str, _options: &CompletionOptions) -> Result<TokenStream, LlmError> {
+            Err(LlmError::Unavailable("test backend".into()))
+        }
+
+        fn cancel(&self) {}
+
+        fn context_window(&self) -> usize {
+            self.window
+        }
+
+        async fn count_tokens(&self, text: &str) -> Result<usize, LlmError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(text.chars().count().div_ceil(3))
+        }
+    }
+
+    #[tokio::test]
+    async fn trims_to_the_exact_count_with_few_tokenizer_calls() {
+        let backend = CountingBackend {
+            window: 400,
+            calls: AtomicUsize::new(0),
+        };
+        let long: Vec<String> = (0..5)
+            .map(|piece| (0..40).map(|line| format!("piece {} line {}\n", piece, line)).collect())
+            .collect();
+        let mut sections = vec![
+            Section::new("Agent Role Prompt", 0, vec!["You write code.".into()]),
+            Section::new("Task Input", 1, vec!["Add a flag".into()]),
+            Section::new("Relevant Project Context", 3, long),
+        ];
+
+        let trace = fit_to_budget(&mut sections, &backend, 0).await;
+
+        let used: usize = sections
+            .iter()
+            .map(|section| section.render().chars().count().div_ceil(3))
+            .sum();
+        assert!(used <= 300, "{} tokens used", used);
+        assert!(!sections[2].pieces.is_empty());
+        assert!(trace.iter().any(|line| line.contains("trimmed Relevant Project Context")));
+        assert!(backend.calls.load(Ordering::SeqCst) <= sections.len() + MAX_TRIM_ROUNDS);
+    }
+
+    #[tokio::test]
+    async fn reserved_tokens_shrink_the_budget() {
+        let backend = CountingBackend {
+            window: 400,
+            calls: AtomicUsize::new(0),
+        };
+        let text: String = (0..60).map(|line| format!("context line {}\n", line)).collect();
+        let mut sections = vec![Section::new("Relevant Project Context", 3, vec![text])];
+
+        fit_to_budget(&mut sections, &backend, 200).await;
+
+        let used = sections[0].render().chars().count().div_ceil(3);
+        assert!(used <= 100, "{} tokens used", used);
+    }
+}
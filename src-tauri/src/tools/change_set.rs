@@ -0,0 +1,423 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::tools::path_jail::PathJail;
+
+const CHANGES_DIR: &str = "WinterData/changes";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Create,
+    Modify,
+    Delete,
+}
+
+/// One file effect a tool applied, or would have applied outside Execute mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    /// New file content; None for deletes
+    pub content: Option<String>,
+    /// File content before the change; None if the file did not exist
+    #[serde(default)]
+    pub before: Option<String>,
+}
+
+impl FileChange {
+    /// Captures the current content of `path` as the before-image
+    pub fn capture(path: &str, kind: ChangeKind, content: Option<String>) -> Self {
+        Self {
+            path: path.to_string(),
+            kind,
+            content,
+            before: fs::read_to_string(path).ok(),
+        }
+    }
+}
+
+/// Shared, append-only list of the file effects produced while running one task.
+/// Tools only stage changes here; in Execute mode the orchestrator applies the whole set
+/// when the task succeeds, otherwise it is the reviewable change set returned to the user.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSet(pub Arc<Mutex<Vec<FileChange>>>);
+
+impl ChangeSet {
+    pub fn new() -> Self {
+        ChangeSet(Arc::new(Mutex::new(Vec::new())))
+    }
+
+    pub fn record(&self, change: FileChange) {
+        if let Ok(mut changes) = self.0.lock() {
+            changes.push(change);
+        }
+    }
+
+    pub fn changes(&self) -> Vec<FileChange> {
+        self.0.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().map(|c| c.is_empty()).unwrap_or(true)
+    }
+
+    /// Content of `path` as seen by the task: the latest staged change, if any.
+    /// `Some(None)` means the task staged a delete.
+    pub fn staged_content(&self, path: &str) -> Option<Option<String>> {
+        self.0.lock().ok().and_then(|changes| {
+            changes
+                .iter()
+                .rev()
+                .find(|c| c.path == path)
+                .map(|c| c.content.clone())
+        })
+    }
+
+    /// Applies every staged change in order. Files must still hold the content the task saw
+    /// when it staged its first change to them; each before-image is then re-read right before
+    /// its change is written, so later changes to the same file revert to the earlier ones.
+    /// If one fails, the ones already applied are rolled back, so the project is never left half-modified.
+    /// Every path is resolved through the project jail again first, so a symlink swapped in
+    /// after staging cannot redirect a write.
+    pub fn apply(&self, jail: Option<&PathJail>) -> Result<Vec<FileChange>, String> {
+        let mut changes = self.changes();
+
+        let jail = jail.ok_or("No project is open; file access is disabled")?;
+        for change in &changes {
+            let resolved = jail.resolve(&change.path).map_err(|e| e.to_string())?;
+            if resolved != Path::new(&change.path) {
+                return Err(format!(
+                    "{} now resolves to {}; refusing to write through it",
+                    change.path,
+                    resolved.display()
+                ));
+            }
+        }
+
+        let mut modified = vec![];
+        for (i, change) in changes.iter().enumerate() {
+            let is_first = !changes[..i].iter().any(|c| c.path == change.path);
+            if is_first && fs::read_to_string(&change.path).ok() != change.before {
+                modified.push(change.path.clone());
+            }
+        }
+        if !modified.is_empty() {
+            return Err(format!("Files changed since the task read them: {}", modified.join(", ")));
+        }
+
+        for i in 0..changes.len() {
+            changes[i].before = fs::read_to_string(&changes[i].path).ok();
+            if let Err(e) = apply_change(&changes[i]) {
+                for applied in changes[..i].iter().rev() {
+                    if let Err(rollback) = restore_before_image(applied) {
+                        eprintln!("[warn] Rollback of {} failed: {}", applied.path, rollback);
+                    }
+                }
+                return Err(format!("Applying change to {} failed: {}", changes[i].path, e));
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+/// A change set that was applied to the project, kept on disk so it can be reverted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedChangeSet {
+    pub task_id: String,
+    pub goal_id: Option<String>,
+    pub changes: Vec<FileChange>,
+    /// Milliseconds since the Unix epoch, so change sets of one goal sort in the order applied
+    pub applied_at: u64,
+    pub reverted: bool,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn changes_dir() -> PathBuf {
+    dirs::home_dir().expect("No home dir").join(CHANGES_DIR)
+}
+
+/// Task and goal ids become file names, so they may only hold letters, digits, `-` and `_`
+fn check_id(kind: &str, id: &str) -> std::io::Result<()> {
+    if !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid {} id: {:?}", kind, id),
+        ))
+    }
+}
+
+fn write_atomically(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("winter-tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)
+}
+
+fn apply_change(change: &FileChange) -> std::io::Result<()> {
+    let path = Path::new(&change.path);
+    match (&change.kind, &change.content) {
+        (ChangeKind::Delete, _) => fs::remove_file(path),
+        (_, Some(content)) => write_atomically(path, content),
+        (_, None) => Ok(()),
+    }
+}
+
+fn restore_before_image(change: &FileChange) -> std::io::Result<()> {
+    let path = Path::new(&change.path);
+    match &change.before {
+        Some(before) => write_atomically(path, before),
+        None if path.exists() => fs::remove_file(path),
+        None => Ok(()),
+    }
+}
+
+pub fn save_applied_change_set(record: &AppliedChangeSet) -> std::io::Result<()> {
+    check_id("task", &record.task_id)?;
+    if let Some(goal_id) = &record.goal_id {
+        check_id("goal", goal_id)?;
+    }
+    let folder = changes_dir();
+    fs::create_dir_all(&folder)?;
+    let data = serde_json::to_string_pretty(record)?;
+    fs::write(folder.join(format!("{}.json", record.task_id)), data)
+}
+
+pub fn load_applied_change_set(task_id: &str) -> std::io::Result<AppliedChangeSet> {
+    check_id("task", task_id)?;
+    let data = fs::read_to_string(changes_dir().join(format!("{}.json", task_id)))?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+/// All applied change sets of a goal, newest first
+pub fn load_goal_change_sets(goal_id: &str) -> std::io::Result<Vec<AppliedChangeSet>> {
+    check_id("goal", goal_id)?;
+    let folder = changes_dir();
+    if !folder.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut records: Vec<AppliedChangeSet> = fs::read_dir(folder)?
+        .filter_map(Result::ok)
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|data| serde_json::from_str::<AppliedChangeSet>(&data).ok())
+        .filter(|record| record.goal_id.as_deref() == Some(goal_id))
+        .collect();
+    records.sort_by_key(|record| std::cmp::Reverse(record.applied_at));
+    Ok(records)
+}
+
+pub fn record_applied(task_id: &str, goal_id: Option<String>, changes: Vec<FileChange>) -> std::io::Result<()> {
+    save_applied_change_set(&AppliedChangeSet {
+        task_id: task_id.to_string(),
+        goal_id,
+        changes,
+        applied_at: now_millis(),
+        reverted: false,
+    })
+}
+
+/// Restores the before-images of an applied change set, newest change first.
+/// Files edited since the agent wrote them are left alone unless `force` is set.
+/// If a restore fails, the files already restored get the agent's changes back, so the
+/// change set is either reverted as a whole or still applied as recorded.
+pub fn revert_change_set(record: &mut AppliedChangeSet, force: bool) -> Result<Vec<String>, String> {
+    if record.reverted {
+        return Err(format!("Changes of task {} were already reverted", record.task_id));
+    }
+
+    if !force {
+        // Only the last change per path has to match what is on disk
+        let mut conflicts: Vec<String> = vec![];
+        for (i, change) in record.changes.iter().enumerate() {
+            let is_last = !record.changes[i + 1..].iter().any(|c| c.path == change.path);
+            if is_last && fs::read_to_string(&change.path).ok() != change.content {
+                conflicts.push(change.path.clone());
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(format!(
+                "Files changed since task {} wrote them: {}",
+                record.task_id,
+                conflicts.join(", ")
+            ));
+        }
+    }
+
+    let mut reverted = vec![];
+    for (i, change) in record.changes.iter().enumerate().rev() {
+        if let Err(e) = restore_before_image(change) {
+            for restored in &record.changes[i + 1..] {
+                if let Err(rollback) = apply_change(restored) {
+                    eprintln!("[warn] Re-applying {} failed: {}", restored.path, rollback);
+                }
+            }
+            return Err(format!("Reverting {} failed: {}", change.path, e));
+        }
+        reverted.push(change.path.clone());
+    }
+
+    record.reverted = true;
+    save_applied_change_set(record).map_err(|e| e.to_string())?;
+    Ok(reverted)
+}
+
+#[tauri::command]
+pub fn revert_task_changes(task_id: String, force: Option<bool>) -> Result<Vec<String>, String> {
+    let mut record = load_applied_change_set(&task_id)
+        .map_err(|e| format!("No applied changes for task {}: {}", task_id, e))?;
+    revert_change_set(&mut record, force.unwrap_or(false))
+}
+
+/// Reverts every applied change set of the goal, newest first. A change set that cannot be
+/// reverted does not stop the others; all failures are reported together.
+#[tauri::command]
+pub fn revert_goal_changes(goal_id: String, force: Option<bool>) -> Result<Vec<String>, String> {
+    let records = load_goal_change_sets(&goal_id).map_err(|e| e.to_string())?;
+    let mut reverted = vec![];
+    let mut failures = vec![];
+
+    for mut record in records.into_iter().filter(|r| !r.reverted) {
+        match revert_change_set(&mut record, force.unwrap_or(false)) {
+            Ok(paths) => reverted.extend(paths),
+            Err(e) => failures.push(e),
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(reverted)
+    } else {
+        Err(format!(
+            "Reverted {} file(s), but {} change set(s) failed: {}",
+            reverted.len(),
+            failures.len(),
+            failures.join("; ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("winter-change-set-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn apply_refuses_files_edited_after_staging() {
+        let dir = temp_dir();
+        let path = dir.join("a.txt");
+        fs::write(&path, "original").unwrap();
+        let path = path.to_str().unwrap();
+
+        let changes = ChangeSet::new();
+        changes.record(FileChange::capture(path, ChangeKind::Modify, Some("agent".into())));
+        fs::write(path, "user edit").unwrap();
+
+        assert!(changes.apply(Some(&PathJail::new(&dir).unwrap())).is_err());
+        assert_eq!(fs::read_to_string(path).unwrap(), "user edit");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn apply_refuses_a_symlink_swapped_in_after_staging() {
+        let dir = temp_dir();
+        let outside = temp_dir();
+        let path = dir.join("out.txt");
+        let path = path.to_str().unwrap();
+
+        let changes = ChangeSet::new();
+        changes.record(FileChange::capture(path, ChangeKind::Create, Some("agent".into())));
+        std::os::unix::fs::symlink(outside.join("victim.txt"), path).unwrap();
+
+        assert!(changes.apply(Some(&PathJail::new(&dir).unwrap())).is_err());
+        assert!(!outside.join("victim.txt").exists());
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_dir_all(outside).unwrap();
+    }
+
+    #[test]
+    fn repeated_changes_revert_to_the_previous_change() {
+        let dir = temp_dir();
+        let path = dir.join("a.txt");
+        fs::write(&path, "v1").unwrap();
+        let path = path.to_str().unwrap();
+
+        let changes = ChangeSet::new();
+        changes.record(FileChange::capture(path, ChangeKind::Modify, Some("v2".into())));
+        changes.record(FileChange::capture(path, ChangeKind::Modify, Some("v3".into())));
+        let applied = changes.apply(Some(&PathJail::new(&dir).unwrap())).unwrap();
+
+        assert_eq!(fs::read_to_string(path).unwrap(), "v3");
+        assert_eq!(applied[0].before.as_deref(), Some("v1"));
+        assert_eq!(applied[1].before.as_deref(), Some("v2"));
+
+        for change in applied.iter().rev() {
+            restore_before_image(change).unwrap();
+        }
+        assert_eq!(fs::read_to_string(path).unwrap(), "v1");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_revert_puts_back_the_files_already_restored() {
+        let dir = temp_dir();
+        let file = dir.join("a.txt");
+        fs::write(&file, "v2").unwrap();
+        // Created by the task as a file, replaced by a directory since, so it cannot be removed
+        let blocked = dir.join("b");
+        fs::create_dir_all(blocked.join("inner")).unwrap();
+
+        let mut record = AppliedChangeSet {
+            task_id: "task".into(),
+            goal_id: None,
+            changes: vec![
+                FileChange {
+                    path: blocked.to_str().unwrap().into(),
+                    kind: ChangeKind::Create,
+                    content: Some("new".into()),
+                    before: None,
+                },
+                FileChange {
+                    path: file.to_str().unwrap().into(),
+                    kind: ChangeKind::Modify,
+                    content: Some("v2".into()),
+                    before: Some("v1".into()),
+                },
+            ],
+            applied_at: now_millis(),
+            reverted: false,
+        };
+
+        assert!(revert_change_set(&mut record, true).is_err());
+        assert!(!record.reverted);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v2");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ids_with_path_characters_are_rejected() {
+        assert!(check_id("task", "6f1c2a9e-0b7d-4c1e-9f3a-2d5b8e7c4a10").is_ok());
+        assert!(check_id("task", "plan_step-2").is_ok());
+        for id in ["", "..", "../escape", "a/b", "a\\b", "a.json"] {
+            assert!(check_id("task", id).is_err(), "{:?} should be rejected", id);
+        }
+    }
+}
@@ -1,18 +1,20 @@
+use async_trait::async_trait;
 use serde_json::{json, Value};
 use crate::orchestrator::protocol::{ToolReturn, ToolStatus};
-use crate::tools::tool::{Tool};
+use crate::tools::tool::{Tool, ToolContext};
 
 pub struct EchoTool;
 
+#[async_trait]
 impl Tool for EchoTool{
     fn name(&self) -> &'static str{ "echo" }
     fn description(&self) -> &'static str { "Repeats whatever input is given"}
 
-    async fn run(&self, input: Value) -> ToolReturn {
-        ToolReturn{
+    async fn run(&self, input: Value, _ctx: &ToolContext) -> Result<ToolReturn, String> {
+        Ok(ToolReturn{
             result: json!({"echoded": input}),
             status: ToolStatus::Success,
             trace: Some(vec!["EchoTool::run".into()]),
-        }
+        })
     }
 }
\ No newline at end of file
@@ -4,7 +4,8 @@ use serde_json::Value;
 use std::fs;
 use std::path::Path;
 use crate::orchestrator::protocol::{ToolReturn, ToolStatus};
-use crate::agents::tools::tool::Tool;
+use crate::tools::change_set::{ChangeKind, FileChange};
+use crate::tools::tool::{Tool, ToolContext};
 
 pub struct FileTool;
 
@@ -18,13 +19,21 @@ impl Tool for FileTool{
         "Reads from and writes to the file system"
     }
 
-    async fn run(&self, input: Value) -> Result<ToolReturn, String> {
+    async fn run(&self, input: Value, ctx: &ToolContext) -> Result<ToolReturn, String> {
+        ctx.ensure_active()?;
         let action = input["action"].as_str().unwrap_or("read");
-        let path = input["path"].as_str().ok_or("Missing path")?;
+        let requested = input["path"].as_str().ok_or("Missing path")?;
+        let resolved = ctx.resolve_path(requested)?;
+        let path = resolved.to_str().ok_or("Path is not valid UTF-8")?;
 
         match action {
             "read" => {
-                let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+                // A task sees its own staged writes before they are applied
+                let content = match ctx.changes.staged_content(path) {
+                    Some(Some(staged)) => staged,
+                    Some(None) => return Err(format!("File was deleted by this task: {}", path)),
+                    None => fs::read_to_string(path).map_err(|e| e.to_string())?,
+                };
                 Ok(ToolReturn{
                     result: json!({"content": content}),
                     status: ToolStatus::Success,
@@ -33,14 +42,46 @@ impl Tool for FileTool{
             },
             "write" => {
                 let content = input["content"].as_str().ok_or("Missing content")?;
-                fs::write(path, content).map_err(|e| e.to_string())?;
+                let exists = match ctx.changes.staged_content(path) {
+                    Some(staged) => staged.is_some(),
+                    None => Path::new(path).exists(),
+                };
+                let kind = if exists { ChangeKind::Modify } else { ChangeKind::Create };
+                ctx.changes.record(FileChange::capture(path, kind, Some(content.to_string())));
+
+                if !ctx.mode.applies_effects() {
+                    return Ok(recorded(path, ctx));
+                }
+
+                Ok(ToolReturn{
+                    result: json!({"message": "File write staged"}),
+                    status: ToolStatus::Success,
+                    trace: Some(vec![format!("Staged write to file: {}", path)]),
+                })
+            },
+            "delete" => {
+                ctx.changes.record(FileChange::capture(path, ChangeKind::Delete, None));
+
+                if !ctx.mode.applies_effects() {
+                    return Ok(recorded(path, ctx));
+                }
+
                 Ok(ToolReturn{
-                    result: json!({"message": "File written successfully"}),
+                    result: json!({"message": "File delete staged"}),
                     status: ToolStatus::Success,
-                    trace: Some(vec![format!("Wrote to file: {}", path)]),
+                    trace: Some(vec![format!("Staged delete of file: {}", path)]),
                 })
             },
             _ =>Err("Unsupported action".into())
         }
     }
-}
\ No newline at end of file
+}
+
+/// Result for an effect that was only recorded because the task is not in Execute mode
+fn recorded(path: &str, ctx: &ToolContext) -> ToolReturn {
+    ToolReturn{
+        result: json!({"message": "Change recorded, not applied", "mode": ctx.mode}),
+        status: ToolStatus::Success,
+        trace: Some(vec![format!("{:?}: recorded change to {}", ctx.mode, path)]),
+    }
+}
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use serde_json::{json, Value};
 use crate::orchestrator::protocol::{ToolReturn, ToolStatus};
-use crate::tools::tool::Tool;
+use crate::tools::tool::{Tool, ToolContext};
 
 pub struct LLMPlannerTool;
 
@@ -15,7 +15,7 @@ impl Tool for LLMPlannerTool {
         "Generates a multi-step plan based on a goal using a language model"
     }
 
-    async fn run(&self, input: Value) -> Result<ToolReturn, String> {
+    async fn run(&self, input: Value, _ctx: &ToolContext) -> Result<ToolReturn, String> {
         // A stub for now, we will add an LLM call later
         println!("[LLMPlannerTool] Simulating plan for: {}", input);
 
@@ -1,9 +1,97 @@
+use crate::llm::backend::{CompletionOptions, LlmBackend, LlmError};
+use crate::llm::streaming::stream_completion;
+use crate::llm::structured::{generate_parsed, generate_structured, structured_options};
+use crate::orchestrator::events::EventSink;
 use crate::orchestrator::protocol::{ToolReturn, ToolStatus};
-use crate::tools::tool::Tool;
+use crate::tools::tool::{Tool, ToolContext};
 use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
+use std::sync::Arc;
 
-pub struct LLMTool;
+/// Sends prompts to whichever `LlmBackend` is active for the current WinterMode
+pub struct LLMTool {
+    backend: Arc<dyn LlmBackend>,
+}
+
+impl LLMTool {
+    pub fn new(backend: Arc<dyn LlmBackend>) -> Self {
+        Self { backend }
+    }
+
+    pub async fn query(&self, prompt: String) -> Result<String, LlmError> {
+        self.backend
+            .complete(&prompt, &CompletionOptions::default())
+            .await
+    }
+
+    /// Like `query`, but streams tokens to the frontend tagged with the task and agent
+    pub async fn query_streaming(
+        &self,
+        prompt: String,
+        task_id: &str,
+        agent_id: &str,
+        events: &dyn EventSink,
+    ) -> Result<String, LlmError> {
+        stream_completion(
+            self.backend.as_ref(),
+            &prompt,
+            &CompletionOptions::default(),
+            task_id,
+            agent_id,
+            events,
+        )
+        .await
+    }
+
+    /// Streams a schema-constrained completion and parses it into `T`,
+    /// asking the model to correct itself when the JSON does not fit.
+    /// The prompt should come from `PromptAssembler::assemble_structured`.
+    pub async fn query_structured<T: DeserializeOwned + JsonSchema>(
+        &self,
+        prompt: String,
+        task_id: &str,
+        agent_id: &str,
+        events: &dyn EventSink,
+    ) -> Result<T, LlmError> {
+        let options = structured_options::<T>();
+        generate_structured::<T, _, _>(&prompt, |prompt| {
+            let options = &options;
+            async move {
+                stream_completion(self.backend.as_ref(), &prompt, options, task_id, agent_id, events).await
+            }
+        })
+        .await
+    }
+
+    /// Like `query_structured`, for a schema known only at runtime. `parse` checks each answer.
+    pub async fn query_schema<P>(
+        &self,
+        prompt: String,
+        schema: Value,
+        parse: P,
+        task_id: &str,
+        agent_id: &str,
+        events: &dyn EventSink,
+    ) -> Result<Value, LlmError>
+    where
+        P: Fn(&str) -> Result<Value, String>,
+    {
+        let options = CompletionOptions {
+            temperature: 0.2,
+            json_schema: Some(schema),
+            ..CompletionOptions::default()
+        };
+        generate_parsed(&prompt, parse, |prompt| {
+            let options = &options;
+            async move {
+                stream_completion(self.backend.as_ref(), &prompt, options, task_id, agent_id, events).await
+            }
+        })
+        .await
+    }
+}
 
 #[async_trait]
 impl Tool for LLMTool {
@@ -12,24 +100,17 @@ impl Tool for LLMTool {
     }
 
     fn description(&self) -> &'static str {
-        "Generates a multi-step plan based on a goal using a language model"
+        "Completes a prompt using the configured language model"
     }
 
-    async fn run(&self, input: Value) -> Result<ToolReturn, String> {
-        // A stub for now, we will add an LLM call later
-        println!("[LLMPlannerTool] Simulating plan for: {}", input);
-
-        let simulated_plan = json!({
-            "task_graph": [
-                { "task_type": "clarify", "payload": "Clarify goal"},
-                { "task_type": "design", "payload": "Propose architecture"}
-            ]
-        });
+    async fn run(&self, input: Value, _ctx: &ToolContext) -> Result<ToolReturn, String> {
+        let prompt = input["prompt"].as_str().ok_or("Missing prompt")?;
+        let text = self.query(prompt.to_string()).await.map_err(|e| e.to_string())?;
 
         Ok(ToolReturn {
-            result: simulated_plan,
+            result: json!({ "text": text }),
             status: ToolStatus::Success,
-            trace: Some(vec!["stub::plan_generated".into()]),
+            trace: Some(vec![format!("llm::{}", self.backend.name())]),
         })
     }
 }
@@ -3,5 +3,7 @@ pub mod tool;
 pub mod echo_tool;
 pub mod llm_planner;
 pub mod llm_tool;
-mod file_tool;
-
+pub mod file_tool;
+pub mod change_set;
+pub mod path_jail;
+pub mod patch_tool;
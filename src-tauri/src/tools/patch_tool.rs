@@ -0,0 +1,564 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use crate::orchestrator::protocol::{ToolReturn, ToolStatus};
+use crate::tools::change_set::{ChangeKind, FileChange};
+use crate::tools::tool::{Tool, ToolContext};
+
+/// How far (in lines) a hunk may have moved from the line numbers in its header
+const MAX_OFFSET: usize = 200;
+/// How many leading/trailing context lines may be ignored when a hunk does not match exactly
+const MAX_FUZZ: usize = 2;
+
+/// A `CodePatch`: one unified diff, possibly touching several files
+#[derive(Debug, Clone)]
+pub struct FilePatch {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub new_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+impl Hunk {
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Add(s) => Some(s.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+
+    /// Leading and trailing context line counts, which fuzz is allowed to drop
+    fn context_edges(&self) -> (usize, usize) {
+        let is_context = |l: &&HunkLine| matches!(l, HunkLine::Context(_));
+        let leading = self.lines.iter().take_while(is_context).count();
+        let trailing = self.lines.iter().rev().take_while(is_context).count();
+        (leading, trailing)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome")]
+pub enum HunkOutcome {
+    Applied,
+    /// Matched, but only after shifting and/or ignoring context lines
+    AppliedWithDrift { offset: isize, fuzz: usize },
+    Conflict { reason: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HunkReport {
+    pub file: String,
+    pub hunk: usize,
+    pub old_start: usize,
+    #[serde(flatten)]
+    pub outcome: HunkOutcome,
+}
+
+/// Parses a unified diff (`---`/`+++` headers followed by `@@` hunks).
+/// Text before the first header, such as `diff --git` lines, is ignored.
+pub fn parse_unified_diff(diff: &str) -> Result<Vec<FilePatch>, String> {
+    let mut patches: Vec<FilePatch> = vec![];
+    let mut lines = diff.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if let Some(old) = line.strip_prefix("--- ") {
+            let new = lines
+                .next()
+                .and_then(|l| l.strip_prefix("+++ "))
+                .ok_or_else(|| format!("Missing +++ header after: {}", line))?;
+            patches.push(FilePatch {
+                old_path: header_path(old, "a/"),
+                new_path: header_path(new, "b/"),
+                hunks: vec![],
+            });
+        } else if line.starts_with("@@") {
+            let patch = patches.last_mut().ok_or("Hunk before any file header")?;
+            let (old_start, old_count, new_start, new_count) = parse_hunk_header(line)?;
+            let mut hunk = Hunk { old_start, new_start, lines: vec![] };
+            let (mut old_seen, mut new_seen) = (0, 0);
+
+            while old_seen < old_count || new_seen < new_count {
+                let Some(body) = lines.next() else {
+                    return Err(format!("Hunk truncated: {}", line));
+                };
+                match body.chars().next() {
+                    Some('+') => {
+                        hunk.lines.push(HunkLine::Add(body[1..].to_string()));
+                        new_seen += 1;
+                    }
+                    Some('-') => {
+                        hunk.lines.push(HunkLine::Remove(body[1..].to_string()));
+                        old_seen += 1;
+                    }
+                    Some('\\') => {}
+                    // LLMs often drop the leading space of blank context lines
+                    Some(' ') | None => {
+                        hunk.lines.push(HunkLine::Context(body.get(1..).unwrap_or("").to_string()));
+                        old_seen += 1;
+                        new_seen += 1;
+                    }
+                    Some(_) => return Err(format!("Unexpected line in hunk: {}", body)),
+                }
+            }
+            while lines.peek().is_some_and(|l| l.starts_with('\\')) {
+                lines.next();
+            }
+            patch.hunks.push(hunk);
+        }
+    }
+
+    if patches.is_empty() {
+        return Err("No file headers found in patch".into());
+    }
+    Ok(patches)
+}
+
+fn header_path(header: &str, prefix: &str) -> Option<String> {
+    // Headers may carry a tab-separated timestamp
+    let path = header.split('\t').next().unwrap_or("").trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+fn parse_hunk_header(line: &str) -> Result<(usize, usize, usize, usize), String> {
+    let invalid = || format!("Invalid hunk header: {}", line);
+    let inner = line
+        .trim_start_matches('@')
+        .split("@@")
+        .next()
+        .ok_or_else(invalid)?
+        .trim();
+    let mut ranges = inner.split_whitespace();
+    let old = ranges.next().and_then(|r| r.strip_prefix('-')).ok_or_else(invalid)?;
+    let new = ranges.next().and_then(|r| r.strip_prefix('+')).ok_or_else(invalid)?;
+
+    let range = |r: &str| -> Result<(usize, usize), String> {
+        let mut parts = r.splitn(2, ',');
+        let start = parts.next().unwrap_or("").parse().map_err(|_| invalid())?;
+        let count = match parts.next() {
+            Some(c) => c.parse().map_err(|_| invalid())?,
+            None => 1,
+        };
+        Ok((start, count))
+    };
+    let (old_start, old_count) = range(old)?;
+    let (new_start, new_count) = range(new)?;
+    Ok((old_start, old_count, new_start, new_count))
+}
+
+/// Applies the hunks of one file to `original`, returning the patched text and a report per hunk.
+/// Hunks that cannot be placed are skipped and reported as conflicts.
+pub fn apply_hunks(file: &str, original: &str, hunks: &[Hunk]) -> (String, Vec<HunkReport>) {
+    let mut lines: Vec<String> = original.lines().map(String::from).collect();
+    let mut reports = vec![];
+    // Net line shift introduced by the hunks already applied
+    let mut shift: isize = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        // A pure insertion's header names the line it goes after; otherwise the first line it replaces
+        let first_line = if hunk.old_lines().is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (first_line as isize + shift).max(0) as usize;
+        let report = |outcome| HunkReport {
+            file: file.to_string(),
+            hunk: index + 1,
+            old_start: hunk.old_start,
+            outcome,
+        };
+
+        let Some((position, fuzz)) = locate_hunk(&lines, hunk, expected) else {
+            reports.push(report(HunkOutcome::Conflict {
+                reason: "Context does not match the file".into(),
+            }));
+            continue;
+        };
+
+        let (leading, trailing) = hunk.context_edges();
+        let (skip_front, skip_back) = (fuzz.min(leading), fuzz.min(trailing));
+        let old = hunk.old_lines();
+        let new = hunk.new_lines();
+        let old_core = &old[skip_front..old.len() - skip_back];
+        let new_core = &new[skip_front..new.len() - skip_back];
+
+        lines.splice(
+            position..position + old_core.len(),
+            new_core.iter().map(|s| s.to_string()),
+        );
+
+        let offset = position as isize - (expected + skip_front) as isize;
+        shift += new_core.len() as isize - old_core.len() as isize;
+        reports.push(report(if offset == 0 && fuzz == 0 {
+            HunkOutcome::Applied
+        } else {
+            HunkOutcome::AppliedWithDrift { offset, fuzz }
+        }));
+    }
+
+    let mut patched = lines.join("\n");
+    if !lines.is_empty() && (original.ends_with('\n') || original.is_empty()) {
+        patched.push('\n');
+    }
+    (patched, reports)
+}
+
+/// Finds where the hunk's old lines sit, searching outwards from `expected`.
+/// Returns the start line of the (possibly fuzz-trimmed) match and the fuzz used.
+fn locate_hunk(lines: &[String], hunk: &Hunk, expected: usize) -> Option<(usize, usize)> {
+    let old = hunk.old_lines();
+    let (leading, trailing) = hunk.context_edges();
+
+    for fuzz in 0..=MAX_FUZZ {
+        let (front, back) = (fuzz.min(leading), fuzz.min(trailing));
+        if fuzz > 0 && (front == 0 && back == 0 || front + back >= old.len()) {
+            break;
+        }
+        let core = &old[front..old.len() - back];
+        let target = expected + front;
+
+        for distance in 0..=MAX_OFFSET {
+            let candidates = [target.checked_add(distance), target.checked_sub(distance)];
+            for start in candidates.into_iter().flatten().take(if distance == 0 { 1 } else { 2 }) {
+                if matches_at(lines, core, start) {
+                    return Some((start, fuzz));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn matches_at(lines: &[String], expected: &[&str], start: usize) -> bool {
+    start + expected.len() <= lines.len()
+        && lines[start..start + expected.len()]
+            .iter()
+            .zip(expected)
+            .all(|(have, want)| have.trim_end() == want.trim_end())
+}
+
+/// Applies unified diffs produced by agents (the `CodePatch` output schema) inside the project.
+/// Results are staged in the task's change set like FileTool writes; `dry_run` only previews.
+pub struct PatchTool;
+
+#[async_trait]
+impl Tool for PatchTool {
+    fn name(&self) -> &'static str {
+        "PatchTool"
+    }
+
+    fn description(&self) -> &'static str {
+        "Applies a unified diff to project files, with offset and fuzz tolerance"
+    }
+
+    async fn run(&self, input: Value, ctx: &ToolContext) -> Result<ToolReturn, String> {
+        ctx.ensure_active()?;
+        let diff = input["patch"].as_str().ok_or("Missing patch")?;
+        let dry_run = input["dry_run"].as_bool().unwrap_or(false);
+        let patches = parse_unified_diff(diff)?;
+
+        let mut reports: Vec<HunkReport> = vec![];
+        let mut staged: Vec<FileChange> = vec![];
+        let mut preview = vec![];
+
+        for patch in &patches {
+            ctx.ensure_active()?;
+            // Read from the old path and write to the new one; they differ for a rename
+            let source = patch.old_path.as_ref().or(patch.new_path.as_ref()).ok_or("Patch has no file path")?;
+            let target = patch.new_path.as_ref().unwrap_or(source);
+            let source_path = ctx.resolve_path(source)?;
+            let target_path = ctx.resolve_path(target)?;
+            let source_path = source_path.to_str().ok_or("Path is not valid UTF-8")?;
+            let target_path = target_path.to_str().ok_or("Path is not valid UTF-8")?;
+            let renamed = source_path != target_path;
+
+            let current = staged_or_on_disk(ctx, source_path);
+            let target_exists = renamed && staged_or_on_disk(ctx, target_path).is_some();
+
+            let changes = match (&patch.old_path, &patch.new_path, current) {
+                (None, Some(_), Some(_)) => {
+                    reports.push(file_conflict(target, "File to create already exists"));
+                    continue;
+                }
+                (Some(_), _, None) => {
+                    reports.push(file_conflict(source, "File to patch does not exist"));
+                    continue;
+                }
+                _ if target_exists => {
+                    reports.push(file_conflict(target, "Rename target already exists"));
+                    continue;
+                }
+                (_, None, Some(_)) => vec![FileChange::capture(source_path, ChangeKind::Delete, None)],
+                (old, _, current) => {
+                    let original = current.unwrap_or_default();
+                    let (patched, file_reports) = apply_hunks(target, &original, &patch.hunks);
+                    reports.extend(file_reports);
+                    let kind = if old.is_some() && !renamed { ChangeKind::Modify } else { ChangeKind::Create };
+                    let mut changes = vec![FileChange::capture(target_path, kind, Some(patched))];
+                    if renamed {
+                        changes.push(FileChange::capture(source_path, ChangeKind::Delete, None));
+                    }
+                    changes
+                }
+            };
+
+            for change in changes {
+                let shown = if change.path == source_path { source } else { target };
+                preview.push(json!({"path": shown, "kind": change.kind, "content": change.content}));
+                staged.push(change);
+            }
+        }
+
+        let conflicts = reports
+            .iter()
+            .filter(|r| matches!(r.outcome, HunkOutcome::Conflict { .. }))
+            .count();
+        let drifted = reports
+            .iter()
+            .filter(|r| matches!(r.outcome, HunkOutcome::AppliedWithDrift { .. }))
+            .count();
+
+        // Any conflict keeps the whole patch from being staged, so it counts as a failure
+        let status = if conflicts > 0 {
+            ToolStatus::Failed
+        } else if drifted > 0 {
+            ToolStatus::Warning
+        } else {
+            ToolStatus::Success
+        };
+
+        // A patch with conflicts is never staged, so the project never holds half a patch
+        let apply = !dry_run && conflicts == 0;
+        if apply {
+            for change in staged {
+                ctx.changes.record(change);
+            }
+        }
+
+        let message = if dry_run {
+            "Patch previewed, nothing staged"
+        } else if !apply {
+            "Patch has conflicts, nothing staged"
+        } else if ctx.mode.applies_effects() {
+            "Patch staged, applied when the task succeeds"
+        } else {
+            "Patch recorded, not applied"
+        };
+
+        Ok(ToolReturn {
+            result: json!({
+                "message": message,
+                "staged": apply,
+                "dry_run": dry_run,
+                "mode": ctx.mode,
+                "hunks": reports,
+                "files": preview,
+            }),
+            status,
+            trace: Some(vec![format!(
+                "Patched {} file(s): {} conflict(s), {} hunk(s) with drift{}",
+                patches.len(),
+                conflicts,
+                drifted,
+                if dry_run { " (dry run)" } else { "" }
+            )]),
+        })
+    }
+}
+
+/// Content of `path` as the task sees it: its own staged change, else the file on disk
+fn staged_or_on_disk(ctx: &ToolContext, path: &str) -> Option<String> {
+    match ctx.changes.staged_content(path) {
+        Some(staged) => staged,
+        None => fs::read_to_string(path).ok(),
+    }
+}
+
+fn file_conflict(file: &str, reason: &str) -> HunkReport {
+    HunkReport {
+        file: file.to_string(),
+        hunk: 0,
+        old_start: 0,
+        outcome: HunkOutcome::Conflict { reason: reason.into() },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestrator::types::ExecutionMode;
+
+    fn numbered(count: usize) -> String {
+        (1..=count).map(|i| format!("line {}\n", i)).collect()
+    }
+
+    fn single_hunk(diff: &str) -> Hunk {
+        let mut patches = parse_unified_diff(diff).unwrap();
+        patches.remove(0).hunks.remove(0)
+    }
+
+    #[test]
+    fn parses_headers_and_hunks() {
+        let diff = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\t2024-01-01\n+++ b/src/a.rs\n@@ -1,2 +1,2 @@\n keep\n-old\n+new\n--- /dev/null\n+++ b/new.rs\n@@ -0,0 +1 @@\n+created\n";
+        let patches = parse_unified_diff(diff).unwrap();
+
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].old_path.as_deref(), Some("src/a.rs"));
+        assert_eq!(patches[0].new_path.as_deref(), Some("src/a.rs"));
+        assert_eq!(
+            patches[0].hunks[0].lines,
+            vec![
+                HunkLine::Context("keep".into()),
+                HunkLine::Remove("old".into()),
+                HunkLine::Add("new".into()),
+            ]
+        );
+        assert_eq!(patches[1].old_path, None);
+        assert_eq!(patches[1].hunks[0].lines, vec![HunkLine::Add("created".into())]);
+    }
+
+    #[test]
+    fn rejects_malformed_diffs() {
+        assert!(parse_unified_diff("just text").is_err());
+        assert!(parse_unified_diff("--- a/x\n").is_err());
+        assert!(parse_unified_diff("--- a/x\n+++ b/x\n@@ -1,3 +1,3 @@\n a\n").is_err());
+        assert!(parse_unified_diff("--- a/x\n+++ b/x\n@@ nonsense @@\n").is_err());
+    }
+
+    #[test]
+    fn applies_exact_match() {
+        let hunk = single_hunk("--- a/f\n+++ b/f\n@@ -2,3 +2,3 @@\n line 2\n-line 3\n+changed\n line 4\n");
+        let (patched, reports) = apply_hunks("f", &numbered(5), &[hunk]);
+
+        assert_eq!(patched, "line 1\nline 2\nchanged\nline 4\nline 5\n");
+        assert!(matches!(reports[0].outcome, HunkOutcome::Applied));
+    }
+
+    #[test]
+    fn applies_with_offset_when_lines_moved() {
+        let hunk = single_hunk("--- a/f\n+++ b/f\n@@ -2,3 +2,3 @@\n line 5\n-line 6\n+changed\n line 7\n");
+        let (patched, reports) = apply_hunks("f", &numbered(8), &[hunk]);
+
+        assert!(patched.contains("line 5\nchanged\nline 7"));
+        assert!(matches!(reports[0].outcome, HunkOutcome::AppliedWithDrift { offset: 3, fuzz: 0 }));
+    }
+
+    #[test]
+    fn applies_with_fuzz_when_outer_context_differs() {
+        let hunk = single_hunk("--- a/f\n+++ b/f\n@@ -2,4 +2,4 @@\n edited\n line 3\n-line 4\n+changed\n line 5\n");
+        let (patched, reports) = apply_hunks("f", &numbered(6), &[hunk]);
+
+        assert_eq!(patched, "line 1\nline 2\nline 3\nchanged\nline 5\nline 6\n");
+        assert!(matches!(reports[0].outcome, HunkOutcome::AppliedWithDrift { fuzz: 1, .. }));
+    }
+
+    #[test]
+    fn inserts_after_the_header_line() {
+        let hunk = single_hunk("--- a/f\n+++ b/f\n@@ -2,0 +3,1 @@\n+inserted\n");
+        let (patched, reports) = apply_hunks("f", &numbered(3), &[hunk]);
+
+        assert_eq!(patched, "line 1\nline 2\ninserted\nline 3\n");
+        assert!(matches!(reports[0].outcome, HunkOutcome::Applied));
+    }
+
+    #[test]
+    fn inserts_at_the_top_of_a_file() {
+        let hunk = single_hunk("--- a/f\n+++ b/f\n@@ -0,0 +1,1 @@\n+first\n");
+        let (patched, _) = apply_hunks("f", &numbered(2), &[hunk]);
+        assert_eq!(patched, "first\nline 1\nline 2\n");
+    }
+
+    #[test]
+    fn deletes_lines() {
+        let hunk = single_hunk("--- a/f\n+++ b/f\n@@ -2,2 +1,0 @@\n-line 2\n-line 3\n");
+        let (patched, reports) = apply_hunks("f", &numbered(4), &[hunk]);
+
+        assert_eq!(patched, "line 1\nline 4\n");
+        assert!(matches!(reports[0].outcome, HunkOutcome::Applied));
+    }
+
+    #[test]
+    fn later_hunks_account_for_earlier_shifts() {
+        let patch = "--- a/f\n+++ b/f\n@@ -1,1 +1,2 @@\n line 1\n+extra\n@@ -4,1 +5,1 @@\n-line 4\n+four\n";
+        let hunks = parse_unified_diff(patch).unwrap().remove(0).hunks;
+        let (patched, reports) = apply_hunks("f", &numbered(5), &hunks);
+
+        assert_eq!(patched, "line 1\nextra\nline 2\nline 3\nfour\nline 5\n");
+        assert!(reports.iter().all(|r| matches!(r.outcome, HunkOutcome::Applied)));
+    }
+
+    #[test]
+    fn reports_conflicts_and_leaves_text_unchanged() {
+        let hunk = single_hunk("--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n missing\n-lines\n+new\n");
+        let original = numbered(3);
+        let (patched, reports) = apply_hunks("f", &original, &[hunk]);
+
+        assert_eq!(patched, original);
+        assert!(matches!(reports[0].outcome, HunkOutcome::Conflict { .. }));
+    }
+
+    #[tokio::test]
+    async fn rename_patch_moves_the_patched_file() {
+        let dir = std::env::temp_dir().join(format!("winter-patch-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("x.txt"), numbered(3)).unwrap();
+        let jail = crate::tools::path_jail::PathJail::new(&dir).unwrap();
+        let ctx = ToolContext::new(ExecutionMode::Simulate, Some(std::sync::Arc::new(jail)));
+
+        let diff = "--- a/x.txt\n+++ b/y.txt\n@@ -2,1 +2,1 @@\n-line 2\n+two\n";
+        let output = PatchTool.run(json!({ "patch": diff }), &ctx).await.unwrap();
+
+        assert!(output.status.is_success());
+        assert_eq!(output.result["message"], "Patch recorded, not applied");
+        let changes = ctx.changes.changes();
+        assert_eq!(changes.len(), 2);
+        assert!(changes[0].path.ends_with("y.txt"));
+        assert_eq!(changes[0].kind, ChangeKind::Create);
+        assert_eq!(changes[0].content.as_deref(), Some("line 1\ntwo\nline 3\n"));
+        assert!(changes[1].path.ends_with("x.txt"));
+        assert_eq!(changes[1].kind, ChangeKind::Delete);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn locate_prefers_the_nearest_match() {
+        let lines: Vec<String> = ["a", "x", "b", "c", "x", "d"].iter().map(|s| s.to_string()).collect();
+        let hunk = Hunk {
+            old_start: 5,
+            new_start: 5,
+            lines: vec![HunkLine::Remove("x".into())],
+        };
+        assert_eq!(locate_hunk(&lines, &hunk, 3), Some((4, 0)));
+        assert_eq!(locate_hunk(&lines, &hunk, 0), Some((1, 0)));
+    }
+}
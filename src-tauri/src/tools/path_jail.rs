@@ -0,0 +1,297 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Per-project deny list, one entry per line. `#` starts a comment.
+pub const DENY_FILE: &str = ".winterdeny";
+/// `.winter` holds Winter's own memory, index and timeline for the project
+const DEFAULT_DENY: [&str; 3] = [".git", ".env*", ".winter"];
+/// Symlinks followed while resolving one path, as in the kernel's ELOOP limit
+const MAX_SYMLINK_HOPS: usize = 40;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", content = "detail")]
+pub enum JailError {
+    /// The project root does not exist or cannot be resolved
+    InvalidRoot(String),
+    /// The path resolves outside the project, via `..`, an absolute path or a symlink
+    OutsideRoot(String),
+    /// The path is inside the project but matches the deny list
+    Denied(String),
+    Io(String),
+}
+
+impl fmt::Display for JailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JailError::InvalidRoot(root) => write!(f, "Invalid project root: {}", root),
+            JailError::OutsideRoot(path) => write!(f, "Path escapes the project: {}", path),
+            JailError::Denied(path) => write!(f, "Access denied by project deny list: {}", path),
+            JailError::Io(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for JailError {}
+
+impl From<std::io::Error> for JailError {
+    fn from(e: std::io::Error) -> Self {
+        JailError::Io(e.to_string())
+    }
+}
+
+/// Confines file access to a project directory.
+/// Every path is normalised and canonicalised before use, so `..` segments, absolute paths
+/// and symlinks cannot reach outside the root, and deny-listed entries are never touched.
+#[derive(Debug, Clone)]
+pub struct PathJail {
+    root: PathBuf,
+    deny: Vec<String>,
+}
+
+impl PathJail {
+    /// Jail rooted at `root`, using the default deny list plus the project's `.winterdeny`
+    pub fn new(root: impl AsRef<Path>) -> Result<Self, JailError> {
+        let root = root.as_ref();
+        let canonical = fs::canonicalize(root)
+            .map_err(|e| JailError::InvalidRoot(format!("{}: {}", root.display(), e)))?;
+        if !canonical.is_dir() {
+            return Err(JailError::InvalidRoot(root.display().to_string()));
+        }
+
+        let mut deny: Vec<String> = DEFAULT_DENY.iter().map(|s| s.to_string()).collect();
+        if let Ok(content) = fs::read_to_string(canonical.join(DENY_FILE)) {
+            deny.extend(
+                content
+                    .lines()
+                    .map(|line| line.trim().trim_end_matches('/'))
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(String::from),
+            );
+        }
+
+        Ok(Self { root: canonical, deny })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolves a project-relative (or absolute, in-project) path to an absolute path inside the jail.
+    /// The target does not have to exist yet.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, JailError> {
+        let requested = Path::new(path);
+        let relative = if requested.is_absolute() {
+            let absolute = normalize(requested).ok_or_else(|| JailError::OutsideRoot(path.into()))?;
+            absolute
+                .strip_prefix(&self.root)
+                .map(|p| p.to_path_buf())
+                .map_err(|_| JailError::OutsideRoot(path.into()))?
+        } else {
+            normalize(requested).ok_or_else(|| JailError::OutsideRoot(path.into()))?
+        };
+
+        if self.is_denied(&relative) {
+            return Err(JailError::Denied(relative.display().to_string()));
+        }
+
+        // Follow symlinks component by component, dangling ones included
+        let resolved = self.resolve_links(&relative)?;
+        if !resolved.starts_with(&self.root) {
+            return Err(JailError::OutsideRoot(path.into()));
+        }
+
+        // The symlink may point at a deny-listed entry elsewhere in the project
+        if let Ok(inside) = resolved.strip_prefix(&self.root) {
+            if self.is_denied(inside) {
+                return Err(JailError::Denied(inside.display().to_string()));
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Walks the project-relative path from the root, replacing every symlink by its target,
+    /// whether or not the target exists. Components that do not exist yet are kept as they are.
+    fn resolve_links(&self, relative: &Path) -> Result<PathBuf, JailError> {
+        let mut resolved = self.root.clone();
+        let mut pending: VecDeque<OsString> = relative.iter().map(|name| name.to_os_string()).collect();
+        let mut hops = 0;
+
+        while let Some(name) = pending.pop_front() {
+            if name == "." {
+                continue;
+            }
+            if name == ".." {
+                resolved.pop();
+                continue;
+            }
+
+            let candidate = resolved.join(&name);
+            match fs::symlink_metadata(&candidate) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    hops += 1;
+                    if hops > MAX_SYMLINK_HOPS {
+                        return Err(JailError::Io(format!("Too many symlinks in {}", relative.display())));
+                    }
+                    let target = fs::read_link(&candidate)?;
+                    if target.has_root() {
+                        resolved = target
+                            .components()
+                            .take_while(|c| matches!(c, Component::Prefix(_) | Component::RootDir))
+                            .collect();
+                    }
+                    let mut rest: VecDeque<OsString> = target
+                        .components()
+                        .filter(|c| !matches!(c, Component::Prefix(_) | Component::RootDir))
+                        .map(|c| c.as_os_str().to_os_string())
+                        .collect();
+                    while let Some(next) = rest.pop_back() {
+                        pending.push_front(next);
+                    }
+                }
+                Ok(_) => resolved = candidate,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => resolved = candidate,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// True if any component of the project-relative path is on the deny list.
+    /// Entries ending in `*` match by prefix, e.g. `.env*`.
+    pub fn is_denied(&self, relative: &Path) -> bool {
+        relative.components().any(|component| {
+            let Component::Normal(name) = component else {
+                return false;
+            };
+            let name = name.to_string_lossy();
+            self.deny.iter().any(|entry| match entry.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == entry.as_str(),
+            })
+        })
+    }
+}
+
+/// Lexically removes `.` and `..`. Returns None if `..` climbs above the start of the path.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    let mut depth = 0usize;
+
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => normalized.push(component.as_os_str()),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if depth == 0 {
+                    return None;
+                }
+                normalized.pop();
+                depth -= 1;
+            }
+            Component::Normal(name) => {
+                normalized.push(name);
+                depth += 1;
+            }
+        }
+    }
+
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh directory under the system temp dir, removed on drop
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let path = std::env::temp_dir().join(format!("winter-jail-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&path).unwrap();
+            Self(fs::canonicalize(path).unwrap())
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn denies_env_variants_by_default() {
+        let project = TempDir::new();
+        let jail = PathJail::new(&project.0).unwrap();
+
+        for name in [".env", ".env.local", ".env.production", "config/.env.test"] {
+            assert!(
+                matches!(jail.resolve(name), Err(JailError::Denied(_))),
+                "{} should be denied",
+                name
+            );
+        }
+        assert!(jail.resolve("src/environment.rs").is_ok());
+    }
+
+    #[test]
+    fn denies_winter_state_by_default() {
+        let project = TempDir::new();
+        let jail = PathJail::new(&project.0).unwrap();
+
+        assert!(matches!(jail.resolve(".winter/project_memory.json"), Err(JailError::Denied(_))));
+        assert!(jail.resolve("winter/notes.md").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_dangling_symlink_pointing_outside() {
+        let project = TempDir::new();
+        let outside = TempDir::new();
+        std::os::unix::fs::symlink(outside.0.join("created.txt"), project.0.join("link")).unwrap();
+        let jail = PathJail::new(&project.0).unwrap();
+
+        assert!(matches!(jail.resolve("link"), Err(JailError::OutsideRoot(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_relative_symlink_climbing_out() {
+        let project = TempDir::new();
+        fs::create_dir_all(project.0.join("src")).unwrap();
+        std::os::unix::fs::symlink("../../elsewhere", project.0.join("src/link")).unwrap();
+        let jail = PathJail::new(&project.0).unwrap();
+
+        assert!(matches!(jail.resolve("src/link/file.txt"), Err(JailError::OutsideRoot(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinks_that_stay_inside() {
+        let project = TempDir::new();
+        fs::create_dir_all(project.0.join("real")).unwrap();
+        std::os::unix::fs::symlink("real", project.0.join("alias")).unwrap();
+        std::os::unix::fs::symlink("real/new.txt", project.0.join("dangling")).unwrap();
+        let jail = PathJail::new(&project.0).unwrap();
+
+        assert_eq!(jail.resolve("alias/a.txt").unwrap(), project.0.join("real/a.txt"));
+        assert_eq!(jail.resolve("dangling").unwrap(), project.0.join("real/new.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_to_denied_entry() {
+        let project = TempDir::new();
+        fs::write(project.0.join(".env.local"), "SECRET=1").unwrap();
+        std::os::unix::fs::symlink(".env.local", project.0.join("settings")).unwrap();
+        let jail = PathJail::new(&project.0).unwrap();
+
+        assert!(matches!(jail.resolve("settings"), Err(JailError::Denied(_))));
+    }
+}
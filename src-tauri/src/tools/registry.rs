@@ -1,24 +1,144 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::Arc;
+use serde::Serialize;
 use super::tool::Tool;
+use crate::orchestrator::types::now_timestamp;
 
 /// Provides capability for composable utilities eg ShellTol, GitTool, SearchTool
 pub struct ToolRegistry{
-    tools: HashMap<String, Box<dyn Tool>>,
+    tools: HashMap<String, Arc<dyn Tool>>,
+    /// Set on the view handed to one agent's task; None for the full registry
+    scope: Option<ToolScope>,
+}
+
+struct ToolScope {
+    agent_id: String,
+    task_id: String,
+    allowed: HashSet<String>,
+}
+
+/// A tool lookup an agent was not permitted to make
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyViolation {
+    pub agent_id: String,
+    pub task_id: String,
+    pub tool: String,
+    pub timestamp: u64,
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ToolRegistry {
     pub fn new()-> Self{
         Self {
-            tools: HashMap::new()
+            tools: HashMap::new(),
+            scope: None,
         }
     }
     pub fn register_tool(&mut self, tool: Box<dyn Tool>){
-        self.tools.insert(tool.name().to_string(), tool);
+        self.tools.insert(tool.name().to_string(), Arc::from(tool));
     }
-    pub fn get(&self, name: &str)-> Option<&Box<dyn Tool>>{
-        self.tools.get(name)
+    /// Looks up a tool. In an agent's view, a registered tool the agent is not allowed
+    /// to use is not returned and the attempt is logged as a policy violation.
+    pub fn get(&self, name: &str)-> Option<&Arc<dyn Tool>>{
+        let tool = self.tools.get(name)?;
+        match &self.scope {
+            Some(scope) if !scope.allowed.contains(name) => {
+                log_policy_violation(PolicyViolation {
+                    agent_id: scope.agent_id.clone(),
+                    task_id: scope.task_id.clone(),
+                    tool: name.to_string(),
+                    timestamp: now_timestamp(),
+                });
+                None
+            }
+            _ => Some(tool),
+        }
     }
     pub fn all(&self) -> Vec<String>{
-        self.tools.keys().cloned().collect()
+        self.tools
+            .keys()
+            .filter(|name| self.scope.as_ref().is_none_or(|scope| scope.allowed.contains(*name)))
+            .cloned()
+            .collect()
+    }
+    /// View for one task of an agent exposing only the tools in `allowed`.
+    /// Views share the tool instances. Scoping a view again can only narrow it: the result
+    /// exposes the tools allowed by both scopes.
+    pub fn scoped(&self, agent_id: &str, task_id: &str, allowed: &[String]) -> ToolRegistry {
+        let allowed = allowed
+            .iter()
+            .filter(|name| self.scope.as_ref().is_none_or(|scope| scope.allowed.contains(*name)))
+            .cloned()
+            .collect();
+        ToolRegistry {
+            tools: self.tools.clone(),
+            scope: Some(ToolScope {
+                agent_id: agent_id.to_string(),
+                task_id: task_id.to_string(),
+                allowed,
+            }),
+        }
+    }
+}
+
+/// Appends to `WinterData/logs/policy_violations.jsonl`
+fn log_policy_violation(violation: PolicyViolation) {
+    eprintln!(
+        "[policy] Agent {} tried to use tool {} without permission (task {})",
+        violation.agent_id, violation.tool, violation.task_id
+    );
+
+    let folder = dirs::home_dir().expect("No home dir").join("WinterData/logs");
+    let write = fs::create_dir_all(&folder).and_then(|_| {
+        let mut line = serde_json::to_string(&violation)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(folder.join("policy_violations.jsonl"))?
+            .write_all(line.as_bytes())
+    });
+    if let Err(e) = write {
+        eprintln!("[warn] Failed to log policy violation: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::echo_tool::EchoTool;
+    use crate::tools::file_tool::FileTool;
+
+    fn names(registry: &ToolRegistry) -> Vec<String> {
+        let mut names = registry.all();
+        names.sort();
+        names
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn a_scoped_view_cannot_be_widened() {
+        let mut registry = ToolRegistry::new();
+        registry.register_tool(Box::new(EchoTool));
+        registry.register_tool(Box::new(FileTool));
+
+        let agent_view = registry.scoped("agent", "task-1", &["FileTool".to_string()]);
+        assert_eq!(names(&agent_view), vec!["FileTool".to_string()]);
+
+        let rescoped = agent_view.scoped(
+            "agent",
+            "task-2",
+            &["FileTool".to_string(), "echo".to_string()],
+        );
+        assert_eq!(names(&rescoped), vec!["FileTool".to_string()]);
+
+        let full = registry.scoped("other", "task-3", &["echo".to_string()]);
+        assert_eq!(names(&full), vec!["echo".to_string()]);
+    }
+}
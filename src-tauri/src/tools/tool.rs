@@ -1,13 +1,58 @@
 use async_trait::async_trait;
 use serde_json::Value;
-use serde::{Serialize,Deserialize};
+use crate::orchestrator::protocol::ToolReturn;
+use crate::orchestrator::types::ExecutionMode;
+use crate::tools::change_set::ChangeSet;
+use crate::tools::path_jail::PathJail;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
+/// Per-invocation settings handed to a tool by the calling agent
+#[derive(Debug, Clone)]
+pub struct ToolContext {
+    /// Side-effecting tools only apply effects in `ExecutionMode::Execute`;
+    /// otherwise they record what they would have done into `changes`.
+    pub mode: ExecutionMode,
+    pub changes: ChangeSet,
+    /// Jail of the open project. File-touching tools must resolve every path through it.
+    pub sandbox: Option<Arc<PathJail>>,
+    /// Cancellation of the calling task; long-running tools should stop when it fires
+    pub cancel: CancellationToken,
+}
+
+impl ToolContext {
+    pub fn new(mode: ExecutionMode, sandbox: Option<Arc<PathJail>>) -> Self {
+        Self {
+            mode,
+            changes: ChangeSet::new(),
+            sandbox,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Fails if the calling task has been cancelled
+    pub fn ensure_active(&self) -> Result<(), String> {
+        if self.cancel.is_cancelled() {
+            return Err("Task was cancelled".into());
+        }
+        Ok(())
+    }
+
+    /// Resolves a path through the project jail; fails when no project is open
+    pub fn resolve_path(&self, path: &str) -> Result<PathBuf, String> {
+        let sandbox = self
+            .sandbox
+            .as_ref()
+            .ok_or("No project is open; file access is disabled")?;
+        sandbox.resolve(path).map_err(|e| e.to_string())
+    }
+}
 
 /// Tools take structured JSON input and return structured JSON output.
 #[async_trait]
 pub trait Tool: Send + Sync{
     fn name(&self) -> &'static str;
     fn description(&self) -> &'static str;
-    async fn run(&self, input: Value) -> ToolReturn;
+    async fn run(&self, input: Value, ctx: &ToolContext) -> Result<ToolReturn, String>;
 }
-